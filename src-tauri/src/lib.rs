@@ -1,5 +1,5 @@
 mod modules;
-use modules::commands::{budget::*, expense::*, security::*, greet};
+use modules::commands::{account::*, alert::*, analytics::*, attachment::*, budget::*, category::*, debt::*, expense::*, goal::*, history::*, merchant::*, reconcile::*, reminder::*, report::*, search::*, security::*, split::*, tag::*, transfer::*, trash::*, greet};
 use modules::database::{init_state, DbState};
 use tauri::Manager;
 
@@ -7,9 +7,11 @@ use tauri::Manager;
 pub fn run() {
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             let state = init_state(&app.handle()).map_err(|e| Box::<dyn std::error::Error>::from(e.to_string()))?;
             app.manage::<DbState>(state);
+            start_reminder_scheduler(app.handle().clone());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -17,26 +19,125 @@ pub fn run() {
             init_database,
             run_migration,
             create_monthly_budget,
+            clone_monthly_budget,
             delete_monthly_budget,
             finish_monthly_budget,
             unfinish_monthly_budget,
             get_budget_change_history,
+            undo_change,
+            redo_change,
+            query_change_history,
             update_budget_title,
             list_monthly_budgets,
             list_monthly_budgets_sorted,
             // Category Grid System
             get_budget_categories_with_stats,
+            get_budget_allocation_summary,
             get_category_ledger,
             add_category_entry,
             update_category_entry,
             soft_delete_category_entry,
+            split_category_entry,
+            unsplit_category_entry,
+            get_entry_splits,
+            transfer_between_categories,
+            search_entries,
+            rebuild_search_index,
+            // Tags
+            add_entry_tags,
+            remove_entry_tags,
+            search_tags,
+            get_tag_report,
+            // Analytics
+            get_monthly_trends,
+            get_budget_forecast,
+            get_period_report,
+            export_period_report,
+            // Merchants
+            list_merchants,
+            create_merchant,
+            rename_merchant,
+            delete_merchant,
+            add_merchant_aliases,
+            remove_merchant_alias,
+            suggest_merchant_aliases,
+            get_merchant_report,
+            // Savings goals
+            list_savings_goals,
+            get_savings_goal,
+            create_savings_goal,
+            update_savings_goal,
+            delete_savings_goal,
+            // Debts
+            list_debts,
+            create_debt,
+            update_debt,
+            delete_debt,
+            set_entry_debt,
+            plan_debt_payoff,
+            // Accounts and net worth
+            list_accounts,
+            create_account,
+            update_account,
+            delete_account,
+            record_account_balance,
+            list_account_balances,
+            delete_account_balance,
+            set_entry_account,
+            get_net_worth,
+            // Reconciliation
+            start_reconciliation,
+            get_reconciliation,
+            list_reconciliations,
+            set_entries_cleared,
+            finish_reconciliation,
+            cancel_reconciliation,
+            unlock_reconciled_entry,
+            // Budget alerts
+            list_alert_thresholds,
+            create_alert_threshold,
+            update_alert_threshold,
+            delete_alert_threshold,
+            list_budget_alerts,
+            acknowledge_budget_alerts,
+            check_budget_alerts,
+            // Reminders
+            list_reminders,
+            create_reminder,
+            update_reminder,
+            delete_reminder,
+            snooze_reminder,
+            dismiss_reminder,
+            // Attachments
+            add_attachment,
+            list_attachments,
+            get_attachment,
+            delete_attachment,
             set_category_allocated_amount,
+            set_category_allocation_rule,
+            update_budget_income,
             add_budget_category,
+            set_category_type,
+            delete_budget_category,
+            // Trash
+            get_budget_trash,
+            list_trashed_budgets,
+            restore_category_entry,
+            restore_budget_category,
+            restore_monthly_budget,
+            purge_trash,
+            move_budget_category,
             // Global Categories
             get_global_categories,
             create_global_category,
             update_global_category,
             delete_global_category,
+            move_global_category,
+            // Category merge and split
+            merge_global_categories,
+            split_budget_category,
+            get_category_operations,
+            revert_category_operation,
             // Budget Templates
             get_budget_templates,
             get_budget_template_with_categories,
@@ -51,6 +152,7 @@ pub fn run() {
             add_expense,
             list_expenses,
             verify_master_password,
+            verify_history_chain,
         ]);
 
     app.run(tauri::generate_context!())
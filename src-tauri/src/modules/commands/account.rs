@@ -0,0 +1,500 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::macros::format_description;
+use time::{Date, Month};
+
+use crate::modules::commands::analytics::parse_month;
+use crate::modules::commands::budget::log_budget_change;
+use crate::modules::commands::history::{attach_row_snapshots, row_snapshot};
+use crate::modules::commands::reconcile::check_not_reconciled;
+use crate::modules::commands::search::check_date;
+use crate::modules::database::DbState;
+
+pub const ACCOUNT_TYPES: [&str; 6] = ["checking", "savings", "cash", "credit_card", "investment", "loan"];
+
+// Account types whose balance is money owed rather than money held
+const LIABILITY_TYPES: [&str; 2] = ["credit_card", "loan"];
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountArgs {
+    pub name: String,
+    pub account_type: String,
+    #[serde(default)]
+    pub opening_balance: f64, // for credit cards and loans, the amount owed
+    pub opening_date: String, // "YYYY-MM-DD"
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub closed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub account_id: i64,
+    pub name: String,
+    pub account_type: String,
+    pub is_liability: bool,
+    pub opening_balance: f64,
+    pub opening_date: String,
+    pub notes: Option<String>,
+    pub closed_at: Option<String>,
+    pub current_balance: f64,
+    pub last_snapshot_date: Option<String>,
+    pub entries_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBalanceSnapshot {
+    pub snapshot_id: i64,
+    pub account_id: i64,
+    pub balance_date: String,
+    pub balance: f64,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountMonthBalance {
+    pub account_id: i64,
+    pub balance: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetWorthMonth {
+    pub month: String, // "YYYY-MM"
+    pub as_of: String, // the month's last day
+    pub assets: f64,
+    pub liabilities: f64,
+    pub net_worth: f64,
+    pub accounts: Vec<AccountMonthBalance>,
+}
+
+pub(crate) fn is_liability(account_type: &str) -> bool {
+    LIABILITY_TYPES.contains(&account_type)
+}
+
+/// Signed amount by which an entry aliased `e` moves the money on its account. Transfer sides
+/// move it like income and spending do; adjustments never sit on an account.
+pub(crate) const ACCOUNT_FLOW_SQL: &str = "CASE e.entry_type
+    WHEN 'income' THEN e.amount WHEN 'expense' THEN -e.amount
+    WHEN 'transfer_in' THEN e.amount WHEN 'transfer_out' THEN -e.amount
+    ELSE 0 END";
+
+/// Fails for entry types that cannot be booked to an account.
+pub(crate) fn check_account_entry_type(entry_type: &str) -> Result<(), String> {
+    if entry_type == "adjustment" {
+        return Err("Adjustments only correct category totals and cannot be booked to an account".to_string());
+    }
+    Ok(())
+}
+
+/// Fails unless the account exists and is still open, so entries can be booked to it.
+pub(crate) fn check_open_account(conn: &Connection, account_id: i64) -> Result<(), String> {
+    let closed_at: Option<String> = conn.query_row(
+        "SELECT closed_at FROM accounts WHERE account_id = ?1",
+        [account_id],
+        |row| row.get(0)
+    ).map_err(|_| format!("Account {} not found", account_id))?;
+
+    match closed_at {
+        Some(_) => Err(format!("Account {} is closed", account_id)),
+        None => Ok(()),
+    }
+}
+
+/// Balance of the account at the end of `date`: the latest snapshot up to that date (or the
+/// opening balance) moved by the account's entries after it. None before the account opened.
+/// For credit cards and loans the balance is the amount owed, so spending raises it.
+pub(crate) fn account_balance_at(conn: &Connection, account_id: i64, date: &str) -> Result<Option<f64>, String> {
+    let (account_type, opening_balance, opening_date): (String, f64, String) = conn.query_row(
+        "SELECT account_type, opening_balance, opening_date FROM accounts WHERE account_id = ?1",
+        [account_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|_| format!("Account {} not found", account_id))?;
+
+    if date < opening_date.as_str() {
+        return Ok(None);
+    }
+
+    let snapshot: Option<(String, f64)> = conn.query_row(
+        "SELECT balance_date, balance FROM account_balances
+         WHERE account_id = ?1 AND balance_date <= ?2
+         ORDER BY balance_date DESC
+         LIMIT 1",
+        rusqlite::params![account_id, date],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).optional().map_err(|e| e.to_string())?;
+
+    // Entries on the opening date itself already moved the opening balance
+    let (anchor_date, anchor_balance, inclusive) = match snapshot {
+        Some((snapshot_date, balance)) if snapshot_date >= opening_date => (snapshot_date, balance, false),
+        _ => (opening_date, opening_balance, true),
+    };
+
+    let flow: f64 = conn.query_row(
+        &format!(
+            "SELECT COALESCE(SUM({}), 0)
+             FROM expenses e
+             JOIN budget_categories c ON c.category_id = e.category_id
+             JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+             WHERE e.account_id = ?1 AND e.deleted_at IS NULL AND c.deleted_at IS NULL AND b.deleted_at IS NULL
+               AND e.date {} ?2 AND e.date <= ?3",
+            ACCOUNT_FLOW_SQL,
+            if inclusive { ">=" } else { ">" }
+        ),
+        rusqlite::params![account_id, anchor_date, date],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    let balance = if is_liability(&account_type) { anchor_balance - flow } else { anchor_balance + flow };
+    Ok(Some(balance))
+}
+
+fn today(conn: &Connection) -> Result<String, String> {
+    conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+fn load_account(conn: &Connection, account_id: i64, today: &str) -> Result<Account, String> {
+    let mut account = conn.query_row(
+        "SELECT a.account_id, a.name, a.account_type, a.opening_balance, a.opening_date, a.notes, a.closed_at,
+                (SELECT MAX(balance_date) FROM account_balances s WHERE s.account_id = a.account_id),
+                (SELECT COUNT(*) FROM expenses e WHERE e.account_id = a.account_id AND e.deleted_at IS NULL)
+         FROM accounts a WHERE a.account_id = ?1",
+        [account_id],
+        |row| {
+            let account_type: String = row.get(2)?;
+            Ok(Account {
+                account_id: row.get(0)?,
+                name: row.get(1)?,
+                is_liability: is_liability(&account_type),
+                account_type,
+                opening_balance: row.get(3)?,
+                opening_date: row.get(4)?,
+                notes: row.get(5)?,
+                closed_at: row.get(6)?,
+                current_balance: 0.0,
+                last_snapshot_date: row.get(7)?,
+                entries_count: row.get(8)?,
+            })
+        }
+    ).map_err(|_| format!("Account {} not found", account_id))?;
+
+    // Entries dated in the future are not on the account yet
+    account.current_balance = account_balance_at(conn, account_id, today)?.unwrap_or(account.opening_balance);
+    Ok(account)
+}
+
+fn validate_account(args: &AccountArgs) -> Result<String, String> {
+    let name = args.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Account name cannot be empty".to_string());
+    }
+    if !ACCOUNT_TYPES.contains(&args.account_type.as_str()) {
+        return Err(format!("Unknown account type '{}', expected one of {}", args.account_type, ACCOUNT_TYPES.join(", ")));
+    }
+    check_date(&args.opening_date)?;
+    Ok(name)
+}
+
+#[tauri::command]
+pub fn list_accounts(include_closed: Option<bool>, db: State<DbState>) -> Result<Vec<Account>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let today = today(&conn)?;
+
+    let ids: Vec<i64> = {
+        let mut stmt = conn.prepare(
+            "SELECT account_id FROM accounts
+             WHERE ?1 OR closed_at IS NULL
+             ORDER BY closed_at IS NOT NULL, name COLLATE NOCASE ASC"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([include_closed.unwrap_or(false)], |row| row.get(0)).map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| e.to_string())?);
+        }
+        ids
+    };
+
+    ids.into_iter().map(|id| load_account(&conn, id, &today)).collect()
+}
+
+#[tauri::command]
+pub fn create_account(args: AccountArgs, db: State<DbState>) -> Result<Account, String> {
+    println!("=== CREATE_ACCOUNT COMMAND CALLED ===");
+    println!("Account: {:?}", args);
+
+    let name = validate_account(&args)?;
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO accounts (name, account_type, opening_balance, opening_date, notes, closed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, CASE WHEN ?6 THEN datetime('now') END)",
+        rusqlite::params![name, args.account_type, args.opening_balance, args.opening_date, args.notes, args.closed],
+    ).map_err(|e| format!("Failed to create account: {}", e))?;
+
+    let account_id = conn.last_insert_rowid();
+    println!("Created account {}", account_id);
+    load_account(&conn, account_id, &today(&conn)?)
+}
+
+#[tauri::command]
+pub fn update_account(account_id: i64, args: AccountArgs, db: State<DbState>) -> Result<Account, String> {
+    println!("=== UPDATE_ACCOUNT COMMAND CALLED ===");
+    println!("Account {}: {:?}", account_id, args);
+
+    let name = validate_account(&args)?;
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    // Closing keeps the original closing time; reopening clears it
+    let rows_affected = conn.execute(
+        "UPDATE accounts
+         SET name = ?1, account_type = ?2, opening_balance = ?3, opening_date = ?4, notes = ?5,
+             closed_at = CASE WHEN ?6 THEN COALESCE(closed_at, datetime('now')) END,
+             updated_at = datetime('now')
+         WHERE account_id = ?7",
+        rusqlite::params![name, args.account_type, args.opening_balance, args.opening_date, args.notes, args.closed, account_id],
+    ).map_err(|e| format!("Failed to update account: {}", e))?;
+
+    if rows_affected == 0 {
+        return Err(format!("Account {} not found", account_id));
+    }
+    load_account(&conn, account_id, &today(&conn)?)
+}
+
+#[tauri::command]
+pub fn delete_account(account_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== DELETE_ACCOUNT COMMAND CALLED ===");
+    println!("Deleting account ID: {}", account_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let entries: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM expenses WHERE account_id = ?1",
+        [account_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if entries > 0 {
+        return Err(format!("Account {} still has {} entries; close it instead", account_id, entries));
+    }
+
+    let rows_affected = conn.execute("DELETE FROM accounts WHERE account_id = ?1", [account_id])
+        .map_err(|e| e.to_string())?;
+    if rows_affected == 0 {
+        return Err(format!("Account {} not found", account_id));
+    }
+    Ok(())
+}
+
+/// Records the balance read off a statement or app for the end of a day, replacing any
+/// earlier reading for the same day.
+#[tauri::command]
+pub fn record_account_balance(
+    account_id: i64,
+    balance_date: String,
+    balance: f64,
+    note: Option<String>,
+    db: State<DbState>,
+) -> Result<AccountBalanceSnapshot, String> {
+    println!("=== RECORD_ACCOUNT_BALANCE COMMAND CALLED ===");
+    println!("Account {} balance on {}: {:.2}", account_id, balance_date, balance);
+
+    check_date(&balance_date)?;
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let opening_date: String = conn.query_row(
+        "SELECT opening_date FROM accounts WHERE account_id = ?1",
+        [account_id],
+        |row| row.get(0)
+    ).map_err(|_| format!("Account {} not found", account_id))?;
+    if balance_date < opening_date {
+        return Err(format!("The account was opened on {}", opening_date));
+    }
+
+    conn.execute(
+        "INSERT INTO account_balances (account_id, balance_date, balance, note) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(account_id, balance_date) DO UPDATE SET balance = excluded.balance, note = excluded.note",
+        rusqlite::params![account_id, balance_date, balance, note],
+    ).map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT snapshot_id, account_id, balance_date, balance, note FROM account_balances
+         WHERE account_id = ?1 AND balance_date = ?2",
+        rusqlite::params![account_id, balance_date],
+        |row| Ok(AccountBalanceSnapshot {
+            snapshot_id: row.get(0)?,
+            account_id: row.get(1)?,
+            balance_date: row.get(2)?,
+            balance: row.get(3)?,
+            note: row.get(4)?,
+        })
+    ).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_account_balances(account_id: i64, db: State<DbState>) -> Result<Vec<AccountBalanceSnapshot>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT snapshot_id, account_id, balance_date, balance, note FROM account_balances
+         WHERE account_id = ?1
+         ORDER BY balance_date DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([account_id], |row| {
+        Ok(AccountBalanceSnapshot {
+            snapshot_id: row.get(0)?,
+            account_id: row.get(1)?,
+            balance_date: row.get(2)?,
+            balance: row.get(3)?,
+            note: row.get(4)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn delete_account_balance(snapshot_id: i64, db: State<DbState>) -> Result<(), String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let rows_affected = conn.execute("DELETE FROM account_balances WHERE snapshot_id = ?1", [snapshot_id])
+        .map_err(|e| e.to_string())?;
+    if rows_affected == 0 {
+        return Err(format!("Balance snapshot {} not found", snapshot_id));
+    }
+    Ok(())
+}
+
+/// Moves entries onto an account, or off any account when none is given.
+#[tauri::command]
+pub fn set_entry_account(entry_ids: Vec<i64>, account_id: Option<i64>, db: State<DbState>) -> Result<usize, String> {
+    println!("=== SET_ENTRY_ACCOUNT COMMAND CALLED ===");
+    println!("Moving entries {:?} to account {:?}", entry_ids, account_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    if let Some(account_id) = account_id {
+        check_open_account(&tx, account_id)?;
+    }
+
+    let mut updated = 0;
+    for entry_id in &entry_ids {
+        check_not_reconciled(&tx, *entry_id)?;
+
+        let entry: Option<(i64, String, String, Option<i64>)> = tx.query_row(
+            "SELECT c.budget_id, e.description, e.entry_type, e.account_id FROM expenses e
+             JOIN budget_categories c ON c.category_id = e.category_id
+             WHERE e.expense_id = ?1 AND e.deleted_at IS NULL",
+            [entry_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        ).optional().map_err(|e| e.to_string())?;
+        let (budget_id, what, entry_type, current_account) = match entry {
+            Some(entry) => entry,
+            None => continue,
+        };
+        if account_id.is_some() {
+            check_account_entry_type(&entry_type)?;
+        }
+        if current_account == account_id {
+            continue;
+        }
+
+        let before = row_snapshot(&tx, "expenses", *entry_id)?;
+        updated += tx.execute(
+            "UPDATE expenses SET account_id = ?1, cleared_at = NULL WHERE expense_id = ?2",
+            rusqlite::params![account_id, entry_id],
+        ).map_err(|e| e.to_string())?;
+
+        let description = match account_id {
+            Some(account_id) => format!("Moved entry {} ({}) to account {}", entry_id, what, account_id),
+            None => format!("Took entry {} ({}) off its account", entry_id, what),
+        };
+        let change_id = log_budget_change(&tx, budget_id, "entry_account", Some("account_id"),
+                                          current_account.map(|id| id.to_string()).as_deref(),
+                                          account_id.map(|id| id.to_string()).as_deref(), &description)?;
+        attach_row_snapshots(&tx, change_id, "expenses", *entry_id, before)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+/// Net worth at the end of each month in the range: what the accounts hold minus what is owed
+/// on them. Accounts count from the month they were opened until the month they were closed.
+#[tauri::command]
+pub fn get_net_worth(from_month: String, to_month: String, db: State<DbState>) -> Result<Vec<NetWorthMonth>, String> {
+    println!("=== GET_NET_WORTH COMMAND CALLED ===");
+    println!("Net worth from {} to {}", from_month, to_month);
+
+    let from = parse_month(&from_month)?;
+    let to = parse_month(&to_month)?;
+    if from > to {
+        return Err("The start month is after the end month".to_string());
+    }
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let date_format = format_description!("[year]-[month]-[day]");
+
+    let accounts: Vec<(i64, String, Option<String>)> = {
+        let mut stmt = conn.prepare("SELECT account_id, account_type, closed_at FROM accounts ORDER BY account_id ASC")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).map_err(|e| e.to_string())?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    let mut months = Vec::new();
+    for index in from..=to {
+        let year = (index / 12) as i32;
+        let month = Month::try_from((index % 12 + 1) as u8).map_err(|e| e.to_string())?;
+        let last_day = time::util::days_in_year_month(year, month);
+        let as_of = Date::from_calendar_date(year, month, last_day)
+            .map_err(|e| e.to_string())?
+            .format(date_format)
+            .map_err(|e| e.to_string())?;
+        let month_start = format!("{:04}-{:02}-01", year, index % 12 + 1);
+
+        let mut totals = NetWorthMonth {
+            month: format!("{:04}-{:02}", year, index % 12 + 1),
+            as_of: as_of.clone(),
+            assets: 0.0,
+            liabilities: 0.0,
+            net_worth: 0.0,
+            accounts: Vec::new(),
+        };
+
+        for (account_id, account_type, closed_at) in &accounts {
+            if closed_at.as_deref().is_some_and(|closed| closed < month_start.as_str()) {
+                continue;
+            }
+            let Some(balance) = account_balance_at(&conn, *account_id, &as_of)? else {
+                continue;
+            };
+            if is_liability(account_type) {
+                totals.liabilities += balance;
+            } else {
+                totals.assets += balance;
+            }
+            totals.accounts.push(AccountMonthBalance { account_id: *account_id, balance });
+        }
+
+        totals.net_worth = totals.assets - totals.liabilities;
+        months.push(totals);
+    }
+
+    Ok(months)
+}
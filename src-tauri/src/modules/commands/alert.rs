@@ -0,0 +1,404 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::modules::database::DbState;
+use crate::modules::utils::notify::notify;
+
+pub const BUDGET_ALERT_EVENT: &str = "budget-alert";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertThresholdArgs {
+    pub scope: String, // "category" | "budget"
+    #[serde(default)]
+    pub global_category_id: Option<i64>, // category scope only; None applies to every category
+    pub percent: f64,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertThreshold {
+    pub threshold_id: i64,
+    pub scope: String,
+    pub global_category_id: Option<i64>,
+    pub global_category_name: Option<String>,
+    pub percent: f64,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlert {
+    pub alert_id: i64,
+    pub threshold_id: i64,
+    pub budget_id: i64,
+    pub category_id: Option<i64>, // None for budget-wide alerts
+    pub category_name: Option<String>,
+    pub percent: f64,
+    pub used_amount: f64,
+    pub limit_amount: f64,
+    pub message: String,
+    pub fired_at: String,
+    pub acknowledged_at: Option<String>,
+}
+
+const ALERT_SELECT_SQL: &str = "
+    SELECT a.alert_id, a.threshold_id, a.budget_id, a.category_id, c.category_name, a.percent,
+           a.used_amount, a.limit_amount, a.message, a.fired_at, a.acknowledged_at
+    FROM budget_alerts a
+    LEFT JOIN budget_categories c ON c.category_id = a.category_id";
+
+fn alert_from_row(row: &rusqlite::Row) -> rusqlite::Result<BudgetAlert> {
+    Ok(BudgetAlert {
+        alert_id: row.get(0)?,
+        threshold_id: row.get(1)?,
+        budget_id: row.get(2)?,
+        category_id: row.get(3)?,
+        category_name: row.get(4)?,
+        percent: row.get(5)?,
+        used_amount: row.get(6)?,
+        limit_amount: row.get(7)?,
+        message: row.get(8)?,
+        fired_at: row.get(9)?,
+        acknowledged_at: row.get(10)?,
+    })
+}
+
+fn load_threshold(conn: &Connection, threshold_id: i64) -> Result<AlertThreshold, String> {
+    conn.query_row(
+        "SELECT t.threshold_id, t.scope, t.global_category_id, g.name, t.percent, t.enabled, t.created_at
+         FROM alert_thresholds t
+         LEFT JOIN global_categories g ON g.global_category_id = t.global_category_id
+         WHERE t.threshold_id = ?1",
+        [threshold_id],
+        |row| Ok(AlertThreshold {
+            threshold_id: row.get(0)?,
+            scope: row.get(1)?,
+            global_category_id: row.get(2)?,
+            global_category_name: row.get(3)?,
+            percent: row.get(4)?,
+            enabled: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    ).map_err(|_| format!("Alert threshold {} not found", threshold_id))
+}
+
+fn validate_threshold(conn: &Connection, args: &AlertThresholdArgs) -> Result<(), String> {
+    match args.scope.as_str() {
+        "category" => {}
+        "budget" if args.global_category_id.is_none() => {}
+        "budget" => return Err("Budget-wide thresholds cannot be tied to a category".to_string()),
+        other => return Err(format!("Unknown threshold scope '{}'", other)),
+    }
+    if args.percent <= 0.0 {
+        return Err("Threshold percent must be positive".to_string());
+    }
+    if let Some(global_category_id) = args.global_category_id {
+        conn.query_row(
+            "SELECT 1 FROM global_categories WHERE global_category_id = ?1",
+            [global_category_id],
+            |_| Ok(())
+        ).map_err(|_| format!("Global category {} not found", global_category_id))?;
+    }
+    Ok(())
+}
+
+fn budget_label(conn: &Connection, budget_id: i64) -> Result<String, String> {
+    let (name, year, month): (Option<String>, i32, u32) = conn.query_row(
+        "SELECT name, year, month FROM MonthlyBudgets WHERE budget_id = ?1",
+        [budget_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|_| format!("Budget with ID {} not found", budget_id))?;
+
+    Ok(name.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| format!("{}-{:02}", year, month)))
+}
+
+// Stores the crossing of `threshold` (id, percent) unless it already fired for this budget
+// and category; returns the alert only when it is new
+fn record_alert(
+    conn: &Connection,
+    (threshold_id, percent): (i64, f64),
+    budget_id: i64,
+    category_id: Option<i64>,
+    used_amount: f64,
+    limit_amount: f64,
+    message: &str,
+) -> Result<Option<BudgetAlert>, String> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO budget_alerts (threshold_id, budget_id, category_id, percent, used_amount, limit_amount, message)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![threshold_id, budget_id, category_id, percent, used_amount, limit_amount, message],
+    ).map_err(|e| e.to_string())?;
+
+    if inserted == 0 {
+        return Ok(None);
+    }
+    conn.query_row(
+        &format!("{} WHERE a.alert_id = ?1", ALERT_SELECT_SQL),
+        [conn.last_insert_rowid()],
+        alert_from_row
+    ).map(Some).map_err(|e| e.to_string())
+}
+
+/// Checks the budget against the enabled thresholds and stores every threshold crossed for
+/// the first time. Spending is what expense categories paid out net of refunds, measured
+/// against their allocation plus transfers; thresholds set for a global category replace the
+/// ones that apply to every category.
+pub(crate) fn evaluate_budget_alerts(conn: &Connection, budget_id: i64) -> Result<Vec<BudgetAlert>, String> {
+    let label = budget_label(conn, budget_id)?;
+
+    let categories: Vec<(i64, String, Option<i64>, f64, f64)> = {
+        let mut stmt = conn.prepare(
+            "SELECT c.category_id, c.category_name, c.global_category_id,
+                    c.allocated_amount + COALESCE(SUM(CASE l.entry_type
+                        WHEN 'transfer_in' THEN l.amount WHEN 'transfer_out' THEN -l.amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE l.entry_type
+                        WHEN 'expense' THEN l.amount WHEN 'income' THEN -l.amount ELSE 0 END), 0)
+             FROM budget_categories c
+             LEFT JOIN entry_lines l ON l.category_id = c.category_id AND l.deleted_at IS NULL
+             WHERE c.budget_id = ?1 AND c.deleted_at IS NULL AND COALESCE(c.category_type, 'expense') = 'expense'
+             GROUP BY c.category_id, c.category_name, c.global_category_id, c.allocated_amount"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([budget_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        }).map_err(|e| e.to_string())?;
+        let mut categories = Vec::new();
+        for row in rows {
+            categories.push(row.map_err(|e| e.to_string())?);
+        }
+        categories
+    };
+
+    let thresholds: Vec<(i64, String, Option<i64>, f64)> = {
+        let mut stmt = conn.prepare(
+            "SELECT threshold_id, scope, global_category_id, percent FROM alert_thresholds
+             WHERE enabled = 1 ORDER BY percent ASC"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .map_err(|e| e.to_string())?;
+        let mut thresholds = Vec::new();
+        for row in rows {
+            thresholds.push(row.map_err(|e| e.to_string())?);
+        }
+        thresholds
+    };
+
+    let mut fired = Vec::new();
+
+    for (category_id, category_name, global_category_id, limit_amount, used_amount) in &categories {
+        if *limit_amount <= 0.0 || *used_amount <= 0.0 {
+            continue;
+        }
+        let has_own = global_category_id.is_some()
+            && thresholds.iter().any(|t| t.1 == "category" && t.2 == *global_category_id);
+        let used_percent = used_amount / limit_amount * 100.0;
+
+        for (threshold_id, scope, threshold_category, percent) in &thresholds {
+            let applies = scope == "category"
+                && if has_own { threshold_category == global_category_id } else { threshold_category.is_none() };
+            if !applies || used_percent < *percent {
+                continue;
+            }
+            let message = format!(
+                "{} has used {:.0}% of its ${:.2} allocation in {} (${:.2} spent)",
+                category_name, percent, limit_amount, label, used_amount
+            );
+            if let Some(alert) = record_alert(conn, (*threshold_id, *percent), budget_id, Some(*category_id),
+                                              *used_amount, *limit_amount, &message)? {
+                fired.push(alert);
+            }
+        }
+    }
+
+    let total_limit: f64 = categories.iter().map(|c| c.3.max(0.0)).sum();
+    let total_used: f64 = categories.iter().map(|c| c.4).sum();
+    if total_limit > 0.0 && total_used > 0.0 {
+        let used_percent = total_used / total_limit * 100.0;
+        for (threshold_id, scope, _, percent) in &thresholds {
+            if scope != "budget" || used_percent < *percent {
+                continue;
+            }
+            let message = format!(
+                "Budget {} has used {:.0}% of its ${:.2} allocations (${:.2} spent)",
+                label, percent, total_limit, total_used
+            );
+            if let Some(alert) = record_alert(conn, (*threshold_id, *percent), budget_id, None,
+                                              total_used, total_limit, &message)? {
+                fired.push(alert);
+            }
+        }
+    }
+
+    Ok(fired)
+}
+
+/// Evaluates the budget after a ledger change has been committed and tells the user about
+/// every threshold it crossed. Errors are only logged, since the change itself succeeded.
+pub(crate) fn raise_budget_alerts(app: &AppHandle, conn: &Connection, budget_id: i64) {
+    match evaluate_budget_alerts(conn, budget_id) {
+        Ok(alerts) => {
+            for alert in &alerts {
+                println!("Budget alert {}: {}", alert.alert_id, alert.message);
+                notify(app, BUDGET_ALERT_EVENT, alert, "Budget alert", &alert.message);
+            }
+        }
+        Err(e) => println!("Warning: failed to evaluate alerts for budget {}: {}", budget_id, e),
+    }
+}
+
+/// Same as `raise_budget_alerts`, for the budget the entry belongs to.
+pub(crate) fn raise_entry_budget_alerts(app: &AppHandle, conn: &Connection, entry_id: i64) {
+    let budget_id: Option<i64> = conn.query_row(
+        "SELECT c.budget_id FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE e.expense_id = ?1",
+        [entry_id],
+        |row| row.get(0)
+    ).optional().ok().flatten();
+
+    if let Some(budget_id) = budget_id {
+        raise_budget_alerts(app, conn, budget_id);
+    }
+}
+
+#[tauri::command]
+pub fn list_alert_thresholds(db: State<DbState>) -> Result<Vec<AlertThreshold>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let ids: Vec<i64> = {
+        let mut stmt = conn.prepare(
+            "SELECT threshold_id FROM alert_thresholds
+             ORDER BY scope DESC, global_category_id IS NOT NULL, global_category_id, percent"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| e.to_string())?);
+        }
+        ids
+    };
+
+    ids.into_iter().map(|id| load_threshold(&conn, id)).collect()
+}
+
+#[tauri::command]
+pub fn create_alert_threshold(args: AlertThresholdArgs, db: State<DbState>) -> Result<AlertThreshold, String> {
+    println!("=== CREATE_ALERT_THRESHOLD COMMAND CALLED ===");
+    println!("Threshold: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    validate_threshold(&conn, &args)?;
+
+    conn.execute(
+        "INSERT INTO alert_thresholds (scope, global_category_id, percent, enabled) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![args.scope, args.global_category_id, args.percent, args.enabled.unwrap_or(true)],
+    ).map_err(|e| format!("Failed to create alert threshold: {}", e))?;
+
+    load_threshold(&conn, conn.last_insert_rowid())
+}
+
+/// Changing a threshold's percent lets it fire again, so its earlier alerts are dropped.
+#[tauri::command]
+pub fn update_alert_threshold(threshold_id: i64, args: AlertThresholdArgs, db: State<DbState>) -> Result<AlertThreshold, String> {
+    println!("=== UPDATE_ALERT_THRESHOLD COMMAND CALLED ===");
+    println!("Threshold {}: {:?}", threshold_id, args);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    validate_threshold(&tx, &args)?;
+
+    let current = load_threshold(&tx, threshold_id)?;
+    if current.scope != args.scope || current.global_category_id != args.global_category_id
+        || (current.percent - args.percent).abs() > f64::EPSILON {
+        tx.execute("DELETE FROM budget_alerts WHERE threshold_id = ?1", [threshold_id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.execute(
+        "UPDATE alert_thresholds SET scope = ?1, global_category_id = ?2, percent = ?3, enabled = ?4
+         WHERE threshold_id = ?5",
+        rusqlite::params![args.scope, args.global_category_id, args.percent,
+                          args.enabled.unwrap_or(current.enabled), threshold_id],
+    ).map_err(|e| format!("Failed to update alert threshold: {}", e))?;
+
+    let threshold = load_threshold(&tx, threshold_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(threshold)
+}
+
+#[tauri::command]
+pub fn delete_alert_threshold(threshold_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== DELETE_ALERT_THRESHOLD COMMAND CALLED ===");
+    println!("Deleting alert threshold ID: {}", threshold_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let rows_affected = conn.execute("DELETE FROM alert_thresholds WHERE threshold_id = ?1", [threshold_id])
+        .map_err(|e| e.to_string())?;
+    if rows_affected == 0 {
+        return Err(format!("Alert threshold {} not found", threshold_id));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_budget_alerts(
+    budget_id: Option<i64>,
+    include_acknowledged: bool,
+    db: State<DbState>,
+) -> Result<Vec<BudgetAlert>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE (?1 IS NULL OR a.budget_id = ?1) AND (?2 OR a.acknowledged_at IS NULL)
+         ORDER BY a.fired_at DESC, a.alert_id DESC",
+        ALERT_SELECT_SQL
+    )).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(rusqlite::params![budget_id, include_acknowledged], alert_from_row)
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn acknowledge_budget_alerts(alert_ids: Vec<i64>, db: State<DbState>) -> Result<usize, String> {
+    println!("=== ACKNOWLEDGE_BUDGET_ALERTS COMMAND CALLED ===");
+    println!("Acknowledging alerts: {:?}", alert_ids);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut acknowledged = 0;
+    for alert_id in &alert_ids {
+        acknowledged += tx.execute(
+            "UPDATE budget_alerts SET acknowledged_at = datetime('now') WHERE alert_id = ?1 AND acknowledged_at IS NULL",
+            [alert_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(acknowledged)
+}
+
+/// Re-evaluates a budget on demand, e.g. after thresholds were configured.
+#[tauri::command]
+pub fn check_budget_alerts(budget_id: i64, app: AppHandle, db: State<DbState>) -> Result<Vec<BudgetAlert>, String> {
+    println!("=== CHECK_BUDGET_ALERTS COMMAND CALLED ===");
+    println!("Checking alerts for budget ID: {}", budget_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let alerts = evaluate_budget_alerts(&conn, budget_id)?;
+    for alert in &alerts {
+        notify(&app, BUDGET_ALERT_EVENT, alert, "Budget alert", &alert.message);
+    }
+    Ok(alerts)
+}
@@ -0,0 +1,538 @@
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::macros::format_description;
+use time::{Date, Duration, Month};
+
+use crate::modules::database::DbState;
+
+const DEFAULT_ROLLING_MONTHS: u32 = 3;
+const MAX_ROLLING_MONTHS: u32 = 24;
+
+// How many earlier budgets a forecast looks at for a category's usual spending
+const FORECAST_HISTORY_MONTHS: i64 = 6;
+
+// A category in an earlier budget counts as the same one when it is linked to the same global
+// category, or, for unlinked categories, has the same name. Expects ?2 = global id, ?3 = name.
+const SAME_CATEGORY_SQL: &str =
+    "(c.global_category_id = ?2 OR (?2 IS NULL AND c.global_category_id IS NULL AND LOWER(c.category_name) = LOWER(?3)))";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendArgs {
+    pub from_month: String, // "YYYY-MM", inclusive
+    pub to_month: String,   // "YYYY-MM", inclusive
+    #[serde(default)]
+    pub global_category_ids: Option<Vec<i64>>,
+    #[serde(default)]
+    pub rolling_months: Option<u32>, // window of the rolling average, default 3
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendPoint {
+    pub budget_id: i64,
+    pub year: i32,
+    pub month: u32,
+    pub allocated: f64,
+    pub spent: f64,
+    pub change_amount: Option<f64>,  // against the previous budgeted month
+    pub change_percent: Option<f64>, // None when the previous month had no spending
+    pub rolling_average: f64,
+    pub share_of_income: Option<f64>, // None when the month has no income
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryTrend {
+    pub global_category_id: Option<i64>, // None for categories not linked to a global category
+    pub name: String,
+    pub total_allocated: f64,
+    pub total_spent: f64,
+    pub average_spent: f64,
+    pub points: Vec<TrendPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthTotals {
+    pub budget_id: i64,
+    pub year: i32,
+    pub month: u32,
+    pub total_income: f64,
+    pub allocated: f64,
+    pub spent: f64,
+    pub share_of_income: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyTrends {
+    pub months: Vec<MonthTotals>,
+    pub categories: Vec<CategoryTrend>,
+}
+
+/// Parses "YYYY-MM" into a month index (year * 12 + month - 1) that orders and subtracts cleanly.
+pub(crate) fn parse_month(value: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid month '{}', expected YYYY-MM", value);
+    let (year, month) = value.trim().split_once('-').ok_or_else(invalid)?;
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: i64 = month.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || !(1900..=9999).contains(&year) {
+        return Err(invalid());
+    }
+    Ok(year * 12 + month - 1)
+}
+
+fn share(amount: f64, income: f64) -> Option<f64> {
+    if income.abs() < 0.005 {
+        None
+    } else {
+        Some(amount / income * 100.0)
+    }
+}
+
+#[tauri::command]
+pub fn get_monthly_trends(args: TrendArgs, db: State<DbState>) -> Result<MonthlyTrends, String> {
+    println!("=== GET_MONTHLY_TRENDS COMMAND CALLED ===");
+    println!("Trends: {:?}", args);
+
+    let from = parse_month(&args.from_month)?;
+    let to = parse_month(&args.to_month)?;
+    if from > to {
+        return Err("The start month is after the end month".to_string());
+    }
+
+    let window = args.rolling_months.unwrap_or(DEFAULT_ROLLING_MONTHS).clamp(1, MAX_ROLLING_MONTHS);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    // Budgets before the range are read too, so the first months of the range get a
+    // month-over-month change and a full rolling average; they are filtered out at the end.
+    let mut params: Vec<SqlValue> = vec![
+        SqlValue::Integer(from - window as i64),
+        SqlValue::Integer(to),
+        SqlValue::Integer(from),
+    ];
+
+    let mut category_filter = String::new();
+    if let Some(ids) = args.global_category_ids.as_ref().filter(|ids| !ids.is_empty()) {
+        let mut placeholders = Vec::new();
+        for id in ids {
+            params.push(SqlValue::Integer(*id));
+            placeholders.push(format!("?{}", params.len()));
+        }
+        category_filter = format!("AND c.global_category_id IN ({})", placeholders.join(", "));
+    }
+
+    // Spending is what was booked as 'expense'; transfers only move allocation around and
+    // income entries are not netted off. Split entries count towards each part's category.
+    let query = format!(
+        r#"
+        WITH months AS (
+            SELECT budget_id, year, month, total_income, year * 12 + month - 1 AS month_index
+            FROM MonthlyBudgets
+            WHERE deleted_at IS NULL AND year * 12 + month - 1 BETWEEN ?1 AND ?2
+        ),
+        cats AS (
+            SELECT c.category_id, c.budget_id, c.allocated_amount, COALESCE(c.global_category_id, 0) AS gid
+            FROM budget_categories c
+            JOIN months m ON m.budget_id = c.budget_id
+            WHERE c.deleted_at IS NULL {}
+        ),
+        spent AS (
+            SELECT l.category_id, SUM(l.amount) AS amount
+            FROM entry_lines l
+            JOIN cats ON cats.category_id = l.category_id
+            WHERE l.deleted_at IS NULL AND l.entry_type = 'expense'
+            GROUP BY l.category_id
+        ),
+        per_month AS (
+            SELECT cats.budget_id, cats.gid,
+                   SUM(cats.allocated_amount) AS allocated,
+                   SUM(COALESCE(spent.amount, 0)) AS spent
+            FROM cats
+            LEFT JOIN spent ON spent.category_id = cats.category_id
+            GROUP BY cats.budget_id, cats.gid
+        ),
+        grid AS (
+            SELECT m.budget_id, m.year, m.month, m.month_index, m.total_income, g.gid
+            FROM months m
+            CROSS JOIN (SELECT DISTINCT gid FROM cats) g
+        ),
+        series AS (
+            SELECT grid.budget_id, grid.year, grid.month, grid.month_index, grid.total_income, grid.gid,
+                   COALESCE(p.allocated, 0) AS allocated,
+                   COALESCE(p.spent, 0) AS spent,
+                   LAG(COALESCE(p.spent, 0)) OVER (PARTITION BY grid.gid ORDER BY grid.month_index) AS previous_spent,
+                   AVG(COALESCE(p.spent, 0)) OVER (
+                       PARTITION BY grid.gid ORDER BY grid.month_index
+                       ROWS BETWEEN {} PRECEDING AND CURRENT ROW
+                   ) AS rolling_average
+            FROM grid
+            LEFT JOIN per_month p ON p.budget_id = grid.budget_id AND p.gid = grid.gid
+        )
+        SELECT s.budget_id, s.year, s.month, s.total_income, s.gid,
+               COALESCE(g.name, 'Unassigned'),
+               s.allocated, s.spent, s.previous_spent, s.rolling_average
+        FROM series s
+        LEFT JOIN global_categories g ON g.global_category_id = s.gid
+        WHERE s.month_index >= ?3
+        ORDER BY s.gid = 0, COALESCE(g.name, 'Unassigned') COLLATE NOCASE, s.gid, s.month_index
+        "#,
+        category_filter,
+        window - 1
+    );
+
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i32>(1)?,
+            row.get::<_, u32>(2)?,
+            row.get::<_, f64>(3)?,
+            row.get::<_, i64>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, f64>(6)?,
+            row.get::<_, f64>(7)?,
+            row.get::<_, Option<f64>>(8)?,
+            row.get::<_, f64>(9)?,
+        ))
+    }).map_err(|e| e.to_string())?;
+
+    let mut categories: Vec<CategoryTrend> = Vec::new();
+    let mut months: Vec<MonthTotals> = Vec::new();
+
+    for row in rows {
+        let (budget_id, year, month, total_income, gid, name, allocated, spent, previous_spent, rolling_average) =
+            row.map_err(|e| e.to_string())?;
+
+        let change_amount = previous_spent.map(|previous| spent - previous);
+        let change_percent = previous_spent
+            .filter(|previous| previous.abs() >= 0.005)
+            .map(|previous| (spent - previous) / previous * 100.0);
+
+        let global_category_id = if gid == 0 { None } else { Some(gid) };
+        if categories.last().map(|c| c.global_category_id) != Some(global_category_id) {
+            categories.push(CategoryTrend {
+                global_category_id,
+                name,
+                total_allocated: 0.0,
+                total_spent: 0.0,
+                average_spent: 0.0,
+                points: Vec::new(),
+            });
+        }
+        let category = categories.last_mut().expect("category was just pushed");
+        category.total_allocated += allocated;
+        category.total_spent += spent;
+        category.points.push(TrendPoint {
+            budget_id,
+            year,
+            month,
+            allocated,
+            spent,
+            change_amount,
+            change_percent,
+            rolling_average,
+            share_of_income: share(spent, total_income),
+        });
+
+        match months.iter_mut().find(|m| m.budget_id == budget_id) {
+            Some(totals) => {
+                totals.allocated += allocated;
+                totals.spent += spent;
+            }
+            None => months.push(MonthTotals {
+                budget_id,
+                year,
+                month,
+                total_income,
+                allocated,
+                spent,
+                share_of_income: None,
+            }),
+        }
+    }
+
+    for category in &mut categories {
+        category.average_spent = category.total_spent / category.points.len() as f64;
+    }
+
+    months.sort_by_key(|m| (m.year, m.month));
+    for totals in &mut months {
+        totals.share_of_income = share(totals.spent, totals.total_income);
+    }
+
+    println!("Trends cover {} months and {} categories", months.len(), categories.len());
+    Ok(MonthlyTrends { months, categories })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingRecurring {
+    pub what: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryForecast {
+    pub category_id: i64,
+    pub category_name: String,
+    pub available: f64,        // allocation plus income and transfers booked to the category
+    pub spent_to_date: f64,    // expenses dated up to today
+    pub scheduled_amount: f64, // expenses already booked for later this month
+    pub pending_recurring: Vec<PendingRecurring>, // last month's recurring items not booked yet
+    pub history_average: Option<f64>,
+    pub history_months: i64,
+    pub projected_spent: f64,
+    pub projected_remaining: f64,
+    pub will_overspend: bool,
+    pub run_out_date: Option<String>, // "YYYY-MM-DD" the money is projected to run out
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetForecast {
+    pub budget_id: i64,
+    pub year: i32,
+    pub month: u32,
+    pub as_of: String,
+    pub days_in_month: u32,
+    pub days_elapsed: u32,
+    pub total_available: f64,
+    pub total_projected_spent: f64,
+    pub overspending_count: usize,
+    pub categories: Vec<CategoryForecast>,
+}
+
+struct ForecastCategory {
+    category_id: i64,
+    category_name: String,
+    global_category_id: Option<i64>,
+    available: f64,
+    variable_to_date: f64,
+    recurring_to_date: f64,
+    scheduled_amount: f64,
+}
+
+// Average monthly non-recurring spending of the category over the latest earlier budgets
+fn category_history(conn: &Connection, month_index: i64, category: &ForecastCategory) -> Result<(Option<f64>, i64), String> {
+    let query = format!(
+        "SELECT AVG(total), COUNT(*) FROM (
+             SELECT b.budget_id,
+                    COALESCE(SUM(CASE WHEN l.entry_type = 'expense' AND e.is_recurring = 0 THEN l.amount ELSE 0 END), 0) AS total
+             FROM MonthlyBudgets b
+             JOIN budget_categories c ON c.budget_id = b.budget_id
+             LEFT JOIN entry_lines l ON l.category_id = c.category_id AND l.deleted_at IS NULL
+             LEFT JOIN expenses e ON e.expense_id = l.expense_id
+             WHERE b.deleted_at IS NULL AND c.deleted_at IS NULL
+               AND b.year * 12 + b.month - 1 < ?1
+               AND {}
+             GROUP BY b.budget_id
+             ORDER BY b.year * 12 + b.month DESC
+             LIMIT ?4
+         )",
+        SAME_CATEGORY_SQL
+    );
+    conn.query_row(
+        &query,
+        rusqlite::params![month_index, category.global_category_id, category.category_name, FORECAST_HISTORY_MONTHS],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| e.to_string())
+}
+
+// Recurring expenses of the category in the previous budget that have no entry with the same
+// description in this one yet, e.g. because the budget was created without them
+fn pending_recurring(
+    conn: &Connection,
+    previous_budget_id: i64,
+    category: &ForecastCategory,
+) -> Result<Vec<PendingRecurring>, String> {
+    let query = format!(
+        "SELECT e.description, e.amount
+         FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE c.budget_id = ?1 AND c.deleted_at IS NULL AND {}
+           AND e.is_recurring = 1 AND e.entry_type = 'expense' AND e.deleted_at IS NULL
+           AND NOT EXISTS (
+               SELECT 1 FROM expenses x
+               WHERE x.category_id = ?4 AND x.deleted_at IS NULL
+                 AND LOWER(TRIM(x.description)) = LOWER(TRIM(e.description))
+           )
+         ORDER BY e.date ASC",
+        SAME_CATEGORY_SQL
+    );
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(
+        rusqlite::params![previous_budget_id, category.global_category_id, category.category_name, category.category_id],
+        |row| Ok(PendingRecurring { what: row.get(0)?, amount: row.get(1)? })
+    ).map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn get_budget_forecast(budget_id: i64, db: State<DbState>) -> Result<BudgetForecast, String> {
+    println!("=== GET_BUDGET_FORECAST COMMAND CALLED ===");
+    println!("Forecasting budget ID: {}", budget_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let date_format = format_description!("[year]-[month]-[day]");
+
+    let (year, month, finished_at): (i32, u32, Option<String>) = conn.query_row(
+        "SELECT year, month, finished_at FROM MonthlyBudgets WHERE budget_id = ?1 AND deleted_at IS NULL",
+        [budget_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|_| format!("Budget with ID {} not found", budget_id))?;
+
+    if finished_at.is_some() {
+        return Err(format!("Budget with ID {} is already finished; forecasts are for open budgets", budget_id));
+    }
+
+    let today: String = conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let today_date = Date::parse(&today, date_format).map_err(|e| e.to_string())?;
+
+    let month_enum = Month::try_from(month as u8).map_err(|e| e.to_string())?;
+    let first_day = Date::from_calendar_date(year, month_enum, 1).map_err(|e| e.to_string())?;
+    let days_in_month = time::util::days_in_year_month(year, month_enum) as u32;
+    let last_day = first_day + Duration::days(days_in_month as i64 - 1);
+
+    // Before the month starts nothing is elapsed; once it is over the forecast is the actual
+    let days_elapsed = if today_date < first_day {
+        0
+    } else if today_date > last_day {
+        days_in_month
+    } else {
+        today_date.day() as u32
+    };
+    let elapsed_share = days_elapsed as f64 / days_in_month as f64;
+    let month_index = year as i64 * 12 + month as i64 - 1;
+
+    let categories: Vec<ForecastCategory> = {
+        let mut stmt = conn.prepare(
+            "SELECT c.category_id, c.category_name, c.global_category_id, c.allocated_amount
+                    + COALESCE(SUM(CASE l.entry_type
+                        WHEN 'income' THEN l.amount
+                        WHEN 'transfer_in' THEN l.amount
+                        WHEN 'transfer_out' THEN -l.amount
+                        ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN l.entry_type = 'expense' AND l.date <= ?2 AND e.is_recurring = 0 THEN l.amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN l.entry_type = 'expense' AND l.date <= ?2 AND e.is_recurring = 1 THEN l.amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN l.entry_type = 'expense' AND l.date > ?2 THEN l.amount ELSE 0 END), 0)
+             FROM budget_categories c
+             LEFT JOIN entry_lines l ON l.category_id = c.category_id AND l.deleted_at IS NULL
+             LEFT JOIN expenses e ON e.expense_id = l.expense_id
+             WHERE c.budget_id = ?1 AND c.deleted_at IS NULL AND COALESCE(c.category_type, 'expense') <> 'income'
+             GROUP BY c.category_id, c.category_name, c.global_category_id, c.allocated_amount
+             ORDER BY c.created_at ASC"
+        ).map_err(|e| e.to_string())?;
+
+        let rows = stmt.query_map(rusqlite::params![budget_id, today], |row| {
+            Ok(ForecastCategory {
+                category_id: row.get(0)?,
+                category_name: row.get(1)?,
+                global_category_id: row.get(2)?,
+                available: row.get(3)?,
+                variable_to_date: row.get(4)?,
+                recurring_to_date: row.get(5)?,
+                scheduled_amount: row.get(6)?,
+            })
+        }).map_err(|e| e.to_string())?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    let previous_budget_id: Option<i64> = conn.query_row(
+        "SELECT budget_id FROM MonthlyBudgets
+         WHERE deleted_at IS NULL AND year * 12 + month - 1 < ?1
+         ORDER BY year * 12 + month DESC
+         LIMIT 1",
+        [month_index],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+
+    let mut forecasts = Vec::new();
+    for category in &categories {
+        let (history_average, history_months) = category_history(&conn, month_index, category)?;
+        let pending = match previous_budget_id {
+            Some(previous) if days_elapsed < days_in_month => pending_recurring(&conn, previous, category)?,
+            _ => Vec::new(),
+        };
+
+        // Everyday spending follows this month's pace, leaning on the category's usual month
+        // while little of the month has passed
+        let pace = if days_elapsed == 0 { 0.0 } else { category.variable_to_date / elapsed_share };
+        let projected_variable = match history_average {
+            Some(usual) => elapsed_share * pace + (1.0 - elapsed_share) * usual,
+            None => pace,
+        }
+        .max(category.variable_to_date);
+
+        let spent_to_date = category.variable_to_date + category.recurring_to_date;
+        let pending_total: f64 = pending.iter().map(|p| p.amount).sum();
+        let projected_spent = projected_variable + category.recurring_to_date + category.scheduled_amount + pending_total;
+        let projected_remaining = category.available - projected_spent;
+        let will_overspend = projected_remaining < -0.005;
+
+        // Assumes the rest of the month's spending is spread evenly over the days left
+        let run_out_date = if !will_overspend {
+            None
+        } else if spent_to_date >= category.available || days_elapsed >= days_in_month {
+            Some(today_date.min(last_day))
+        } else {
+            let days_left = (days_in_month - days_elapsed) as f64;
+            let daily = (projected_spent - spent_to_date) / days_left;
+            let days = ((category.available - spent_to_date) / daily).ceil() as i64;
+            let start = if days_elapsed == 0 { first_day - Duration::days(1) } else { today_date };
+            Some((start + Duration::days(days.max(1))).min(last_day))
+        }
+        .map(|date| date.format(date_format))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+        forecasts.push(CategoryForecast {
+            category_id: category.category_id,
+            category_name: category.category_name.clone(),
+            available: category.available,
+            spent_to_date,
+            scheduled_amount: category.scheduled_amount,
+            pending_recurring: pending,
+            history_average,
+            history_months,
+            projected_spent,
+            projected_remaining,
+            will_overspend,
+            run_out_date,
+        });
+    }
+
+    let total_available = forecasts.iter().map(|f| f.available).sum();
+    let total_projected_spent = forecasts.iter().map(|f| f.projected_spent).sum();
+    let overspending_count = forecasts.iter().filter(|f| f.will_overspend).count();
+
+    println!("Forecast for budget {}: {} of {} categories projected to overspend", budget_id, overspending_count, forecasts.len());
+    Ok(BudgetForecast {
+        budget_id,
+        year,
+        month,
+        as_of: today,
+        days_in_month,
+        days_elapsed,
+        total_available,
+        total_projected_spent,
+        overspending_count,
+        categories: forecasts,
+    })
+}
@@ -0,0 +1,325 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::State;
+
+use crate::modules::commands::budget::log_budget_change;
+use crate::modules::database::DbState;
+use crate::modules::security::encryption;
+
+const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
+// What an attachment can belong to; invoices join this list once they exist
+const OWNER_TYPES: [&str; 1] = ["entry"];
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddAttachmentArgs {
+    #[serde(default = "default_owner_type")]
+    pub owner_type: String,
+    pub owner_id: i64,
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+fn default_owner_type() -> String {
+    "entry".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentInfo {
+    pub attachment_id: i64,
+    pub owner_type: String,
+    pub owner_id: i64,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentData {
+    pub info: AttachmentInfo,
+    pub data: Vec<u8>,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+// attachments/ab/abcdef..., so no single directory grows too large
+fn blob_path(dir: &Path, sha256: &str) -> PathBuf {
+    dir.join(&sha256[..2]).join(sha256)
+}
+
+// Recognises the receipt formats people actually attach, then falls back to the extension
+fn detect_mime_type(data: &[u8], file_name: &str) -> String {
+    let sniffed = if data.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if data.len() >= 12 && &data[4..8] == b"ftyp" && (&data[8..12] == b"heic" || &data[8..12] == b"heix") {
+        Some("image/heic")
+    } else {
+        None
+    };
+    if let Some(mime) = sniffed {
+        return mime.to_string();
+    }
+
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+        "json" => "application/json",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
+
+// Budget an attachment's owner belongs to, for the change history
+fn owner_budget(conn: &Connection, owner_type: &str, owner_id: i64) -> Result<i64, String> {
+    match owner_type {
+        "entry" => conn.query_row(
+            "SELECT c.budget_id FROM expenses e
+             JOIN budget_categories c ON c.category_id = e.category_id
+             WHERE e.expense_id = ?1",
+            [owner_id],
+            |row| row.get(0)
+        ).map_err(|_| format!("Entry {} not found", owner_id)),
+        other => Err(format!("Attachments cannot belong to {}", other)),
+    }
+}
+
+fn load_attachment(conn: &Connection, attachment_id: i64) -> Result<(AttachmentInfo, String), String> {
+    conn.query_row(
+        "SELECT attachment_id, owner_type, owner_id, file_name, mime_type, size_bytes, sha256, created_at, encryption
+         FROM attachments WHERE attachment_id = ?1",
+        [attachment_id],
+        |row| Ok((AttachmentInfo {
+            attachment_id: row.get(0)?,
+            owner_type: row.get(1)?,
+            owner_id: row.get(2)?,
+            file_name: row.get(3)?,
+            mime_type: row.get(4)?,
+            size_bytes: row.get(5)?,
+            sha256: row.get(6)?,
+            created_at: row.get(7)?,
+        }, row.get(8)?))
+    ).map_err(|_| format!("Attachment {} not found", attachment_id))
+}
+
+/// Removes stored files that no attachment refers to any more, returning how many went.
+/// Attachment rows of purged entries are dropped by a trigger and deleted attachments leave
+/// their files behind; this clears them.
+pub(crate) fn collect_orphaned_files(conn: &Connection, dir: &Path) -> Result<usize, String> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let referenced: HashSet<String> = {
+        let mut stmt = conn.prepare("SELECT DISTINCT sha256 FROM attachments").map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0)).map_err(|e| e.to_string())?;
+        let mut hashes = HashSet::new();
+        for row in rows {
+            hashes.insert(row.map_err(|e| e.to_string())?);
+        }
+        hashes
+    };
+
+    let mut removed = 0;
+    for shard in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let shard = shard.map_err(|e| e.to_string())?.path();
+        if !shard.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(&shard).map_err(|e| e.to_string())? {
+            let file = file.map_err(|e| e.to_string())?.path();
+            let name = file.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+            if !referenced.contains(&name) {
+                fs::remove_file(&file).map_err(|e| format!("Failed to remove {}: {}", file.display(), e))?;
+                removed += 1;
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+#[tauri::command]
+pub fn add_attachment(args: AddAttachmentArgs, db: State<DbState>) -> Result<AttachmentInfo, String> {
+    println!("=== ADD_ATTACHMENT COMMAND CALLED ===");
+    println!("Attaching {} ({} bytes) to {} {}", args.file_name, args.data.len(), args.owner_type, args.owner_id);
+
+    if !OWNER_TYPES.contains(&args.owner_type.as_str()) {
+        return Err(format!("Attachments cannot belong to {}", args.owner_type));
+    }
+    if args.data.is_empty() {
+        return Err("The file is empty".to_string());
+    }
+    if args.data.len() > MAX_ATTACHMENT_BYTES {
+        return Err(format!("Attachments are limited to {} MB", MAX_ATTACHMENT_BYTES / (1024 * 1024)));
+    }
+
+    // Keep only the name, never a path the frontend happened to send along
+    let file_name = Path::new(&args.file_name)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .unwrap_or("attachment")
+        .to_string();
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let budget_id = owner_budget(&tx, &args.owner_type, args.owner_id)?;
+    let in_trash: bool = tx.query_row(
+        "SELECT e.deleted_at IS NOT NULL OR c.deleted_at IS NOT NULL OR b.deleted_at IS NOT NULL
+         FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+         WHERE e.expense_id = ?1",
+        [args.owner_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if in_trash {
+        return Err(format!("Entry {} is in the trash", args.owner_id));
+    }
+
+    let sha256 = sha256_hex(&args.data);
+    let mime_type = detect_mime_type(&args.data, &file_name);
+    let size_bytes = args.data.len() as i64;
+
+    tx.execute(
+        "INSERT INTO attachments (owner_type, owner_id, file_name, mime_type, size_bytes, sha256, encryption)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![args.owner_type, args.owner_id, file_name, mime_type, size_bytes, sha256, encryption::FILE_ENCRYPTION_SCHEME],
+    ).map_err(|e| e.to_string())?;
+    let attachment_id = tx.last_insert_rowid();
+
+    log_budget_change(&tx, budget_id, "attachment_add", Some("attachments"), None, Some(&file_name),
+                     &format!("Attached {} to {} {}", file_name, args.owner_type, args.owner_id))?;
+
+    let (info, _) = load_attachment(&tx, attachment_id)?;
+
+    // The file is stored last, right before the commit, and removed again if the commit fails,
+    // so a failed upload never leaves a file that no row points to
+    let path = blob_path(&db.attachments_dir(), &sha256);
+    let stored_new = !path.exists();
+    if stored_new {
+        let shard = path.parent().ok_or("Invalid attachment path")?;
+        fs::create_dir_all(shard).map_err(|e| format!("Failed to create attachment directory: {}", e))?;
+
+        // Write next to the final name first so a crash never leaves half a file behind it
+        let partial = path.with_extension("partial");
+        if let Err(e) = fs::write(&partial, encryption::seal_file(args.data)).and_then(|_| fs::rename(&partial, &path)) {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("Failed to store attachment: {}", e));
+        }
+    }
+
+    if let Err(e) = tx.commit() {
+        if stored_new {
+            let _ = fs::remove_file(&path);
+        }
+        return Err(e.to_string());
+    }
+
+    println!("Stored attachment {} as {}", attachment_id, sha256);
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn list_attachments(owner_type: Option<String>, owner_id: i64, db: State<DbState>) -> Result<Vec<AttachmentInfo>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let owner_type = owner_type.unwrap_or_else(default_owner_type);
+
+    let mut stmt = conn.prepare(
+        "SELECT attachment_id, owner_type, owner_id, file_name, mime_type, size_bytes, sha256, created_at
+         FROM attachments
+         WHERE owner_type = ?1 AND owner_id = ?2
+         ORDER BY created_at ASC, attachment_id ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(rusqlite::params![owner_type, owner_id], |row| {
+        Ok(AttachmentInfo {
+            attachment_id: row.get(0)?,
+            owner_type: row.get(1)?,
+            owner_id: row.get(2)?,
+            file_name: row.get(3)?,
+            mime_type: row.get(4)?,
+            size_bytes: row.get(5)?,
+            sha256: row.get(6)?,
+            created_at: row.get(7)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn get_attachment(attachment_id: i64, db: State<DbState>) -> Result<AttachmentData, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let (info, scheme) = load_attachment(&conn, attachment_id)?;
+
+    let path = blob_path(&db.attachments_dir(), &info.sha256);
+    let sealed = fs::read(&path).map_err(|e| format!("Attachment file is missing: {}", e))?;
+    let data = encryption::open_file(sealed, &scheme)?;
+
+    if sha256_hex(&data) != info.sha256 {
+        return Err(format!("Attachment {} is corrupted; its contents no longer match the stored hash", attachment_id));
+    }
+
+    Ok(AttachmentData { info, data })
+}
+
+/// Removes an attachment. This cannot be undone from the change history; the file stays on
+/// disk until the next trash purge collects files nothing refers to.
+#[tauri::command]
+pub fn delete_attachment(attachment_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== DELETE_ATTACHMENT COMMAND CALLED ===");
+    println!("Deleting attachment ID: {}", attachment_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (info, _) = load_attachment(&tx, attachment_id)?;
+
+    tx.execute("DELETE FROM attachments WHERE attachment_id = ?1", [attachment_id])
+        .map_err(|e| e.to_string())?;
+
+    if let Ok(budget_id) = owner_budget(&tx, &info.owner_type, info.owner_id) {
+        log_budget_change(&tx, budget_id, "attachment_delete", Some("attachments"), Some(&info.file_name), None,
+                         &format!("Removed attachment {} from {} {}", info.file_name, info.owner_type, info.owner_id))?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Successfully deleted attachment {}", attachment_id);
+    Ok(())
+}
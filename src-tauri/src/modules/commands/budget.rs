@@ -1,7 +1,18 @@
+use rusqlite::types::Value as SqlValue;
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
 
+use crate::modules::commands::account::{check_account_entry_type, check_open_account};
+use crate::modules::commands::alert::{raise_budget_alerts, raise_entry_budget_alerts};
+use crate::modules::commands::history::{attach_row_snapshots, row_snapshot};
+use crate::modules::commands::reconcile::{check_no_reconciled_entries, check_not_reconciled};
+use crate::modules::commands::split::rebalance_entry_splits;
+use crate::modules::commands::tag::{push_tag_filters, split_tags, ENTRY_TAGS_SQL};
+use crate::modules::commands::transfer::{is_transfer_type, linked_entry};
+use crate::modules::commands::trash::{purge_trash_older_than, TRASH_RETENTION_DAYS};
 use crate::modules::database::{DbState, run_migrations};
+use crate::modules::security::audit;
+use crate::modules::utils::formula;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,6 +23,18 @@ pub struct CreateBudgetArgs {
     pub name: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneBudgetArgs {
+    pub source_budget_id: i64,
+    pub month: u8,
+    pub year: i32,
+    pub total_income: Option<f64>, // defaults to the source budget's income
+    pub name: Option<String>,      // defaults to the source name with month/year swapped
+    #[serde(default)]
+    pub include_recurring: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BudgetSummary {
     pub budget_id: i64,
@@ -38,6 +61,12 @@ pub fn init_database(db: State<DbState>) -> Result<(), String> {
                 Err(e) => println!("Warning: Could not seed initial data: {}", e),
             }
             
+            // Clear out anything that has sat in the trash past the retention period
+            match purge_trash_older_than(&db, TRASH_RETENTION_DAYS) {
+                Ok(summary) => println!("Trash purge completed: {:?}", summary),
+                Err(e) => println!("Warning: Could not purge the trash: {}", e),
+            }
+            
             Ok(())
         }
         Err(e) => {
@@ -112,8 +141,13 @@ pub fn create_monthly_budget(args: CreateBudgetArgs, db: State<DbState>) -> Resu
         rusqlite::params![args.month, args.year, args.total_income, args.name],
     ).map_err(|e| {
         let error_msg = if e.to_string().contains("UNIQUE constraint failed") {
-            format!("A budget for {}/{} already exists. Please choose a different month/year or edit the existing budget.", 
-                   args.month, args.year)
+            if is_month_in_trash(&tx, args.month, args.year) {
+                format!("A budget for {}/{} is in the trash. Restore it or purge the trash before creating a new one.", 
+                       args.month, args.year)
+            } else {
+                format!("A budget for {}/{} already exists. Please choose a different month/year or edit the existing budget.", 
+                       args.month, args.year)
+            }
         } else {
             format!("SQL execution error: {}", e)
         };
@@ -124,6 +158,9 @@ pub fn create_monthly_budget(args: CreateBudgetArgs, db: State<DbState>) -> Resu
     let id = tx.last_insert_rowid();
     println!("Created budget with ID: {}", id);
     
+    let change_id = log_budget_change(&tx, id, "creation", None, None, None, "Budget created")?;
+    attach_row_snapshots(&tx, change_id, "MonthlyBudgets", id, None)?;
+    
     tx.commit().map_err(|e| {
         let error_msg = format!("Transaction commit error: {}", e);
         println!("{}", error_msg);
@@ -133,11 +170,20 @@ pub fn create_monthly_budget(args: CreateBudgetArgs, db: State<DbState>) -> Resu
     Ok(id)
 }
 
+// A trashed budget still holds its month until it is purged
+fn is_month_in_trash(conn: &rusqlite::Connection, month: u8, year: i32) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM MonthlyBudgets WHERE month = ?1 AND year = ?2 AND deleted_at IS NOT NULL",
+        rusqlite::params![month, year],
+        |row| row.get::<_, i64>(0)
+    ).map(|count| count > 0).unwrap_or(false)
+}
+
 #[tauri::command]
 pub fn list_monthly_budgets(db: State<DbState>) -> Result<Vec<BudgetSummary>, String> {
     let conn = db.get_conn().map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare("SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets ORDER BY last_edited DESC")
+        .prepare("SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets WHERE deleted_at IS NULL ORDER BY last_edited DESC")
         .map_err(|e| e.to_string())?;
     let rows = stmt
         .query_map([], |row| {
@@ -172,6 +218,11 @@ pub struct BudgetChangeHistoryEntry {
     pub new_value: Option<String>,
     pub change_description: String,
     pub changed_at: String,
+    pub entity_table: Option<String>,
+    pub entity_id: Option<i64>,
+    pub before_snapshot: Option<String>, // JSON of the row before the change
+    pub after_snapshot: Option<String>,  // JSON of the row after the change
+    pub reverts_change_id: Option<i64>,  // set on "undo" and "redo" entries
 }
 
 // New types for category grid system
@@ -181,11 +232,40 @@ pub struct CategoryRow {
     pub category_id: i64,
     pub budget_id: i64,
     pub category_name: String,
+    pub parent_category_id: Option<i64>,
     pub allocated_amount: f64,
+    pub allocation_type: String, // "fixed" | "percent_income" | "percent_category" | "formula"
+    pub formula: Option<String>,
     pub net_amount: f64,
     pub remaining_amount: f64,
     pub last_activity_at: Option<String>,
     pub entries_count: i64,
+    // Totals of this category and all of its sub-categories
+    pub rollup_allocated_amount: f64,
+    pub rollup_net_amount: f64,
+    pub rollup_remaining_amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverspentCategory {
+    pub category_id: i64,
+    pub category_name: String,
+    pub allocated_amount: f64,
+    pub net_amount: f64,
+    pub over_by: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAllocationSummary {
+    pub budget_id: i64,
+    pub total_income: f64,
+    pub total_allocated: f64,
+    pub to_be_assigned: f64, // negative when more than the income has been allocated
+    pub total_spent: f64,
+    pub total_remaining: f64,
+    pub overspent_categories: Vec<OverspentCategory>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -195,8 +275,14 @@ pub struct NewEntry {
     pub entry_type: String, // "expense" | "income" | "adjustment"
     pub what: String,
     pub r#where: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
     pub amount: f64,        // positive
     pub date: String,       // "YYYY-MM-DD"
+    #[serde(default)]
+    pub is_recurring: bool, // carried over when the budget is cloned
+    #[serde(default)]
+    pub account_id: Option<i64>, // the account the money moved through
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -206,8 +292,14 @@ pub struct UpdateEntry {
     pub entry_type: String,
     pub what: String,
     pub r#where: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>, // None keeps the stored notes, an empty string clears them
     pub amount: f64,
     pub date: String,
+    #[serde(default)]
+    pub is_recurring: Option<bool>, // None keeps the stored flag
+    #[serde(default)]
+    pub account_id: Option<i64>, // None keeps the stored account; set_entry_account unlinks
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -218,8 +310,16 @@ pub struct LedgerEntry {
     pub entry_type: String,
     pub what: String,
     pub r#where: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
     pub amount: f64,
+    pub category_amount: f64, // the part of the amount that counts towards this category
+    pub is_split: bool,
     pub date: String,
+    pub is_recurring: bool,
+    pub account_id: Option<i64>,
+    pub cleared: bool,    // matched against a bank statement
+    pub reconciled: bool, // locked by a finished reconciliation
     pub created_at: String,
 }
 
@@ -237,9 +337,34 @@ pub struct NewCategory {
     pub budget_id: i64,
     pub category_name: String,
     pub allocated_amount: f64,
+    #[serde(default)]
+    pub parent_category_id: Option<i64>,
+    #[serde(default)]
+    pub global_category_id: Option<i64>,
+    #[serde(default)]
+    pub category_type: Option<String>, // "expense" (default) | "savings"
+}
+
+/// Kinds of budget categories; savings categories count towards savings goals.
+pub const CATEGORY_TYPES: [&str; 2] = ["expense", "savings"];
+
+fn check_category_type(category_type: &str) -> Result<(), String> {
+    if CATEGORY_TYPES.contains(&category_type) {
+        Ok(())
+    } else {
+        Err(format!("Unknown category type '{}'", category_type))
+    }
+}
+
+fn check_global_category(conn: &rusqlite::Connection, global_category_id: i64) -> Result<(), String> {
+    conn.query_row(
+        "SELECT 1 FROM global_categories WHERE global_category_id = ?1",
+        [global_category_id],
+        |_| Ok(())
+    ).map_err(|_| format!("Global category {} not found", global_category_id))
 }
 
-fn log_budget_change(
+pub(crate) fn log_budget_change(
     conn: &rusqlite::Connection,
     budget_id: i64,
     change_type: &str,
@@ -247,7 +372,7 @@ fn log_budget_change(
     old_value: Option<&str>,
     new_value: Option<&str>,
     description: &str,
-) -> Result<(), String> {
+) -> Result<i64, String> {
     println!("=== LOGGING BUDGET CHANGE ===");
     println!("Budget ID: {}", budget_id);
     println!("Change Type: {}", change_type);
@@ -264,7 +389,9 @@ fn log_budget_change(
     match result {
         Ok(rows_affected) => {
             println!("Successfully logged change, rows affected: {}", rows_affected);
-            Ok(())
+            let change_id = conn.last_insert_rowid();
+            audit::seal_history_row(conn, change_id).map_err(|e| format!("Failed to seal budget change: {}", e))?;
+            Ok(change_id)
         }
         Err(e) => {
             let error_msg = format!("Failed to log budget change: {}", e);
@@ -281,7 +408,9 @@ pub fn get_budget_change_history(budget_id: i64, db: State<DbState>) -> Result<V
     
     let conn = db.get_conn().map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare("SELECT change_id, budget_id, change_type, field_name, old_value, new_value, change_description, changed_at FROM BudgetChangeHistory WHERE budget_id = ?1 ORDER BY changed_at DESC")
+        .prepare("SELECT change_id, budget_id, change_type, field_name, old_value, new_value, change_description, changed_at,
+                         entity_table, entity_id, before_snapshot, after_snapshot, reverts_change_id
+                  FROM BudgetChangeHistory WHERE budget_id = ?1 ORDER BY changed_at DESC, change_id DESC")
         .map_err(|e| e.to_string())?;
     
     let rows = stmt
@@ -295,6 +424,11 @@ pub fn get_budget_change_history(budget_id: i64, db: State<DbState>) -> Result<V
                 new_value: row.get(5)?,
                 change_description: row.get(6)?,
                 changed_at: row.get(7)?,
+                entity_table: row.get(8)?,
+                entity_id: row.get(9)?,
+                before_snapshot: row.get(10)?,
+                after_snapshot: row.get(11)?,
+                reverts_change_id: row.get(12)?,
             };
             println!("Found change history entry: {:?}", entry);
             Ok(entry)
@@ -364,15 +498,15 @@ pub fn list_monthly_budgets_sorted(args: SortBudgetsArgs, db: State<DbState>) ->
     let query = match (args.criteria.as_str(), args.ascending) {
         ("budget_date", true) => {
             // Budget date ascending: January 2024, February 2024, ..., October 2025, November 2025
-            "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets ORDER BY year ASC, month ASC".to_string()
+            "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets WHERE deleted_at IS NULL ORDER BY year ASC, month ASC".to_string()
         },
         ("budget_date", false) => {
             // Budget date descending: November 2025, October 2025, ..., February 2024, January 2024
-            "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets ORDER BY year DESC, month DESC".to_string()
+            "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets WHERE deleted_at IS NULL ORDER BY year DESC, month DESC".to_string()
         },
         ("name", true) => {
             // Alphabetical ascending: "aaaaaaa" first, then "August 2025", etc.
-            "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets ORDER BY \
+            "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets WHERE deleted_at IS NULL ORDER BY \
                 CASE WHEN name IS NULL OR name = '' THEN \
                     (CASE month \
                         WHEN 1 THEN 'January ' \
@@ -392,7 +526,7 @@ pub fn list_monthly_budgets_sorted(args: SortBudgetsArgs, db: State<DbState>) ->
         },
         ("name", false) => {
             // Alphabetical descending: "September 2025" first, then "August 2025", then "aaaaaaa" (z to a)
-            "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets ORDER BY \
+            "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets WHERE deleted_at IS NULL ORDER BY \
                 CASE WHEN name IS NULL OR name = '' THEN \
                     (CASE month \
                         WHEN 1 THEN 'January ' \
@@ -413,7 +547,7 @@ pub fn list_monthly_budgets_sorted(args: SortBudgetsArgs, db: State<DbState>) ->
         _ => {
             // All other sorting criteria
             format!(
-                "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets ORDER BY {} {} {}",
+                "SELECT budget_id, month, year, total_income, created_at, finished_at, first_finished_at, name, last_edited FROM MonthlyBudgets WHERE deleted_at IS NULL ORDER BY {} {} {}",
                 order_clause, direction, null_handling
             )
         }
@@ -445,9 +579,9 @@ pub fn list_monthly_budgets_sorted(args: SortBudgetsArgs, db: State<DbState>) ->
 }
 
 #[tauri::command]
-pub fn finish_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), String> {
+pub fn finish_monthly_budget(budget_id: i64, strict: Option<bool>, db: State<DbState>) -> Result<(), String> {
     println!("=== FINISH_MONTHLY_BUDGET COMMAND CALLED ===");
-    println!("Finishing budget with ID: {}", budget_id);
+    println!("Finishing budget with ID: {} (strict: {:?})", budget_id, strict);
     
     let conn = db.get_conn().map_err(|e| {
         let error_msg = format!("Database connection error: {}", e);
@@ -457,7 +591,7 @@ pub fn finish_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), S
     
     // First check if budget exists
     let exists = conn.query_row(
-        "SELECT COUNT(*) FROM MonthlyBudgets WHERE budget_id = ?1",
+        "SELECT COUNT(*) FROM MonthlyBudgets WHERE budget_id = ?1 AND deleted_at IS NULL",
         rusqlite::params![budget_id],
         |row| row.get::<_, i32>(0)
     ).map_err(|e| {
@@ -472,6 +606,22 @@ pub fn finish_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), S
         return Err(error_msg);
     }
     
+    // Zero-based budgeting: in strict mode every unit of income must be assigned
+    if strict.unwrap_or(false) {
+        let summary = compute_allocation_summary(&conn, budget_id)?;
+        let to_be_assigned = (summary.to_be_assigned * 100.0).round() / 100.0;
+        
+        if to_be_assigned != 0.0 {
+            let error_msg = if to_be_assigned > 0.0 {
+                format!("Budget still has ${:.2} to be assigned", to_be_assigned)
+            } else {
+                format!("Budget allocations exceed income by ${:.2}", -to_be_assigned)
+            };
+            println!("{}", error_msg);
+            return Err(error_msg);
+        }
+    }
+    
     // Check if this is the first time finishing this budget
     let first_finish = conn.query_row(
         "SELECT first_finished_at FROM MonthlyBudgets WHERE budget_id = ?1",
@@ -484,6 +634,7 @@ pub fn finish_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), S
     })?;
     
     let is_first_finish = first_finish.is_none();
+    let before = row_snapshot(&conn, "MonthlyBudgets", budget_id)?;
     
     // Update the budget - set both finished_at and first_finished_at if it's the first time
     let update_query = if is_first_finish {
@@ -507,8 +658,9 @@ pub fn finish_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), S
             "Budget marked as finished again after being reopened"
         };
         
-        log_budget_change(&conn, budget_id, "status_change", Some("finished_at"), 
+        let change_id = log_budget_change(&conn, budget_id, "status_change", Some("finished_at"), 
                          Some("null"), Some("current_timestamp"), description)?;
+        attach_row_snapshots(&conn, change_id, "MonthlyBudgets", budget_id, before)?;
         
         println!("Successfully finished budget with ID: {}", budget_id);
         Ok(())
@@ -549,7 +701,7 @@ pub fn unfinish_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(),
     
     // First check if budget exists
     let exists = conn.query_row(
-        "SELECT COUNT(*) FROM MonthlyBudgets WHERE budget_id = ?1",
+        "SELECT COUNT(*) FROM MonthlyBudgets WHERE budget_id = ?1 AND deleted_at IS NULL",
         rusqlite::params![budget_id],
         |row| row.get::<_, i32>(0)
     ).map_err(|e| {
@@ -564,6 +716,8 @@ pub fn unfinish_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(),
         return Err(error_msg);
     }
     
+    let before = row_snapshot(&conn, "MonthlyBudgets", budget_id)?;
+    
     // Update the budget to set finished_at to NULL and update last_edited
     let rows_affected = conn.execute(
         "UPDATE MonthlyBudgets SET finished_at = NULL, last_edited = datetime('now') WHERE budget_id = ?1",
@@ -576,9 +730,10 @@ pub fn unfinish_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(),
     
     if rows_affected > 0 {
         // Log the change to history
-        log_budget_change(&conn, budget_id, "status_change", Some("finished_at"), 
+        let change_id = log_budget_change(&conn, budget_id, "status_change", Some("finished_at"), 
                          Some("current_timestamp"), Some("null"), 
                          "Budget reopened for editing")?;
+        attach_row_snapshots(&conn, change_id, "MonthlyBudgets", budget_id, before)?;
                          
         println!("Successfully unfinished budget with ID: {}", budget_id);
         Ok(())
@@ -619,7 +774,7 @@ pub fn delete_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), S
     
     // First check if budget exists
     let exists = conn.query_row(
-        "SELECT COUNT(*) FROM MonthlyBudgets WHERE budget_id = ?1",
+        "SELECT COUNT(*) FROM MonthlyBudgets WHERE budget_id = ?1 AND deleted_at IS NULL",
         rusqlite::params![budget_id],
         |row| row.get::<_, i32>(0)
     ).map_err(|e| {
@@ -634,9 +789,13 @@ pub fn delete_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), S
         return Err(error_msg);
     }
     
-    // Delete the budget
+    check_no_reconciled_entries(&conn, budget_id, None)?;
+    
+    let before = row_snapshot(&conn, "MonthlyBudgets", budget_id)?;
+    
+    // Move the budget to the trash; its categories and entries stay with it until purged
     let rows_affected = conn.execute(
-        "DELETE FROM MonthlyBudgets WHERE budget_id = ?1",
+        "UPDATE MonthlyBudgets SET deleted_at = datetime('now') WHERE budget_id = ?1 AND deleted_at IS NULL",
         rusqlite::params![budget_id],
     ).map_err(|e| {
         let error_msg = format!("Error deleting budget: {}", e);
@@ -645,7 +804,11 @@ pub fn delete_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), S
     })?;
     
     if rows_affected > 0 {
-        println!("Successfully deleted budget with ID: {}", budget_id);
+        let change_id = log_budget_change(&conn, budget_id, "deletion", Some("deleted_at"), None, Some("current_timestamp"), 
+                                          "Budget moved to the trash")?;
+        attach_row_snapshots(&conn, change_id, "MonthlyBudgets", budget_id, before)?;
+        
+        println!("Successfully moved budget with ID {} to the trash", budget_id);
         Ok(())
     } else {
         let error_msg = format!("Failed to delete budget with ID: {}", budget_id);
@@ -677,6 +840,7 @@ pub async fn update_budget_title(
         .map_err(|e| e.to_string())?;
     
     let old_title = current_title.unwrap_or_else(|| "".to_string());
+    let before = row_snapshot(&conn, "MonthlyBudgets", budget_id as i64)?;
     
     // Update the budget title
     let rows_affected = conn.execute(
@@ -695,7 +859,7 @@ pub async fn update_budget_title(
     }
     
     // Log the title change
-    log_budget_change(
+    let change_id = log_budget_change(
         &conn,
         budget_id as i64,
         "title_change",
@@ -704,11 +868,259 @@ pub async fn update_budget_title(
         Some(&title),
         &format!("Budget title changed to '{}'", title),
     )?;
+    attach_row_snapshots(&conn, change_id, "MonthlyBudgets", budget_id as i64, before)?;
     
     println!("Successfully updated budget title for ID: {}", budget_id);
     Ok(())
 }
 
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+fn month_name(month: u8) -> &'static str {
+    MONTH_NAMES.get((month as usize).wrapping_sub(1)).copied().unwrap_or("")
+}
+
+// Replaces `from` only where it stands as a whole word, so "May" leaves "Mayfair" alone
+// and "2024" leaves "20245" alone
+fn replace_word(text: &str, from: &str, to: &str) -> String {
+    if from.is_empty() {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (pos, _) in text.match_indices(from) {
+        let end = pos + from.len();
+        let before = text[..pos].chars().next_back();
+        let after = text[end..].chars().next();
+        if !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric) {
+            result.push_str(&text[last..pos]);
+            result.push_str(to);
+            last = end;
+        }
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+// Carries a custom budget name over to another month, e.g.
+// "January 2024 Budget" -> "February 2024 Budget"
+fn derive_budget_name(source_name: Option<&str>, from: (u8, i32), to: (u8, i32)) -> Option<String> {
+    let name = source_name.filter(|n| !n.trim().is_empty())?;
+    let renamed = replace_word(name, month_name(from.0), month_name(to.0));
+    let renamed = replace_word(&renamed, &from.1.to_string(), &to.1.to_string());
+    Some(renamed)
+}
+
+// Moves a "YYYY-MM-DD" date into the target month, clamping the day to the month length
+fn shift_date_to_month(date: &str, month: u8, year: i32) -> String {
+    let day: u8 = date.get(8..10).and_then(|d| d.parse().ok()).unwrap_or(1);
+    let last_day = time::Month::try_from(month)
+        .map(|m| m.length(year))
+        .unwrap_or(28);
+    format!("{:04}-{:02}-{:02}", year, month, day.clamp(1, last_day))
+}
+
+struct SourceCategory {
+    category_id: i64,
+    category_name: String,
+    allocated_amount: f64,
+    global_category_id: Option<i64>,
+    category_type: Option<String>,
+    allocation_type: String,
+    formula: Option<String>,
+    parent_category_id: Option<i64>,
+}
+
+#[tauri::command]
+pub fn clone_monthly_budget(args: CloneBudgetArgs, db: State<DbState>) -> Result<i64, String> {
+    println!("=== CLONE_MONTHLY_BUDGET COMMAND CALLED ===");
+    println!("Cloning budget: {:?}", args);
+    
+    if !(1..=12).contains(&args.month) {
+        return Err(format!("Invalid month: {}", args.month));
+    }
+    
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let (source_month, source_year, source_income, source_name, source_template): (u8, i32, f64, Option<String>, Option<i64>) = tx.query_row(
+        "SELECT month, year, total_income, name, template_used FROM MonthlyBudgets WHERE budget_id = ?1 AND deleted_at IS NULL",
+        [args.source_budget_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+    ).map_err(|_| format!("Budget with ID {} not found", args.source_budget_id))?;
+    
+    let total_income = args.total_income.unwrap_or(source_income);
+    let name = args.name.clone().or_else(|| {
+        derive_budget_name(source_name.as_deref(), (source_month, source_year), (args.month, args.year))
+    });
+    
+    tx.execute(
+        "INSERT INTO MonthlyBudgets (month, year, total_income, template_used, name, created_at, last_edited) VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'), datetime('now'))",
+        rusqlite::params![args.month, args.year, total_income, source_template, name],
+    ).map_err(|e| {
+        if e.to_string().contains("UNIQUE constraint failed") && is_month_in_trash(&tx, args.month, args.year) {
+            format!("A budget for {}/{} is in the trash. Restore it or purge the trash before creating a new one.", 
+                   args.month, args.year)
+        } else if e.to_string().contains("UNIQUE constraint failed") {
+            format!("A budget for {}/{} already exists. Please choose a different month/year or edit the existing budget.", 
+                   args.month, args.year)
+        } else {
+            format!("SQL execution error: {}", e)
+        }
+    })?;
+    
+    let budget_id = tx.last_insert_rowid();
+    
+    // Copy the categories with their current allocations and types
+    let source_categories: Vec<SourceCategory> = {
+        let mut stmt = tx.prepare(
+            "SELECT category_id, category_name, allocated_amount, global_category_id, category_type, allocation_type, formula, parent_category_id
+             FROM budget_categories
+             WHERE budget_id = ?1 AND deleted_at IS NULL
+             ORDER BY created_at ASC, category_id ASC"
+        ).map_err(|e| e.to_string())?;
+        
+        let rows = stmt.query_map([args.source_budget_id], |row| {
+            Ok(SourceCategory {
+                category_id: row.get(0)?,
+                category_name: row.get(1)?,
+                allocated_amount: row.get(2)?,
+                global_category_id: row.get(3)?,
+                category_type: row.get(4)?,
+                allocation_type: row.get(5)?,
+                formula: row.get(6)?,
+                parent_category_id: row.get(7)?,
+            })
+        }).map_err(|e| e.to_string())?;
+        
+        let mut categories = Vec::new();
+        for row in rows {
+            categories.push(row.map_err(|e| e.to_string())?);
+        }
+        categories
+    };
+    
+    let mut recurring_count = 0;
+    let mut cloned_ids = std::collections::HashMap::new();
+    let mut cloned_entries = Vec::new();
+    for source in &source_categories {
+        tx.execute(
+            "INSERT INTO budget_categories (budget_id, global_category_id, category_name, allocated_amount, category_type, allocation_type, formula, created_at) 
+             VALUES (?1, ?2, ?3, ?4, COALESCE(?5, 'expense'), ?6, ?7, datetime('now'))",
+            rusqlite::params![budget_id, source.global_category_id, source.category_name, source.allocated_amount,
+                              source.category_type, source.allocation_type, source.formula],
+        ).map_err(|e| e.to_string())?;
+        
+        let category_id = tx.last_insert_rowid();
+        cloned_ids.insert(source.category_id, category_id);
+        
+        if args.include_recurring {
+            // Transfer sides only make sense as a pair, so they are not carried over
+            let recurring: Vec<(i64, String, String, Option<String>, Option<String>, f64, String, Option<i64>)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT expense_id, entry_type, description, place, notes, amount, date, account_id
+                     FROM expenses
+                     WHERE category_id = ?1 AND is_recurring = 1 AND deleted_at IS NULL
+                       AND entry_type NOT IN ('transfer_in', 'transfer_out')
+                     ORDER BY date ASC"
+                ).map_err(|e| e.to_string())?;
+                
+                let rows = stmt.query_map([source.category_id], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?))
+                }).map_err(|e| e.to_string())?;
+                
+                let mut entries = Vec::new();
+                for row in rows {
+                    entries.push(row.map_err(|e| e.to_string())?);
+                }
+                entries
+            };
+            
+            for (source_entry_id, entry_type, description, place, notes, amount, date, account_id) in recurring {
+                tx.execute(
+                    "INSERT INTO expenses (category_id, entry_type, description, place, notes, amount, date, is_recurring, account_id, created_at) 
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1, ?8, datetime('now'))",
+                    rusqlite::params![
+                        category_id,
+                        entry_type,
+                        description,
+                        place,
+                        notes,
+                        amount,
+                        shift_date_to_month(&date, args.month, args.year),
+                        account_id
+                    ],
+                ).map_err(|e| e.to_string())?;
+                
+                cloned_entries.push((source_entry_id, tx.last_insert_rowid(), category_id));
+                recurring_count += 1;
+            }
+        }
+    }
+    
+    // Rebuild the category tree once every clone has its new ID
+    for source in &source_categories {
+        if let Some(parent_id) = source.parent_category_id.and_then(|p| cloned_ids.get(&p)) {
+            tx.execute(
+                "UPDATE budget_categories SET parent_category_id = ?1 WHERE category_id = ?2",
+                rusqlite::params![parent_id, cloned_ids[&source.category_id]],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    
+    // Cloned entries keep their tags and splits. Parts in a category that was not cloned fall
+    // back to the entry's own category, as they do when that category is purged
+    for (source_entry_id, entry_id, category_id) in &cloned_entries {
+        tx.execute(
+            "INSERT INTO entry_tags (expense_id, tag_id) SELECT ?1, tag_id FROM entry_tags WHERE expense_id = ?2",
+            rusqlite::params![entry_id, source_entry_id],
+        ).map_err(|e| e.to_string())?;
+        
+        let parts: Vec<(i64, f64, Option<String>)> = {
+            let mut stmt = tx.prepare(
+                "SELECT category_id, amount, note FROM entry_splits WHERE expense_id = ?1 ORDER BY split_id"
+            ).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([source_entry_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .map_err(|e| e.to_string())?;
+            
+            let mut parts = Vec::new();
+            for row in rows {
+                parts.push(row.map_err(|e| e.to_string())?);
+            }
+            parts
+        };
+        for (part_category_id, amount, note) in parts {
+            let part_category_id = cloned_ids.get(&part_category_id).copied().unwrap_or(*category_id);
+            tx.execute(
+                "INSERT INTO entry_splits (expense_id, category_id, amount, note) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![entry_id, part_category_id, amount, note],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    
+    let description = if args.include_recurring {
+        format!("Budget cloned from budget {} ({} categories, {} recurring entries)", 
+                args.source_budget_id, source_categories.len(), recurring_count)
+    } else {
+        format!("Budget cloned from budget {} ({} categories)", 
+                args.source_budget_id, source_categories.len())
+    };
+    let change_id = log_budget_change(&tx, budget_id, "creation", Some("source_budget_id"), 
+                     None, Some(&args.source_budget_id.to_string()), &description)?;
+    attach_row_snapshots(&tx, change_id, "MonthlyBudgets", budget_id, None)?;
+    
+    // Formula allocations follow the new budget's income
+    recompute_budget_allocations(&tx, budget_id)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("Successfully cloned budget {} into budget {}", args.source_budget_id, budget_id);
+    Ok(budget_id)
+}
+
 // Category Grid System Commands
 #[tauri::command]
 pub fn get_budget_categories_with_stats(budget_id: i64, db: State<DbState>) -> Result<Vec<CategoryRow>, String> {
@@ -716,27 +1128,40 @@ pub fn get_budget_categories_with_stats(budget_id: i64, db: State<DbState>) -> R
     println!("Fetching categories with stats for budget ID: {}", budget_id);
     
     let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let results = query_category_rows(&conn, budget_id)?;
     
+    println!("Found {} categories with stats", results.len());
+    Ok(results)
+}
+
+fn query_category_rows(conn: &rusqlite::Connection, budget_id: i64) -> Result<Vec<CategoryRow>, String> {
     let query = r#"
         SELECT c.category_id,
                c.budget_id,
                c.category_name,
+               c.parent_category_id,
                c.allocated_amount,
+               c.allocation_type,
+               c.formula,
                COALESCE(SUM(CASE e.entry_type
                    WHEN 'income' THEN e.amount
                    WHEN 'expense' THEN -e.amount
+                   WHEN 'transfer_in' THEN e.amount
+                   WHEN 'transfer_out' THEN -e.amount
                    ELSE 0 END), 0) AS net_amount,
                (c.allocated_amount + COALESCE(SUM(CASE e.entry_type
                    WHEN 'income' THEN e.amount
                    WHEN 'expense' THEN -e.amount
+                   WHEN 'transfer_in' THEN e.amount
+                   WHEN 'transfer_out' THEN -e.amount
                    ELSE 0 END), 0)) AS remaining_amount,
                MAX(e.date) AS last_activity_at,
                COUNT(CASE WHEN e.deleted_at IS NULL THEN e.expense_id END) AS entries_count
         FROM budget_categories c
-        LEFT JOIN expenses e
+        LEFT JOIN entry_lines e
           ON e.category_id = c.category_id AND e.deleted_at IS NULL
-        WHERE c.budget_id = ?1
-        GROUP BY c.category_id, c.budget_id, c.category_name, c.allocated_amount
+        WHERE c.budget_id = ?1 AND c.deleted_at IS NULL
+        GROUP BY c.category_id, c.budget_id, c.category_name, c.parent_category_id, c.allocated_amount, c.allocation_type, c.formula
         ORDER BY c.created_at ASC
     "#;
     
@@ -747,11 +1172,17 @@ pub fn get_budget_categories_with_stats(budget_id: i64, db: State<DbState>) -> R
                 category_id: row.get(0)?,
                 budget_id: row.get(1)?,
                 category_name: row.get(2)?,
-                allocated_amount: row.get(3)?,
-                net_amount: row.get(4)?,
-                remaining_amount: row.get(5)?,
-                last_activity_at: row.get(6)?,
-                entries_count: row.get(7)?,
+                parent_category_id: row.get(3)?,
+                allocated_amount: row.get(4)?,
+                allocation_type: row.get(5)?,
+                formula: row.get(6)?,
+                net_amount: row.get(7)?,
+                remaining_amount: row.get(8)?,
+                last_activity_at: row.get(9)?,
+                entries_count: row.get(10)?,
+                rollup_allocated_amount: 0.0,
+                rollup_net_amount: 0.0,
+                rollup_remaining_amount: 0.0,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -761,21 +1192,177 @@ pub fn get_budget_categories_with_stats(budget_id: i64, db: State<DbState>) -> R
         results.push(r.map_err(|e| e.to_string())?);
     }
     
-    println!("Found {} categories with stats", results.len());
+    roll_up_category_totals(&mut results);
     Ok(results)
 }
 
+// Fills in the rollup_* totals by adding every category's own amounts to all of its ancestors.
+// Parents that are not part of the same result set are ignored.
+fn roll_up_category_totals(rows: &mut [CategoryRow]) {
+    let index_of: std::collections::HashMap<i64, usize> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.category_id, i))
+        .collect();
+    
+    let mut totals = vec![(0.0, 0.0, 0.0); rows.len()];
+    for (i, row) in rows.iter().enumerate() {
+        let own = (row.allocated_amount, row.net_amount, row.remaining_amount);
+        let mut current = Some(i);
+        let mut steps = 0;
+        // Bounded walk so a corrupted parent chain cannot loop forever
+        while let Some(index) = current {
+            totals[index].0 += own.0;
+            totals[index].1 += own.1;
+            totals[index].2 += own.2;
+            steps += 1;
+            if steps > rows.len() {
+                break;
+            }
+            current = rows[index].parent_category_id.and_then(|p| index_of.get(&p).copied());
+        }
+    }
+    
+    for (row, (allocated, net, remaining)) in rows.iter_mut().zip(totals) {
+        row.rollup_allocated_amount = allocated;
+        row.rollup_net_amount = net;
+        row.rollup_remaining_amount = remaining;
+    }
+}
+
+// True when `ancestor_id` is `category_id` itself or one of its parents
+fn is_budget_category_ancestor(conn: &rusqlite::Connection, ancestor_id: i64, category_id: i64) -> Result<bool, String> {
+    let found: i64 = conn.query_row(
+        "WITH RECURSIVE chain(id) AS (
+             SELECT ?2
+             UNION
+             SELECT c.parent_category_id FROM budget_categories c JOIN chain ON c.category_id = chain.id
+             WHERE c.parent_category_id IS NOT NULL
+         )
+         SELECT COUNT(*) FROM chain WHERE id = ?1",
+        rusqlite::params![ancestor_id, category_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    Ok(found > 0)
+}
+
+#[tauri::command]
+pub fn move_budget_category(category_id: i64, parent_category_id: Option<i64>, db: State<DbState>) -> Result<(), String> {
+    println!("=== MOVE_BUDGET_CATEGORY COMMAND CALLED ===");
+    println!("Moving category ID: {} under parent: {:?}", category_id, parent_category_id);
+    
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let (category_name, budget_id, current_parent): (String, i64, Option<i64>) = tx.query_row(
+        "SELECT category_name, budget_id, parent_category_id FROM budget_categories WHERE category_id = ?1",
+        [category_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|e| format!("Category not found: {}", e))?;
+    
+    let parent_name = match parent_category_id {
+        Some(parent_id) => {
+            let parent_name: String = tx.query_row(
+                "SELECT category_name FROM budget_categories WHERE category_id = ?1 AND budget_id = ?2 AND deleted_at IS NULL",
+                rusqlite::params![parent_id, budget_id],
+                |row| row.get(0)
+            ).map_err(|_| "Parent category not found in this budget".to_string())?;
+            
+            if is_budget_category_ancestor(&tx, category_id, parent_id)? {
+                return Err(format!("Cannot move {} under {}: it would create a cycle", category_name, parent_name));
+            }
+            Some(parent_name)
+        }
+        None => None,
+    };
+    
+    let before = row_snapshot(&tx, "budget_categories", category_id)?;
+    
+    // Only the parent link changes; ledger entries keep pointing at the same category
+    tx.execute(
+        "UPDATE budget_categories SET parent_category_id = ?1 WHERE category_id = ?2",
+        rusqlite::params![parent_category_id, category_id],
+    ).map_err(|e| e.to_string())?;
+    
+    let description = match &parent_name {
+        Some(parent) => format!("Moved category {} under {}", category_name, parent),
+        None => format!("Moved category {} to the top level", category_name),
+    };
+    let change_id = log_budget_change(&tx, budget_id, "category_move", Some("parent_category_id"), 
+                     current_parent.map(|p| p.to_string()).as_deref(), 
+                     parent_category_id.map(|p| p.to_string()).as_deref(), &description)?;
+    attach_row_snapshots(&tx, change_id, "budget_categories", category_id, before)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("Successfully moved category ID: {}", category_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_budget_allocation_summary(budget_id: i64, db: State<DbState>) -> Result<BudgetAllocationSummary, String> {
+    println!("=== GET_BUDGET_ALLOCATION_SUMMARY COMMAND CALLED ===");
+    println!("Building allocation summary for budget ID: {}", budget_id);
+    
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    compute_allocation_summary(&conn, budget_id)
+}
+
+fn compute_allocation_summary(conn: &rusqlite::Connection, budget_id: i64) -> Result<BudgetAllocationSummary, String> {
+    let total_income: f64 = conn.query_row(
+        "SELECT total_income FROM MonthlyBudgets WHERE budget_id = ?1 AND deleted_at IS NULL",
+        [budget_id],
+        |row| row.get(0)
+    ).map_err(|_| format!("Budget with ID {} not found", budget_id))?;
+    
+    let total_spent: f64 = conn.query_row(
+        "SELECT COALESCE(SUM(e.amount), 0)
+         FROM entry_lines e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE c.budget_id = ?1 AND c.deleted_at IS NULL AND e.entry_type = 'expense' AND e.deleted_at IS NULL",
+        [budget_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    
+    let categories = query_category_rows(conn, budget_id)?;
+    let total_allocated: f64 = categories.iter().map(|c| c.allocated_amount).sum();
+    let total_remaining: f64 = categories.iter().map(|c| c.remaining_amount).sum();
+    
+    let overspent_categories = categories
+        .iter()
+        .filter(|c| c.remaining_amount < -0.005)
+        .map(|c| OverspentCategory {
+            category_id: c.category_id,
+            category_name: c.category_name.clone(),
+            allocated_amount: c.allocated_amount,
+            net_amount: c.net_amount,
+            over_by: -c.remaining_amount,
+        })
+        .collect();
+    
+    Ok(BudgetAllocationSummary {
+        budget_id,
+        total_income,
+        total_allocated,
+        to_be_assigned: total_income - total_allocated,
+        total_spent,
+        total_remaining,
+        overspent_categories,
+    })
+}
+
 #[tauri::command]
 pub fn get_category_ledger(
     category_id: i64, 
     limit: u32, 
     offset: u32, 
     sort: String, 
+    tags: Option<Vec<String>>,
     db: State<DbState>
 ) -> Result<Vec<LedgerEntry>, String> {
     println!("=== GET_CATEGORY_LEDGER COMMAND CALLED ===");
-    println!("Fetching ledger for category ID: {}, limit: {}, offset: {}, sort: {}", 
-             category_id, limit, offset, sort);
+    println!("Fetching ledger for category ID: {}, limit: {}, offset: {}, sort: {}, tags: {:?}", 
+             category_id, limit, offset, sort, tags);
     
     let conn = db.get_conn().map_err(|e| e.to_string())?;
     
@@ -789,33 +1376,59 @@ pub fn get_category_ledger(
         _ => "ORDER BY e.date DESC, e.created_at DESC", // default
     };
     
+    // Split entries show up in every category one of their parts belongs to
+    let mut conditions = vec!["l.category_id = ?1".to_string(), "e.deleted_at IS NULL".to_string()];
+    let mut params = vec![SqlValue::Integer(category_id)];
+    if let Some(tags) = &tags {
+        push_tag_filters(tags, &mut conditions, &mut params)?;
+    }
+    params.push(SqlValue::Integer(limit as i64));
+    params.push(SqlValue::Integer(offset as i64));
+    
     let query = format!(r#"
         SELECT e.expense_id,
                e.category_id,
                e.entry_type,
                e.description,
                e.place,
+               e.notes,
                e.amount,
                e.date,
-               e.created_at
-        FROM expenses e
-        WHERE e.category_id = ?1 AND e.deleted_at IS NULL
+               e.is_recurring,
+               e.created_at,
+               {},
+               l.amount,
+               l.is_split,
+               e.account_id,
+               e.cleared_at IS NOT NULL,
+               e.reconciled_at IS NOT NULL
+        FROM entry_lines l
+        JOIN expenses e ON e.expense_id = l.expense_id
+        WHERE {}
         {}
-        LIMIT ?2 OFFSET ?3
-    "#, order_clause);
+        LIMIT ?{} OFFSET ?{}
+    "#, ENTRY_TAGS_SQL, conditions.join(" AND "), order_clause, params.len() - 1, params.len());
     
     let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map([category_id, limit as i64, offset as i64], |row| {
+        .query_map(rusqlite::params_from_iter(params.iter()), |row| {
             Ok(LedgerEntry {
                 entry_id: row.get(0)?,
                 category_id: row.get(1)?,
                 entry_type: row.get(2)?,
                 what: row.get(3)?,
                 r#where: row.get(4)?,
-                amount: row.get(5)?,
-                date: row.get(6)?,
-                created_at: row.get(7)?,
+                notes: row.get(5)?,
+                amount: row.get(6)?,
+                date: row.get(7)?,
+                is_recurring: row.get(8)?,
+                created_at: row.get(9)?,
+                tags: split_tags(row.get(10)?),
+                category_amount: row.get(11)?,
+                is_split: row.get(12)?,
+                account_id: row.get(13)?,
+                cleared: row.get(14)?,
+                reconciled: row.get(15)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -830,31 +1443,43 @@ pub fn get_category_ledger(
 }
 
 #[tauri::command]
-pub fn add_category_entry(payload: NewEntry, db: State<DbState>) -> Result<LedgerEntry, String> {
+pub fn add_category_entry(payload: NewEntry, app: AppHandle, db: State<DbState>) -> Result<LedgerEntry, String> {
     println!("=== ADD_CATEGORY_ENTRY COMMAND CALLED ===");
     println!("Adding entry: {:?}", payload);
     
+    if is_transfer_type(&payload.entry_type) {
+        return Err("Use transfer_between_categories to record a transfer".to_string());
+    }
+    
     let mut conn = db.get_conn().map_err(|e| e.to_string())?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
     
     // Get category info for logging
     let category_info: (String, i64) = tx.query_row(
-        "SELECT category_name, budget_id FROM budget_categories WHERE category_id = ?1",
+        "SELECT category_name, budget_id FROM budget_categories WHERE category_id = ?1 AND deleted_at IS NULL",
         [payload.category_id],
         |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
     ).map_err(|e| format!("Category not found: {}", e))?;
     
+    if let Some(account_id) = payload.account_id {
+        check_open_account(&tx, account_id)?;
+        check_account_entry_type(&payload.entry_type)?;
+    }
+    
     // Insert the entry
     tx.execute(
-        "INSERT INTO expenses (category_id, entry_type, description, place, amount, date, created_at) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))",
+        "INSERT INTO expenses (category_id, entry_type, description, place, notes, amount, date, is_recurring, account_id, created_at) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, datetime('now'))",
         rusqlite::params![
             payload.category_id,
             payload.entry_type,
             payload.what,
             payload.r#where,
+            payload.notes,
             payload.amount,
-            payload.date
+            payload.date,
+            payload.is_recurring,
+            payload.account_id
         ],
     ).map_err(|e| e.to_string())?;
     
@@ -862,7 +1487,7 @@ pub fn add_category_entry(payload: NewEntry, db: State<DbState>) -> Result<Ledge
     
     // Get the created entry
     let entry: LedgerEntry = tx.query_row(
-        "SELECT expense_id, category_id, entry_type, description, place, amount, date, created_at 
+        "SELECT expense_id, category_id, entry_type, description, place, notes, amount, date, is_recurring, created_at, account_id 
          FROM expenses WHERE expense_id = ?1",
         [entry_id],
         |row| Ok(LedgerEntry {
@@ -871,9 +1496,17 @@ pub fn add_category_entry(payload: NewEntry, db: State<DbState>) -> Result<Ledge
             entry_type: row.get(2)?,
             what: row.get(3)?,
             r#where: row.get(4)?,
-            amount: row.get(5)?,
-            date: row.get(6)?,
-            created_at: row.get(7)?,
+            notes: row.get(5)?,
+            amount: row.get(6)?,
+            category_amount: row.get(6)?,
+            is_split: false,
+            date: row.get(7)?,
+            is_recurring: row.get(8)?,
+            created_at: row.get(9)?,
+            account_id: row.get(10)?,
+            cleared: false,
+            reconciled: false,
+            tags: Vec::new(),
         })
     ).map_err(|e| e.to_string())?;
     
@@ -883,17 +1516,19 @@ pub fn add_category_entry(payload: NewEntry, db: State<DbState>) -> Result<Ledge
     let description = format!("Added {} ${:.2} to {} ({}{})", 
                             payload.entry_type, payload.amount, category_info.0, payload.what, place_display);
     
-    log_budget_change(&*tx, category_info.1, "entry_add", Some("expenses"), 
+    let change_id = log_budget_change(&tx, category_info.1, "entry_add", Some("expenses"), 
                      None, Some(&format!("{:.2}", payload.amount)), &description)?;
+    attach_row_snapshots(&tx, change_id, "expenses", entry_id, None)?;
     
     tx.commit().map_err(|e| e.to_string())?;
+    raise_budget_alerts(&app, &conn, category_info.1);
     
     println!("Successfully added entry with ID: {}", entry_id);
     Ok(entry)
 }
 
 #[tauri::command]
-pub fn update_category_entry(payload: UpdateEntry, db: State<DbState>) -> Result<(), String> {
+pub fn update_category_entry(payload: UpdateEntry, app: AppHandle, db: State<DbState>) -> Result<(), String> {
     println!("=== UPDATE_CATEGORY_ENTRY COMMAND CALLED ===");
     println!("Updating entry: {:?}", payload);
     
@@ -901,24 +1536,52 @@ pub fn update_category_entry(payload: UpdateEntry, db: State<DbState>) -> Result
     let tx = conn.transaction().map_err(|e| e.to_string())?;
     
     // Get category info for logging
-    let category_info: (String, i64) = tx.query_row(
-        "SELECT bc.category_name, bc.budget_id FROM budget_categories bc 
+    let category_info: (String, i64, f64, String) = tx.query_row(
+        "SELECT bc.category_name, bc.budget_id, e.amount, e.entry_type FROM budget_categories bc 
          JOIN expenses e ON e.category_id = bc.category_id 
          WHERE e.expense_id = ?1",
         [payload.entry_id],
-        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, f64>(2)?, row.get::<_, String>(3)?))
     ).map_err(|e| format!("Entry or category not found: {}", e))?;
+    let before = row_snapshot(&tx, "expenses", payload.entry_id)?;
+    check_not_reconciled(&tx, payload.entry_id)?;
+    
+    // The two sides of a transfer have to stay balanced, so only their text can change
+    if is_transfer_type(&category_info.3) || is_transfer_type(&payload.entry_type) {
+        if payload.entry_type != category_info.3 || (payload.amount - category_info.2).abs() >= 0.005 {
+            return Err("A transfer's type and amount cannot be edited; delete it and record a new one".to_string());
+        }
+    }
+    
+    // An entry can stay on an account that was closed since, but not move onto one
+    let current_account: Option<i64> = tx.query_row(
+        "SELECT account_id FROM expenses WHERE expense_id = ?1",
+        [payload.entry_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if let Some(account_id) = payload.account_id.filter(|id| Some(*id) != current_account) {
+        check_open_account(&tx, account_id)?;
+    }
+    
+    let account_id = payload.account_id.or(current_account);
+    if account_id.is_some() {
+        check_account_entry_type(&payload.entry_type)?;
+    }
     
-    // Update the entry
+    // Update the entry; a cleared mark only holds for the account it was cleared on
     let rows_affected = tx.execute(
-        "UPDATE expenses SET entry_type = ?1, description = ?2, place = ?3, amount = ?4, date = ?5 
-         WHERE expense_id = ?6 AND deleted_at IS NULL",
+        "UPDATE expenses SET entry_type = ?1, description = ?2, place = ?3, notes = CASE WHEN ?4 IS NULL THEN notes ELSE NULLIF(TRIM(?4), '') END, amount = ?5, date = ?6, is_recurring = COALESCE(?7, is_recurring), account_id = ?8,
+                cleared_at = CASE WHEN account_id IS ?8 THEN cleared_at END 
+         WHERE expense_id = ?9 AND deleted_at IS NULL",
         rusqlite::params![
             payload.entry_type,
             payload.what,
             payload.r#where,
+            payload.notes,
             payload.amount,
             payload.date,
+            payload.is_recurring,
+            account_id,
             payload.entry_id
         ],
     ).map_err(|e| e.to_string())?;
@@ -926,34 +1589,56 @@ pub fn update_category_entry(payload: UpdateEntry, db: State<DbState>) -> Result
     if rows_affected == 0 {
         return Err("Entry not found or already deleted".to_string());
     }
+    rebalance_entry_splits(&tx, payload.entry_id)?;
     
     // Log change history
     let description = format!("Updated entry {} in {}", payload.entry_id, category_info.0);
-    log_budget_change(&*tx, category_info.1, "entry_update", Some("expenses"), 
-                     None, None, &description)?;
+    let change_id = log_budget_change(&tx, category_info.1, "entry_update", Some("expenses"), 
+                     Some(&format!("{:.2}", category_info.2)), Some(&format!("{:.2}", payload.amount)), &description)?;
+    attach_row_snapshots(&tx, change_id, "expenses", payload.entry_id, before)?;
     
     tx.commit().map_err(|e| e.to_string())?;
+    raise_budget_alerts(&app, &conn, category_info.1);
     
     println!("Successfully updated entry with ID: {}", payload.entry_id);
     Ok(())
 }
 
 #[tauri::command]
-pub fn soft_delete_category_entry(entry_id: i64, db: State<DbState>) -> Result<(), String> {
+pub fn soft_delete_category_entry(entry_id: i64, app: AppHandle, db: State<DbState>) -> Result<(), String> {
     println!("=== SOFT_DELETE_CATEGORY_ENTRY COMMAND CALLED ===");
     println!("Soft deleting entry ID: {}", entry_id);
     
     let mut conn = db.get_conn().map_err(|e| e.to_string())?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
     
+    soft_delete_entry(&tx, entry_id)?;
+    
+    // Both sides of a transfer go to the trash together
+    if let Some(linked_id) = linked_entry(&tx, entry_id, false)? {
+        soft_delete_entry(&tx, linked_id)?;
+    }
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    // Removing a refund can push a category over a threshold too
+    raise_entry_budget_alerts(&app, &conn, entry_id);
+    
+    println!("Successfully soft deleted entry with ID: {}", entry_id);
+    Ok(())
+}
+
+fn soft_delete_entry(tx: &rusqlite::Connection, entry_id: i64) -> Result<(), String> {
+    check_not_reconciled(tx, entry_id)?;
+    
     // Get category info for logging
-    let category_info: (String, i64) = tx.query_row(
-        "SELECT bc.category_name, bc.budget_id FROM budget_categories bc 
+    let category_info: (String, i64, f64) = tx.query_row(
+        "SELECT bc.category_name, bc.budget_id, e.amount FROM budget_categories bc 
          JOIN expenses e ON e.category_id = bc.category_id 
          WHERE e.expense_id = ?1",
         [entry_id],
-        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, f64>(2)?))
     ).map_err(|e| format!("Entry or category not found: {}", e))?;
+    let before = row_snapshot(tx, "expenses", entry_id)?;
     
     // Soft delete the entry
     let rows_affected = tx.execute(
@@ -967,12 +1652,10 @@ pub fn soft_delete_category_entry(entry_id: i64, db: State<DbState>) -> Result<(
     
     // Log change history
     let description = format!("Deleted entry {} from {}", entry_id, category_info.0);
-    log_budget_change(&*tx, category_info.1, "entry_delete", Some("expenses"), 
-                     None, None, &description)?;
+    let change_id = log_budget_change(tx, category_info.1, "entry_delete", Some("expenses"), 
+                     Some(&format!("{:.2}", category_info.2)), None, &description)?;
+    attach_row_snapshots(tx, change_id, "expenses", entry_id, before)?;
     
-    tx.commit().map_err(|e| e.to_string())?;
-    
-    println!("Successfully soft deleted entry with ID: {}", entry_id);
     Ok(())
 }
 
@@ -991,9 +1674,11 @@ pub fn set_category_allocated_amount(category_id: i64, amount: f64, db: State<Db
         |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
     ).map_err(|e| format!("Category not found: {}", e))?;
     
-    // Update allocated amount
+    let before = row_snapshot(&tx, "budget_categories", category_id)?;
+    
+    // Update allocated amount; a manually entered amount replaces any formula
     let rows_affected = tx.execute(
-        "UPDATE budget_categories SET allocated_amount = ?1 WHERE category_id = ?2",
+        "UPDATE budget_categories SET allocated_amount = ?1, allocation_type = 'fixed', formula = NULL WHERE category_id = ?2",
         rusqlite::params![amount, category_id],
     ).map_err(|e| e.to_string())?;
     
@@ -1004,8 +1689,12 @@ pub fn set_category_allocated_amount(category_id: i64, amount: f64, db: State<Db
     // Log change history
     let description = format!("Updated allocated for {} ${:.2} → ${:.2}", 
                             category_name, current_amount, amount);
-    log_budget_change(&*tx, budget_id, "allocation_change", Some("allocated_amount"), 
+    let change_id = log_budget_change(&tx, budget_id, "allocation_change", Some("allocated_amount"), 
                      Some(&format!("{:.2}", current_amount)), Some(&format!("{:.2}", amount)), &description)?;
+    attach_row_snapshots(&tx, change_id, "budget_categories", category_id, before)?;
+    
+    // Formulas referencing this category need to follow the new amount
+    recompute_budget_allocations(&tx, budget_id)?;
     
     tx.commit().map_err(|e| e.to_string())?;
     
@@ -1013,6 +1702,255 @@ pub fn set_category_allocated_amount(category_id: i64, amount: f64, db: State<Db
     Ok(())
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocationRuleArgs {
+    pub category_id: i64,
+    pub allocation_type: String,         // "fixed" | "percent_income" | "percent_category" | "formula"
+    pub amount: Option<f64>,             // for "fixed"
+    pub percent: Option<f64>,            // for "percent_income" and "percent_category"
+    pub source_category_id: Option<i64>, // for "percent_category"
+    pub formula: Option<String>,         // for "formula", e.g. "max(200, income * 0.1)"
+}
+
+#[tauri::command]
+pub fn set_category_allocation_rule(args: AllocationRuleArgs, db: State<DbState>) -> Result<(), String> {
+    println!("=== SET_CATEGORY_ALLOCATION_RULE COMMAND CALLED ===");
+    println!("Setting allocation rule: {:?}", args);
+    
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let (category_name, budget_id, current_formula): (String, i64, Option<String>) = tx.query_row(
+        "SELECT category_name, budget_id, formula FROM budget_categories WHERE category_id = ?1",
+        [args.category_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|e| format!("Category not found: {}", e))?;
+    let before = row_snapshot(&tx, "budget_categories", args.category_id)?;
+    
+    let new_formula = match args.allocation_type.as_str() {
+        "fixed" => {
+            let amount = args.amount.ok_or("An amount is required for a fixed allocation")?;
+            tx.execute(
+                "UPDATE budget_categories SET allocated_amount = ?1 WHERE category_id = ?2",
+                rusqlite::params![amount, args.category_id],
+            ).map_err(|e| e.to_string())?;
+            None
+        }
+        "percent_income" => {
+            let percent = args.percent.ok_or("A percentage is required for a percent of income allocation")?;
+            Some(format!("income * {} / 100", percent))
+        }
+        "percent_category" => {
+            let percent = args.percent.ok_or("A percentage is required for a percent of category allocation")?;
+            let source_id = args.source_category_id.ok_or("A source category is required for a percent of category allocation")?;
+            if source_id == args.category_id {
+                return Err("A category cannot be allocated as a percentage of itself".to_string());
+            }
+            
+            let source_name: String = tx.query_row(
+                "SELECT category_name FROM budget_categories WHERE category_id = ?1 AND budget_id = ?2 AND deleted_at IS NULL",
+                rusqlite::params![source_id, budget_id],
+                |row| row.get(0)
+            ).map_err(|_| "Source category not found in this budget".to_string())?;
+            
+            if source_name.contains(']') {
+                return Err(format!("Category '{}' cannot be referenced in a formula", source_name));
+            }
+            Some(format!("[{}] * {} / 100", source_name, percent))
+        }
+        "formula" => {
+            let text = args.formula.as_deref().map(str::trim).unwrap_or("");
+            formula::parse(text).map_err(|e| format!("Invalid formula: {}", e))?;
+            Some(text.to_string())
+        }
+        other => return Err(format!("Unknown allocation type: {}", other)),
+    };
+    
+    tx.execute(
+        "UPDATE budget_categories SET allocation_type = ?1, formula = ?2 WHERE category_id = ?3",
+        rusqlite::params![args.allocation_type, new_formula, args.category_id],
+    ).map_err(|e| e.to_string())?;
+    
+    let description = match &new_formula {
+        Some(f) => format!("Allocation for {} now computed from '{}'", category_name, f),
+        None => format!("Allocation for {} set to a fixed amount", category_name),
+    };
+    let change_id = log_budget_change(&tx, budget_id, "allocation_rule_change", Some("formula"), 
+                     current_formula.as_deref(), new_formula.as_deref(), &description)?;
+    attach_row_snapshots(&tx, change_id, "budget_categories", args.category_id, before)?;
+    
+    // Validates the rule (unknown categories, cycles) and applies the new values
+    recompute_budget_allocations(&tx, budget_id)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("Successfully set allocation rule for category ID: {}", args.category_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_budget_income(budget_id: i64, total_income: f64, db: State<DbState>) -> Result<(), String> {
+    println!("=== UPDATE_BUDGET_INCOME COMMAND CALLED ===");
+    println!("Updating income for budget ID: {} to {}", budget_id, total_income);
+    
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let current_income: f64 = tx.query_row(
+        "SELECT total_income FROM MonthlyBudgets WHERE budget_id = ?1 AND deleted_at IS NULL",
+        [budget_id],
+        |row| row.get(0)
+    ).map_err(|_| format!("Budget with ID {} not found", budget_id))?;
+    let before = row_snapshot(&tx, "MonthlyBudgets", budget_id)?;
+    
+    tx.execute(
+        "UPDATE MonthlyBudgets SET total_income = ?1, last_edited = datetime('now') WHERE budget_id = ?2",
+        rusqlite::params![total_income, budget_id],
+    ).map_err(|e| e.to_string())?;
+    
+    let description = format!("Updated income ${:.2} → ${:.2}", current_income, total_income);
+    let change_id = log_budget_change(&tx, budget_id, "field_change", Some("total_income"), 
+                     Some(&format!("{:.2}", current_income)), Some(&format!("{:.2}", total_income)), &description)?;
+    attach_row_snapshots(&tx, change_id, "MonthlyBudgets", budget_id, before)?;
+    
+    recompute_budget_allocations(&tx, budget_id)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("Successfully updated income for budget ID: {}", budget_id);
+    Ok(())
+}
+
+struct AllocationState {
+    category_id: i64,
+    category_name: String,
+    allocated_amount: f64,
+    formula: Option<formula::Expr>,
+    formula_text: Option<String>,
+}
+
+// Re-evaluates every formula-based allocation of a budget, dependencies first, and logs
+// each value that changed. Returns the number of categories that were updated.
+pub(crate) fn recompute_budget_allocations(conn: &rusqlite::Connection, budget_id: i64) -> Result<usize, String> {
+    let total_income: f64 = conn.query_row(
+        "SELECT total_income FROM MonthlyBudgets WHERE budget_id = ?1",
+        [budget_id],
+        |row| row.get(0)
+    ).map_err(|_| format!("Budget with ID {} not found", budget_id))?;
+    
+    let mut categories: Vec<AllocationState> = {
+        let mut stmt = conn.prepare(
+            "SELECT category_id, category_name, allocated_amount, allocation_type, formula
+             FROM budget_categories
+             WHERE budget_id = ?1 AND deleted_at IS NULL
+             ORDER BY category_id"
+        ).map_err(|e| e.to_string())?;
+        
+        let rows = stmt.query_map([budget_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?,
+                row.get::<_, String>(3)?, row.get::<_, Option<String>>(4)?))
+        }).map_err(|e| e.to_string())?;
+        
+        let mut categories = Vec::new();
+        for row in rows {
+            let (category_id, category_name, allocated_amount, allocation_type, formula_text) = row.map_err(|e| e.to_string())?;
+            let formula_text = formula_text.filter(|_| allocation_type != "fixed");
+            let formula = match &formula_text {
+                Some(text) => Some(formula::parse(text)
+                    .map_err(|e| format!("Invalid formula for {}: {}", category_name, e))?),
+                None => None,
+            };
+            categories.push(AllocationState { category_id, category_name, allocated_amount, formula, formula_text });
+        }
+        categories
+    };
+    
+    let find = |categories: &[AllocationState], name: &str| -> Result<usize, String> {
+        let mut matches = categories.iter().enumerate().filter(|(_, c)| c.category_name.eq_ignore_ascii_case(name));
+        match (matches.next(), matches.next()) {
+            (Some((index, _)), None) => Ok(index),
+            (Some(_), Some(_)) => Err(format!("Formula reference [{}] is ambiguous", name)),
+            _ => Err(format!("Formula references unknown category [{}]", name)),
+        }
+    };
+    
+    // Resolve references and order the categories so dependencies are evaluated first
+    let mut dependencies: Vec<Vec<usize>> = Vec::with_capacity(categories.len());
+    for category in &categories {
+        let mut deps = Vec::new();
+        if let Some(expr) = &category.formula {
+            for name in expr.category_refs() {
+                deps.push(find(&categories, &name)?);
+            }
+        }
+        dependencies.push(deps);
+    }
+    
+    let mut order = Vec::with_capacity(categories.len());
+    let mut state = vec![0u8; categories.len()]; // 0 = unvisited, 1 = in progress, 2 = done
+    for start in 0..categories.len() {
+        if state[start] != 0 {
+            continue;
+        }
+        let mut stack = vec![(start, 0usize)];
+        state[start] = 1;
+        while let Some((node, next_dep)) = stack.pop() {
+            if let Some(&dep) = dependencies[node].get(next_dep) {
+                stack.push((node, next_dep + 1));
+                match state[dep] {
+                    0 => {
+                        state[dep] = 1;
+                        stack.push((dep, 0));
+                    }
+                    1 => {
+                        return Err(format!("Formula for {} creates a circular reference", categories[dep].category_name));
+                    }
+                    _ => {}
+                }
+            } else {
+                state[node] = 2;
+                order.push(node);
+            }
+        }
+    }
+    
+    let mut updated = 0;
+    for index in order {
+        let Some(expr) = &categories[index].formula else {
+            continue;
+        };
+        
+        let value = expr
+            .evaluate(total_income, &|name: &str| find(&categories, name).ok().map(|i| categories[i].allocated_amount))
+            .map_err(|e| format!("Could not evaluate formula for {}: {}", categories[index].category_name, e))?;
+        let value = (value * 100.0).round() / 100.0;
+        
+        let category = &categories[index];
+        if (value - category.allocated_amount).abs() < 0.005 {
+            continue;
+        }
+        
+        let before = row_snapshot(conn, "budget_categories", category.category_id)?;
+        conn.execute(
+            "UPDATE budget_categories SET allocated_amount = ?1 WHERE category_id = ?2",
+            rusqlite::params![value, category.category_id],
+        ).map_err(|e| e.to_string())?;
+        
+        let description = format!("Recomputed allocated for {} ${:.2} → ${:.2} from '{}'", 
+                                category.category_name, category.allocated_amount, value,
+                                category.formula_text.as_deref().unwrap_or(""));
+        let change_id = log_budget_change(conn, budget_id, "allocation_recompute", Some("allocated_amount"), 
+                         Some(&format!("{:.2}", category.allocated_amount)), Some(&format!("{:.2}", value)), &description)?;
+        attach_row_snapshots(conn, change_id, "budget_categories", category.category_id, before)?;
+        
+        categories[index].allocated_amount = value;
+        updated += 1;
+    }
+    
+    Ok(updated)
+}
+
 #[tauri::command]
 pub fn add_budget_category(payload: NewCategory, db: State<DbState>) -> Result<i64, String> {
     println!("=== ADD_BUDGET_CATEGORY COMMAND CALLED ===");
@@ -1021,14 +1959,31 @@ pub fn add_budget_category(payload: NewCategory, db: State<DbState>) -> Result<i
     let mut conn = db.get_conn().map_err(|e| e.to_string())?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
     
+    if let Some(parent_id) = payload.parent_category_id {
+        tx.query_row(
+            "SELECT 1 FROM budget_categories WHERE category_id = ?1 AND budget_id = ?2 AND deleted_at IS NULL",
+            rusqlite::params![parent_id, payload.budget_id],
+            |_| Ok(())
+        ).map_err(|_| "Parent category not found in this budget".to_string())?;
+    }
+    
+    let category_type = payload.category_type.as_deref().unwrap_or("expense");
+    check_category_type(category_type)?;
+    if let Some(global_category_id) = payload.global_category_id {
+        check_global_category(&tx, global_category_id)?;
+    }
+    
     // Insert the category
     tx.execute(
-        "INSERT INTO budget_categories (budget_id, category_name, allocated_amount, created_at) 
-         VALUES (?1, ?2, ?3, datetime('now'))",
+        "INSERT INTO budget_categories (budget_id, category_name, allocated_amount, parent_category_id, global_category_id, category_type, created_at) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))",
         rusqlite::params![
             payload.budget_id,
             payload.category_name,
-            payload.allocated_amount
+            payload.allocated_amount,
+            payload.parent_category_id,
+            payload.global_category_id,
+            category_type
         ],
     ).map_err(|e| e.to_string())?;
     
@@ -1037,8 +1992,9 @@ pub fn add_budget_category(payload: NewCategory, db: State<DbState>) -> Result<i
     // Log change history
     let description = format!("Added category '{}' with allocated ${:.2}", 
                             payload.category_name, payload.allocated_amount);
-    log_budget_change(&*tx, payload.budget_id, "category_add", Some("budget_categories"), 
+    let change_id = log_budget_change(&tx, payload.budget_id, "category_add", Some("budget_categories"), 
                      None, Some(&payload.category_name), &description)?;
+    attach_row_snapshots(&tx, change_id, "budget_categories", category_id, None)?;
     
     tx.commit().map_err(|e| e.to_string())?;
     
@@ -1046,6 +2002,100 @@ pub fn add_budget_category(payload: NewCategory, db: State<DbState>) -> Result<i
     Ok(category_id)
 }
 
+/// Sets whether a budget category is an expense or a savings category, and optionally links
+/// it to a global category. Savings goals follow savings categories of their global category.
+#[tauri::command]
+pub fn set_category_type(
+    category_id: i64,
+    category_type: String,
+    global_category_id: Option<i64>,
+    db: State<DbState>,
+) -> Result<(), String> {
+    println!("=== SET_CATEGORY_TYPE COMMAND CALLED ===");
+    println!("Setting category {} to type {} (global category {:?})", category_id, category_type, global_category_id);
+    
+    check_category_type(&category_type)?;
+    
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let (category_name, budget_id, current_type): (String, i64, Option<String>) = tx.query_row(
+        "SELECT category_name, budget_id, category_type FROM budget_categories WHERE category_id = ?1 AND deleted_at IS NULL",
+        [category_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|_| "Category not found or deleted".to_string())?;
+    if let Some(global_category_id) = global_category_id {
+        check_global_category(&tx, global_category_id)?;
+    }
+    
+    let before = row_snapshot(&tx, "budget_categories", category_id)?;
+    
+    // Without a global category the current link is kept
+    tx.execute(
+        "UPDATE budget_categories SET category_type = ?1, global_category_id = COALESCE(?2, global_category_id) WHERE category_id = ?3",
+        rusqlite::params![category_type, global_category_id, category_id],
+    ).map_err(|e| e.to_string())?;
+    
+    let current_type = current_type.unwrap_or_else(|| "expense".to_string());
+    let description = format!("Changed {} from {} to {} category", category_name, current_type, category_type);
+    let change_id = log_budget_change(&tx, budget_id, "category_type_change", Some("category_type"),
+                     Some(&current_type), Some(&category_type), &description)?;
+    attach_row_snapshots(&tx, change_id, "budget_categories", category_id, before)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("Successfully updated type of category ID: {}", category_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_budget_category(category_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== DELETE_BUDGET_CATEGORY COMMAND CALLED ===");
+    println!("Moving category ID {} to the trash", category_id);
+    
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let (category_name, budget_id): (String, i64) = tx.query_row(
+        "SELECT category_name, budget_id FROM budget_categories WHERE category_id = ?1 AND deleted_at IS NULL",
+        [category_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|_| "Category not found or already deleted".to_string())?;
+    
+    let children: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM budget_categories WHERE parent_category_id = ?1 AND deleted_at IS NULL",
+        [category_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    
+    if children > 0 {
+        return Err(format!("Category {} has sub-categories; move or delete them first", category_name));
+    }
+    
+    check_no_reconciled_entries(&tx, budget_id, Some(category_id))?;
+    
+    let before = row_snapshot(&tx, "budget_categories", category_id)?;
+    
+    // The category's entries stay attached to it and come back with it on restore
+    tx.execute(
+        "UPDATE budget_categories SET deleted_at = datetime('now') WHERE category_id = ?1",
+        [category_id],
+    ).map_err(|e| e.to_string())?;
+    
+    let description = format!("Moved category '{}' to the trash", category_name);
+    let change_id = log_budget_change(&tx, budget_id, "category_delete", Some("budget_categories"), 
+                     Some(&category_name), None, &description)?;
+    attach_row_snapshots(&tx, change_id, "budget_categories", category_id, before)?;
+    
+    // Fails if another category's formula still refers to this one
+    recompute_budget_allocations(&tx, budget_id)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("Successfully moved category {} to the trash", category_id);
+    Ok(())
+}
+
 // Template System Commands
 #[tauri::command]
 pub fn create_template(name: String, db: State<DbState>) -> Result<i64, String> {
@@ -1141,12 +2191,23 @@ pub fn run_migration(db: State<DbState>) -> Result<String, String> {
     let _ = conn.execute("ALTER TABLE MonthlyBudgets ADD COLUMN template_id INTEGER REFERENCES budget_templates(template_id)", []);
     let _ = conn.execute("ALTER TABLE budget_categories ADD COLUMN global_category_id INTEGER REFERENCES global_categories(global_category_id)", []);
     let _ = conn.execute("ALTER TABLE budget_categories ADD COLUMN category_type TEXT DEFAULT 'expense'", []);
+    let _ = conn.execute("ALTER TABLE global_categories ADD COLUMN parent_id INTEGER REFERENCES global_categories(global_category_id)", []);
+    
+    // Alert thresholds tied to a global category go with it; the thresholds table itself is
+    // created by run_migrations, which the app runs first
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS alert_thresholds_cleanup AFTER DELETE ON global_categories BEGIN
+            DELETE FROM alert_thresholds WHERE global_category_id = old.global_category_id;
+        END",
+        [],
+    ).map_err(|e| e.to_string())?;
     
     // Create indexes
     conn.execute("CREATE INDEX IF NOT EXISTS idx_template_categories_template_id ON template_categories(template_id)", []).map_err(|e| e.to_string())?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_template_categories_global_category_id ON template_categories(global_category_id)", []).map_err(|e| e.to_string())?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_budget_categories_global_category_id ON budget_categories(global_category_id)", []).map_err(|e| e.to_string())?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_monthly_budgets_template_id ON MonthlyBudgets(template_id)", []).map_err(|e| e.to_string())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_global_categories_parent_id ON global_categories(parent_id)", []).map_err(|e| e.to_string())?;
     
     // Insert default categories
     let default_categories = [
@@ -1181,6 +2242,7 @@ pub struct GlobalCategory {
     pub global_category_id: i64,
     pub name: String,
     pub description: Option<String>,
+    pub parent_id: Option<i64>,
     pub created_at: String,
 }
 
@@ -1188,6 +2250,8 @@ pub struct GlobalCategory {
 pub struct CreateGlobalCategoryArgs {
     pub name: String,
     pub description: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<i64>, // only used on create; use move_global_category to re-parent
 }
 
 #[tauri::command]
@@ -1195,7 +2259,7 @@ pub fn get_global_categories(db: State<DbState>) -> Result<Vec<GlobalCategory>,
     let conn = db.get_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn.prepare(
-        "SELECT global_category_id, name, description, created_at 
+        "SELECT global_category_id, name, description, parent_id, created_at 
          FROM global_categories 
          ORDER BY name"
     ).map_err(|e| e.to_string())?;
@@ -1205,7 +2269,8 @@ pub fn get_global_categories(db: State<DbState>) -> Result<Vec<GlobalCategory>,
             global_category_id: row.get(0)?,
             name: row.get(1)?,
             description: row.get(2)?,
-            created_at: row.get(3)?,
+            parent_id: row.get(3)?,
+            created_at: row.get(4)?,
         })
     }).map_err(|e| e.to_string())?;
     
@@ -1221,22 +2286,31 @@ pub fn get_global_categories(db: State<DbState>) -> Result<Vec<GlobalCategory>,
 pub fn create_global_category(db: State<DbState>, args: CreateGlobalCategoryArgs) -> Result<GlobalCategory, String> {
     let conn = db.get_conn().map_err(|e| e.to_string())?;
     
+    if let Some(parent_id) = args.parent_id {
+        conn.query_row(
+            "SELECT 1 FROM global_categories WHERE global_category_id = ?1",
+            [parent_id],
+            |_| Ok(())
+        ).map_err(|_| "Parent category not found".to_string())?;
+    }
+    
     conn.execute(
-        "INSERT INTO global_categories (name, description) VALUES (?1, ?2)",
-        rusqlite::params![args.name, args.description],
+        "INSERT INTO global_categories (name, description, parent_id) VALUES (?1, ?2, ?3)",
+        rusqlite::params![args.name, args.description, args.parent_id],
     ).map_err(|e| format!("Failed to create category: {}", e))?;
     
     let category_id = conn.last_insert_rowid();
     
     // Get the created category
     let category: GlobalCategory = conn.query_row(
-        "SELECT global_category_id, name, description, created_at FROM global_categories WHERE global_category_id = ?1",
+        "SELECT global_category_id, name, description, parent_id, created_at FROM global_categories WHERE global_category_id = ?1",
         [category_id],
         |row| Ok(GlobalCategory {
             global_category_id: row.get(0)?,
             name: row.get(1)?,
             description: row.get(2)?,
-            created_at: row.get(3)?,
+            parent_id: row.get(3)?,
+            created_at: row.get(4)?,
         })
     ).map_err(|e| format!("Failed to retrieve created category: {}", e))?;
     
@@ -1258,13 +2332,14 @@ pub fn update_global_category(db: State<DbState>, category_id: i64, args: Create
     
     // Get the updated category
     let category: GlobalCategory = conn.query_row(
-        "SELECT global_category_id, name, description, created_at FROM global_categories WHERE global_category_id = ?1",
+        "SELECT global_category_id, name, description, parent_id, created_at FROM global_categories WHERE global_category_id = ?1",
         [category_id],
         |row| Ok(GlobalCategory {
             global_category_id: row.get(0)?,
             name: row.get(1)?,
             description: row.get(2)?,
-            created_at: row.get(3)?,
+            parent_id: row.get(3)?,
+            created_at: row.get(4)?,
         })
     ).map_err(|e| format!("Failed to retrieve updated category: {}", e))?;
     
@@ -1273,9 +2348,18 @@ pub fn update_global_category(db: State<DbState>, category_id: i64, args: Create
 
 #[tauri::command]
 pub fn delete_global_category(db: State<DbState>, category_id: i64) -> Result<(), String> {
-    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
     
-    let rows_affected = conn.execute(
+    // Sub-categories move up to the deleted category's parent
+    tx.execute(
+        "UPDATE global_categories 
+         SET parent_id = (SELECT parent_id FROM global_categories WHERE global_category_id = ?1)
+         WHERE parent_id = ?1",
+        [category_id],
+    ).map_err(|e| format!("Failed to re-parent sub-categories: {}", e))?;
+    
+    let rows_affected = tx.execute(
         "DELETE FROM global_categories WHERE global_category_id = ?1",
         [category_id],
     ).map_err(|e| format!("Failed to delete category: {}", e))?;
@@ -1284,9 +2368,61 @@ pub fn delete_global_category(db: State<DbState>, category_id: i64) -> Result<()
         return Err("Category not found".to_string());
     }
     
+    tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
+#[tauri::command]
+pub fn move_global_category(db: State<DbState>, category_id: i64, parent_id: Option<i64>) -> Result<GlobalCategory, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    
+    if let Some(parent_id) = parent_id {
+        // Walk up from the new parent; reaching the moved category means a cycle
+        let creates_cycle: i64 = conn.query_row(
+            "WITH RECURSIVE chain(id) AS (
+                 SELECT ?2
+                 UNION
+                 SELECT g.parent_id FROM global_categories g JOIN chain ON g.global_category_id = chain.id
+                 WHERE g.parent_id IS NOT NULL
+             )
+             SELECT COUNT(*) FROM chain WHERE id = ?1",
+            rusqlite::params![category_id, parent_id],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+        
+        if creates_cycle > 0 {
+            return Err("A category cannot be moved under itself or one of its sub-categories".to_string());
+        }
+        
+        conn.query_row(
+            "SELECT 1 FROM global_categories WHERE global_category_id = ?1",
+            [parent_id],
+            |_| Ok(())
+        ).map_err(|_| "Parent category not found".to_string())?;
+    }
+    
+    let rows_affected = conn.execute(
+        "UPDATE global_categories SET parent_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE global_category_id = ?2",
+        rusqlite::params![parent_id, category_id],
+    ).map_err(|e| format!("Failed to move category: {}", e))?;
+    
+    if rows_affected == 0 {
+        return Err("Category not found".to_string());
+    }
+    
+    conn.query_row(
+        "SELECT global_category_id, name, description, parent_id, created_at FROM global_categories WHERE global_category_id = ?1",
+        [category_id],
+        |row| Ok(GlobalCategory {
+            global_category_id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            parent_id: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    ).map_err(|e| format!("Failed to retrieve moved category: {}", e))
+}
+
 // ===== BUDGET TEMPLATES MANAGEMENT =====
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1312,6 +2448,7 @@ pub struct BudgetTemplateWithCategories {
 pub struct TemplateCategoryItem {
     pub template_category_id: i64,
     pub global_category_id: i64,
+    pub parent_global_category_id: Option<i64>,
     pub category_name: String,
     pub allocated_amount: f64,
     pub category_type: String,
@@ -1379,7 +2516,7 @@ pub fn get_budget_template_with_categories(db: State<DbState>, template_id: i64)
     
     // Get template categories
     let mut stmt = conn.prepare(
-        "SELECT tc.template_category_id, tc.global_category_id, gc.name, 
+        "SELECT tc.template_category_id, tc.global_category_id, gc.parent_id, gc.name, 
                 tc.allocated_amount, tc.category_type, tc.sort_order
          FROM template_categories tc
          JOIN global_categories gc ON tc.global_category_id = gc.global_category_id
@@ -1391,10 +2528,11 @@ pub fn get_budget_template_with_categories(db: State<DbState>, template_id: i64)
         Ok(TemplateCategoryItem {
             template_category_id: row.get(0)?,
             global_category_id: row.get(1)?,
-            category_name: row.get(2)?,
-            allocated_amount: row.get(3)?,
-            category_type: row.get(4)?,
-            sort_order: row.get(5)?,
+            parent_global_category_id: row.get(2)?,
+            category_name: row.get(3)?,
+            allocated_amount: row.get(4)?,
+            category_type: row.get(5)?,
+            sort_order: row.get(6)?,
         })
     }).map_err(|e| e.to_string())?;
     
@@ -1511,7 +2649,7 @@ pub fn apply_template_to_budget(budget_id: i64, template_id: i64, db: State<DbSt
     
     // Verify budget exists
     let _budget_exists: bool = tx.query_row(
-        "SELECT 1 FROM monthly_budgets WHERE budget_id = ?1",
+        "SELECT 1 FROM MonthlyBudgets WHERE budget_id = ?1 AND deleted_at IS NULL",
         [budget_id],
         |_| Ok(true)
     ).map_err(|_| "Budget not found".to_string())?;
@@ -1524,16 +2662,41 @@ pub fn apply_template_to_budget(budget_id: i64, template_id: i64, db: State<DbSt
     ).map_err(|_| "Template not found".to_string())?;
     
     // Update budget to use this template
+    let budget_before = row_snapshot(&tx, "MonthlyBudgets", budget_id)?;
     tx.execute(
-        "UPDATE monthly_budgets SET template_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE budget_id = ?2",
+        "UPDATE MonthlyBudgets SET template_id = ?1, last_edited = datetime('now') WHERE budget_id = ?2",
         rusqlite::params![template_id, budget_id],
     ).map_err(|e| e.to_string())?;
     
-    // Clear existing budget categories
-    tx.execute(
-        "DELETE FROM budget_categories WHERE budget_id = ?1",
-        [budget_id],
-    ).map_err(|e| e.to_string())?;
+    // Existing categories go to the trash, so their entries can come back with them.
+    // Each one is logged on its own so it can be brought back with undo_change.
+    let existing_categories: Vec<(i64, String)> = {
+        let mut stmt = tx.prepare(
+            "SELECT category_id, category_name FROM budget_categories WHERE budget_id = ?1 AND deleted_at IS NULL"
+        ).map_err(|e| e.to_string())?;
+        
+        let rows = stmt.query_map([budget_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        
+        let mut categories = Vec::new();
+        for row_result in rows {
+            categories.push(row_result.map_err(|e| e.to_string())?);
+        }
+        categories
+    };
+    
+    for (category_id, category_name) in &existing_categories {
+        let before = row_snapshot(&tx, "budget_categories", *category_id)?;
+        tx.execute(
+            "UPDATE budget_categories SET deleted_at = datetime('now') WHERE category_id = ?1",
+            [category_id],
+        ).map_err(|e| e.to_string())?;
+        
+        let description = format!("Moved category '{}' to the trash to apply template '{}'", category_name, template_name);
+        let change_id = log_budget_change(&tx, budget_id, "category_delete", Some("budget_categories"), 
+                         Some(category_name.as_str()), None, &description)?;
+        attach_row_snapshots(&tx, change_id, "budget_categories", *category_id, before)?;
+    }
     
     // Get template categories and create budget categories
     let template_categories: Vec<(i64, String, f64, String)> = {
@@ -1561,7 +2724,7 @@ pub fn apply_template_to_budget(budget_id: i64, template_id: i64, db: State<DbSt
         categories
     };
     
-    let mut created_count = 0;
+    let mut created: Vec<(i64, String, f64)> = Vec::new();
     for (global_category_id, category_name, allocated_amount, category_type) in template_categories {
         tx.execute(
             "INSERT INTO budget_categories (budget_id, global_category_id, category_name, allocated_amount, category_type, created_at) 
@@ -1569,24 +2732,38 @@ pub fn apply_template_to_budget(budget_id: i64, template_id: i64, db: State<DbSt
             rusqlite::params![budget_id, global_category_id, category_name, allocated_amount, category_type],
         ).map_err(|e| e.to_string())?;
         
-        created_count += 1;
+        created.push((tx.last_insert_rowid(), category_name, allocated_amount));
     }
+    let created_count = created.len();
     
-    // Log change history
-    let description = format!("Applied template '{}' to budget ({} categories)", template_name, created_count);
+    // Rebuild the hierarchy from the global categories' parent links
     tx.execute(
-        "INSERT INTO BudgetChangeHistory (budget_id, change_type, field_name, old_value, new_value, change_description) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        rusqlite::params![
-            budget_id, 
-            "template_apply", 
-            Some("budget_templates"), 
-            None as Option<String>, 
-            Some(&template_name), 
-            &description
-        ],
+        "UPDATE budget_categories
+         SET parent_category_id = (
+             SELECT p.category_id
+             FROM global_categories g
+             JOIN budget_categories p ON p.global_category_id = g.parent_id AND p.budget_id = budget_categories.budget_id
+                                     AND p.deleted_at IS NULL
+             WHERE g.global_category_id = budget_categories.global_category_id
+         )
+         WHERE budget_id = ?1 AND deleted_at IS NULL",
+        [budget_id],
     ).map_err(|e| e.to_string())?;
     
+    // Log change history, after the hierarchy rebuild so the snapshots include the parents
+    for (category_id, category_name, allocated_amount) in &created {
+        let description = format!("Added category '{}' with allocated ${:.2} from template '{}'", 
+                                category_name, allocated_amount, template_name);
+        let change_id = log_budget_change(&tx, budget_id, "category_add", Some("budget_categories"), 
+                         None, Some(category_name.as_str()), &description)?;
+        attach_row_snapshots(&tx, change_id, "budget_categories", *category_id, None)?;
+    }
+    
+    let description = format!("Applied template '{}' to budget ({} categories)", template_name, created_count);
+    let change_id = log_budget_change(&tx, budget_id, "template_apply", Some("template_id"), 
+                     None, Some(&template_name), &description)?;
+    attach_row_snapshots(&tx, change_id, "MonthlyBudgets", budget_id, budget_before)?;
+    
     tx.commit().map_err(|e| e.to_string())?;
     
     println!("Successfully applied template '{}' with {} categories", template_name, created_count);
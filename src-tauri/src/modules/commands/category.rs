@@ -0,0 +1,734 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::modules::commands::budget::{log_budget_change, recompute_budget_allocations};
+use crate::modules::commands::reconcile::check_not_reconciled;
+use crate::modules::database::DbState;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitCategoryArgs {
+    pub category_id: i64,
+    pub new_category_name: String,
+    pub entry_ids: Vec<i64>,
+    pub allocated_amount: Option<f64>, // moved from the source category, defaults to 0
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryOperation {
+    pub merge_id: i64,
+    pub operation: String, // "merge" | "split"
+    pub source_category_id: i64,
+    pub target_category_id: i64,
+    pub merge_date: String,
+    pub reverted_at: Option<String>,
+}
+
+// Everything a merge changed, stored as JSON in CategoryMergeHistory.details
+#[derive(Debug, Serialize, Deserialize)]
+struct MergeDetails {
+    source_name: String,
+    source_description: Option<String>,
+    source_parent_id: Option<i64>,
+    source_created_at: String,
+    target_name: String,
+    target_previous_parent_id: Option<i64>,
+    reparented_children: Vec<i64>,
+    budgets: Vec<MergedBudgetCategory>,
+    templates: Vec<MergedTemplateCategory>,
+    formulas: Vec<RewrittenFormula>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MergedBudgetCategory {
+    category_id: i64,
+    budget_id: i64,
+    category_name: String,
+    allocated_amount: f64,
+    category_type: Option<String>,
+    allocation_type: String,
+    formula: Option<String>,
+    parent_category_id: Option<i64>,
+    created_at: String,
+    // Set when the budget already had the target category and the rows were combined
+    merged_into: Option<i64>,
+    moved_entry_ids: Vec<i64>,
+    #[serde(default)]
+    moved_split_ids: Vec<i64>,
+    reparented_children: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MergedTemplateCategory {
+    template_category_id: i64,
+    template_id: i64,
+    allocated_amount: f64,
+    category_type: String,
+    sort_order: i32,
+    merged_into: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RewrittenFormula {
+    category_id: i64,
+    formula: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SplitDetails {
+    budget_id: i64,
+    entry_ids: Vec<i64>,
+    allocated_amount: f64,
+}
+
+fn query_ids(conn: &Connection, sql: &str, id: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([id], |row| row.get(0)).map_err(|e| e.to_string())?;
+    let mut ids = Vec::new();
+    for row in rows {
+        ids.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(ids)
+}
+
+// Formulas reference categories by name, so a renamed category has to be renamed there too
+fn rewrite_formula_refs(
+    conn: &Connection,
+    budget_id: i64,
+    old_name: &str,
+    new_name: &str,
+    rewritten: &mut Vec<RewrittenFormula>,
+) -> Result<(), String> {
+    if old_name.eq_ignore_ascii_case(new_name) {
+        return Ok(());
+    }
+
+    let formulas: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT category_id, formula FROM budget_categories
+             WHERE budget_id = ?1 AND formula IS NOT NULL AND instr(formula, '[') > 0"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([budget_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        let mut formulas = Vec::new();
+        for row in rows {
+            formulas.push(row.map_err(|e| e.to_string())?);
+        }
+        formulas
+    };
+
+    for (category_id, formula) in formulas {
+        let updated = replace_category_ref(&formula, old_name, new_name);
+        if updated == formula {
+            continue;
+        }
+        conn.execute(
+            "UPDATE budget_categories SET formula = ?1 WHERE category_id = ?2",
+            rusqlite::params![updated, category_id],
+        ).map_err(|e| e.to_string())?;
+        rewritten.push(RewrittenFormula { category_id, formula });
+    }
+    Ok(())
+}
+
+// Replaces the `[old_name]` references in a formula, matching them the way formulas resolve
+// them: trimmed and ignoring ASCII case
+fn replace_category_ref(formula: &str, old_name: &str, new_name: &str) -> String {
+    let mut result = String::with_capacity(formula.len());
+    let mut rest = formula;
+    while let Some(open) = rest.find('[') {
+        let close = match rest[open..].find(']') {
+            Some(offset) => open + offset,
+            None => break,
+        };
+        result.push_str(&rest[..open]);
+        if rest[open + 1..close].trim().eq_ignore_ascii_case(old_name) {
+            result.push_str(&format!("[{}]", new_name));
+        } else {
+            result.push_str(&rest[open..=close]);
+        }
+        rest = &rest[close + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+// Formula allocations may refer to the categories a merge or split changed
+fn recompute_budgets(conn: &Connection, mut budget_ids: Vec<i64>) -> Result<(), String> {
+    budget_ids.sort_unstable();
+    budget_ids.dedup();
+    for budget_id in budget_ids {
+        recompute_budget_allocations(conn, budget_id)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn merge_global_categories(source_category_id: i64, target_category_id: i64, db: State<DbState>) -> Result<i64, String> {
+    println!("=== MERGE_GLOBAL_CATEGORIES COMMAND CALLED ===");
+    println!("Merging global category {} into {}", source_category_id, target_category_id);
+
+    if source_category_id == target_category_id {
+        return Err("A category cannot be merged into itself".to_string());
+    }
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (source_name, source_description, source_parent_id, source_created_at): (String, Option<String>, Option<i64>, String) = tx.query_row(
+        "SELECT name, description, parent_id, created_at FROM global_categories WHERE global_category_id = ?1",
+        [source_category_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+    ).map_err(|_| "Source category not found".to_string())?;
+
+    let (target_name, target_parent_id): (String, Option<i64>) = tx.query_row(
+        "SELECT name, parent_id FROM global_categories WHERE global_category_id = ?1",
+        [target_category_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|_| "Target category not found".to_string())?;
+
+    // The source's sub-categories move to the target; a target that was itself a
+    // sub-category of the source takes over the source's place in the tree
+    let reparented_children = query_ids(
+        &tx,
+        "SELECT global_category_id FROM global_categories WHERE parent_id = ?1",
+        source_category_id,
+    )?
+    .into_iter()
+    .filter(|id| *id != target_category_id)
+    .collect::<Vec<_>>();
+
+    tx.execute(
+        "UPDATE global_categories SET parent_id = ?1 WHERE parent_id = ?2 AND global_category_id != ?3",
+        rusqlite::params![target_category_id, source_category_id, target_category_id],
+    ).map_err(|e| e.to_string())?;
+
+    if target_parent_id == Some(source_category_id) {
+        tx.execute(
+            "UPDATE global_categories SET parent_id = ?1 WHERE global_category_id = ?2",
+            rusqlite::params![source_parent_id, target_category_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    // Budget categories: combine with the target where the budget has both, re-point otherwise.
+    // Trashed rows are only re-pointed, so they never take in or give up live entries.
+    let source_rows: Vec<(MergedBudgetCategory, bool)> = {
+        let mut stmt = tx.prepare(
+            "SELECT category_id, budget_id, category_name, allocated_amount, category_type,
+                    allocation_type, formula, parent_category_id, created_at, deleted_at IS NOT NULL
+             FROM budget_categories WHERE global_category_id = ?1"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([source_category_id], |row| {
+            Ok((MergedBudgetCategory {
+                category_id: row.get(0)?,
+                budget_id: row.get(1)?,
+                category_name: row.get(2)?,
+                allocated_amount: row.get(3)?,
+                category_type: row.get(4)?,
+                allocation_type: row.get(5)?,
+                formula: row.get(6)?,
+                parent_category_id: row.get(7)?,
+                created_at: row.get(8)?,
+                merged_into: None,
+                moved_entry_ids: Vec::new(),
+                moved_split_ids: Vec::new(),
+                reparented_children: Vec::new(),
+            }, row.get(9)?))
+        }).map_err(|e| e.to_string())?;
+        let mut source_rows = Vec::new();
+        for row in rows {
+            source_rows.push(row.map_err(|e| e.to_string())?);
+        }
+        source_rows
+    };
+
+    let mut budgets = Vec::new();
+    let mut formulas = Vec::new();
+    for (mut source, trashed) in source_rows {
+        if trashed {
+            tx.execute(
+                "UPDATE budget_categories SET global_category_id = ?1 WHERE category_id = ?2",
+                rusqlite::params![target_category_id, source.category_id],
+            ).map_err(|e| e.to_string())?;
+            budgets.push(source);
+            continue;
+        }
+
+        let targets: Vec<(i64, String)> = {
+            let mut stmt = tx.prepare(
+                "SELECT category_id, category_name FROM budget_categories
+                 WHERE budget_id = ?1 AND global_category_id = ?2 AND deleted_at IS NULL"
+            ).map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(rusqlite::params![source.budget_id, target_category_id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?;
+            let mut targets = Vec::new();
+            for row in rows {
+                targets.push(row.map_err(|e| e.to_string())?);
+            }
+            targets
+        };
+        if targets.len() > 1 {
+            return Err(format!(
+                "Budget {} has more than one live {} category; merge or delete the extra ones first",
+                source.budget_id, target_name
+            ));
+        }
+        let target = targets.into_iter().next();
+
+        match target {
+            Some((target_id, target_category_name)) => {
+                source.moved_entry_ids = query_ids(&tx, "SELECT expense_id FROM expenses WHERE category_id = ?1", source.category_id)?;
+                for entry_id in &source.moved_entry_ids {
+                    check_not_reconciled(&tx, *entry_id)?;
+                }
+                tx.execute(
+                    "UPDATE expenses SET category_id = ?1 WHERE category_id = ?2",
+                    rusqlite::params![target_id, source.category_id],
+                ).map_err(|e| e.to_string())?;
+
+                source.moved_split_ids = query_ids(&tx, "SELECT split_id FROM entry_splits WHERE category_id = ?1", source.category_id)?;
+                tx.execute(
+                    "UPDATE entry_splits SET category_id = ?1 WHERE category_id = ?2",
+                    rusqlite::params![target_id, source.category_id],
+                ).map_err(|e| e.to_string())?;
+
+                source.reparented_children = query_ids(
+                    &tx,
+                    "SELECT category_id FROM budget_categories WHERE parent_category_id = ?1",
+                    source.category_id,
+                )?
+                .into_iter()
+                .filter(|id| *id != target_id)
+                .collect();
+                tx.execute(
+                    "UPDATE budget_categories SET parent_category_id = ?1 WHERE parent_category_id = ?2 AND category_id != ?1",
+                    rusqlite::params![target_id, source.category_id],
+                ).map_err(|e| e.to_string())?;
+
+                tx.execute(
+                    "UPDATE budget_categories SET allocated_amount = allocated_amount + ?1 WHERE category_id = ?2",
+                    rusqlite::params![source.allocated_amount, target_id],
+                ).map_err(|e| e.to_string())?;
+
+                tx.execute("DELETE FROM budget_categories WHERE category_id = ?1", [source.category_id])
+                    .map_err(|e| e.to_string())?;
+
+                rewrite_formula_refs(&tx, source.budget_id, &source.category_name, &target_category_name, &mut formulas)?;
+
+                let description = format!("Merged category {} into {} (${:.2} allocated, {} entries moved)",
+                                        source.category_name, target_category_name,
+                                        source.allocated_amount, source.moved_entry_ids.len());
+                log_budget_change(&tx, source.budget_id, "category_merge", Some("budget_categories"),
+                                 Some(&source.category_name), Some(&target_category_name), &description)?;
+
+                source.merged_into = Some(target_id);
+            }
+            None => {
+                tx.execute(
+                    "UPDATE budget_categories SET global_category_id = ?1, category_name = ?2 WHERE category_id = ?3",
+                    rusqlite::params![target_category_id, target_name, source.category_id],
+                ).map_err(|e| e.to_string())?;
+
+                rewrite_formula_refs(&tx, source.budget_id, &source.category_name, &target_name, &mut formulas)?;
+
+                let description = format!("Category {} renamed to {} by a category merge", source.category_name, target_name);
+                log_budget_change(&tx, source.budget_id, "category_merge", Some("category_name"),
+                                 Some(&source.category_name), Some(&target_name), &description)?;
+            }
+        }
+        budgets.push(source);
+    }
+
+    // Template categories follow the same rules
+    let template_rows: Vec<MergedTemplateCategory> = {
+        let mut stmt = tx.prepare(
+            "SELECT template_category_id, template_id, allocated_amount, category_type, sort_order
+             FROM template_categories WHERE global_category_id = ?1"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([source_category_id], |row| {
+            Ok(MergedTemplateCategory {
+                template_category_id: row.get(0)?,
+                template_id: row.get(1)?,
+                allocated_amount: row.get(2)?,
+                category_type: row.get(3)?,
+                sort_order: row.get(4)?,
+                merged_into: None,
+            })
+        }).map_err(|e| e.to_string())?;
+        let mut template_rows = Vec::new();
+        for row in rows {
+            template_rows.push(row.map_err(|e| e.to_string())?);
+        }
+        template_rows
+    };
+
+    let mut templates = Vec::new();
+    for mut source in template_rows {
+        let target_id: Option<i64> = tx.query_row(
+            "SELECT template_category_id FROM template_categories WHERE template_id = ?1 AND global_category_id = ?2",
+            rusqlite::params![source.template_id, target_category_id],
+            |row| row.get(0)
+        ).optional().map_err(|e| e.to_string())?;
+
+        match target_id {
+            Some(target_id) => {
+                tx.execute(
+                    "UPDATE template_categories SET allocated_amount = allocated_amount + ?1 WHERE template_category_id = ?2",
+                    rusqlite::params![source.allocated_amount, target_id],
+                ).map_err(|e| e.to_string())?;
+                tx.execute("DELETE FROM template_categories WHERE template_category_id = ?1", [source.template_category_id])
+                    .map_err(|e| e.to_string())?;
+                source.merged_into = Some(target_id);
+            }
+            None => {
+                tx.execute(
+                    "UPDATE template_categories SET global_category_id = ?1 WHERE template_category_id = ?2",
+                    rusqlite::params![target_category_id, source.template_category_id],
+                ).map_err(|e| e.to_string())?;
+            }
+        }
+        templates.push(source);
+    }
+
+    tx.execute("DELETE FROM global_categories WHERE global_category_id = ?1", [source_category_id])
+        .map_err(|e| format!("Failed to delete merged category: {}", e))?;
+
+    recompute_budgets(&tx, budgets.iter().map(|b| b.budget_id).collect())?;
+
+    let details = MergeDetails {
+        source_name,
+        source_description,
+        source_parent_id,
+        source_created_at,
+        target_name,
+        target_previous_parent_id: target_parent_id,
+        reparented_children,
+        budgets,
+        templates,
+        formulas,
+    };
+    let details_json = serde_json::to_string(&details).map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO CategoryMergeHistory (source_category_id, target_category_id, operation, details) VALUES (?1, ?2, 'merge', ?3)",
+        rusqlite::params![source_category_id, target_category_id, details_json],
+    ).map_err(|e| e.to_string())?;
+    let merge_id = tx.last_insert_rowid();
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Successfully merged global category {} into {} (merge ID: {})", source_category_id, target_category_id, merge_id);
+    Ok(merge_id)
+}
+
+#[tauri::command]
+pub fn split_budget_category(args: SplitCategoryArgs, db: State<DbState>) -> Result<i64, String> {
+    println!("=== SPLIT_BUDGET_CATEGORY COMMAND CALLED ===");
+    println!("Splitting category: {:?}", args);
+
+    let new_name = args.new_category_name.trim();
+    if new_name.is_empty() {
+        return Err("The new category needs a name".to_string());
+    }
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (category_name, budget_id, category_type, parent_category_id): (String, i64, Option<String>, Option<i64>) = tx.query_row(
+        "SELECT c.category_name, c.budget_id, c.category_type, c.parent_category_id
+         FROM budget_categories c
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+         WHERE c.category_id = ?1 AND c.deleted_at IS NULL AND b.deleted_at IS NULL",
+        [args.category_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+    ).map_err(|_| "Category not found or in the trash".to_string())?;
+
+    let allocated_amount = args.allocated_amount.unwrap_or(0.0);
+
+    tx.execute(
+        "INSERT INTO budget_categories (budget_id, category_name, allocated_amount, category_type, parent_category_id, created_at)
+         VALUES (?1, ?2, ?3, COALESCE(?4, 'expense'), ?5, datetime('now'))",
+        rusqlite::params![budget_id, new_name, allocated_amount, category_type, parent_category_id],
+    ).map_err(|e| e.to_string())?;
+    let new_category_id = tx.last_insert_rowid();
+
+    for entry_id in &args.entry_ids {
+        check_not_reconciled(&tx, *entry_id)?;
+        let moved = tx.execute(
+            "UPDATE expenses SET category_id = ?1 WHERE expense_id = ?2 AND category_id = ?3 AND deleted_at IS NULL",
+            rusqlite::params![new_category_id, entry_id, args.category_id],
+        ).map_err(|e| e.to_string())?;
+
+        if moved == 0 {
+            return Err(format!("Entry {} does not belong to {} or is in the trash", entry_id, category_name));
+        }
+    }
+
+    if allocated_amount != 0.0 {
+        tx.execute(
+            "UPDATE budget_categories SET allocated_amount = allocated_amount - ?1, allocation_type = 'fixed', formula = NULL WHERE category_id = ?2",
+            rusqlite::params![allocated_amount, args.category_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    recompute_budgets(&tx, vec![budget_id])?;
+
+    let details = SplitDetails {
+        budget_id,
+        entry_ids: args.entry_ids.clone(),
+        allocated_amount,
+    };
+    let details_json = serde_json::to_string(&details).map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO CategoryMergeHistory (source_category_id, target_category_id, operation, details) VALUES (?1, ?2, 'split', ?3)",
+        rusqlite::params![args.category_id, new_category_id, details_json],
+    ).map_err(|e| e.to_string())?;
+
+    let description = format!("Split {} entries and ${:.2} from {} into new category {}",
+                            args.entry_ids.len(), allocated_amount, category_name, new_name);
+    log_budget_change(&tx, budget_id, "category_split", Some("budget_categories"),
+                     Some(&category_name), Some(new_name), &description)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Successfully split category {} into {}", args.category_id, new_category_id);
+    Ok(new_category_id)
+}
+
+#[tauri::command]
+pub fn get_category_operations(db: State<DbState>) -> Result<Vec<CategoryOperation>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT merge_id, operation, source_category_id, target_category_id, merge_date, reverted_at
+         FROM CategoryMergeHistory
+         ORDER BY merge_id DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(CategoryOperation {
+            merge_id: row.get(0)?,
+            operation: row.get(1)?,
+            source_category_id: row.get(2)?,
+            target_category_id: row.get(3)?,
+            merge_date: row.get(4)?,
+            reverted_at: row.get(5)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut operations = Vec::new();
+    for row in rows {
+        operations.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(operations)
+}
+
+#[tauri::command]
+pub fn revert_category_operation(merge_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== REVERT_CATEGORY_OPERATION COMMAND CALLED ===");
+    println!("Reverting category operation ID: {}", merge_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (operation, source_id, target_id, details, reverted_at): (String, i64, i64, Option<String>, Option<String>) = tx.query_row(
+        "SELECT operation, source_category_id, target_category_id, details, reverted_at FROM CategoryMergeHistory WHERE merge_id = ?1",
+        [merge_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+    ).map_err(|_| format!("Category operation {} not found", merge_id))?;
+
+    if reverted_at.is_some() {
+        return Err("This operation has already been reverted".to_string());
+    }
+    let details = details.ok_or("This operation was recorded without the details needed to revert it")?;
+
+    // Later operations on the same categories were built on top of this one
+    let later: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM CategoryMergeHistory
+         WHERE merge_id > ?1 AND reverted_at IS NULL AND operation = ?2
+           AND (source_category_id IN (?3, ?4) OR target_category_id IN (?3, ?4))",
+        rusqlite::params![merge_id, operation, source_id, target_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    if later > 0 {
+        return Err("Later merges or splits involve these categories; revert those first".to_string());
+    }
+
+    match operation.as_str() {
+        "merge" => {
+            let details: MergeDetails = serde_json::from_str(&details).map_err(|e| e.to_string())?;
+            revert_merge(&tx, source_id, target_id, details)?;
+        }
+        "split" => {
+            let details: SplitDetails = serde_json::from_str(&details).map_err(|e| e.to_string())?;
+            revert_split(&tx, source_id, target_id, details)?;
+        }
+        other => return Err(format!("Unknown category operation: {}", other)),
+    }
+
+    tx.execute(
+        "UPDATE CategoryMergeHistory SET reverted_at = datetime('now') WHERE merge_id = ?1",
+        [merge_id],
+    ).map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Successfully reverted category operation ID: {}", merge_id);
+    Ok(())
+}
+
+fn revert_merge(conn: &Connection, source_id: i64, target_id: i64, details: MergeDetails) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO global_categories (global_category_id, name, description, parent_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![source_id, details.source_name, details.source_description, details.source_parent_id, details.source_created_at],
+    ).map_err(|e| format!("Failed to restore category {}: {}", details.source_name, e))?;
+
+    for child_id in &details.reparented_children {
+        conn.execute(
+            "UPDATE global_categories SET parent_id = ?1 WHERE global_category_id = ?2 AND parent_id = ?3",
+            rusqlite::params![source_id, child_id, target_id],
+        ).map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "UPDATE global_categories SET parent_id = ?1 WHERE global_category_id = ?2",
+        rusqlite::params![details.target_previous_parent_id, target_id],
+    ).map_err(|e| e.to_string())?;
+
+    for source in &details.budgets {
+        match source.merged_into {
+            Some(merged_into) => {
+                conn.execute(
+                    "INSERT INTO budget_categories (category_id, budget_id, global_category_id, category_name, allocated_amount,
+                                                    category_type, allocation_type, formula, parent_category_id, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    rusqlite::params![
+                        source.category_id, source.budget_id, source_id, source.category_name, source.allocated_amount,
+                        source.category_type, source.allocation_type, source.formula, source.parent_category_id, source.created_at
+                    ],
+                ).map_err(|e| format!("Failed to restore category {}: {}", source.category_name, e))?;
+
+                for entry_id in &source.moved_entry_ids {
+                    conn.execute(
+                        "UPDATE expenses SET category_id = ?1 WHERE expense_id = ?2 AND category_id = ?3",
+                        rusqlite::params![source.category_id, entry_id, merged_into],
+                    ).map_err(|e| e.to_string())?;
+                }
+                for split_id in &source.moved_split_ids {
+                    conn.execute(
+                        "UPDATE entry_splits SET category_id = ?1 WHERE split_id = ?2 AND category_id = ?3",
+                        rusqlite::params![source.category_id, split_id, merged_into],
+                    ).map_err(|e| e.to_string())?;
+                }
+                for child_id in &source.reparented_children {
+                    conn.execute(
+                        "UPDATE budget_categories SET parent_category_id = ?1 WHERE category_id = ?2 AND parent_category_id = ?3",
+                        rusqlite::params![source.category_id, child_id, merged_into],
+                    ).map_err(|e| e.to_string())?;
+                }
+                conn.execute(
+                    "UPDATE budget_categories SET allocated_amount = allocated_amount - ?1 WHERE category_id = ?2",
+                    rusqlite::params![source.allocated_amount, merged_into],
+                ).map_err(|e| e.to_string())?;
+            }
+            None => {
+                conn.execute(
+                    "UPDATE budget_categories SET global_category_id = ?1, category_name = ?2 WHERE category_id = ?3",
+                    rusqlite::params![source_id, source.category_name, source.category_id],
+                ).map_err(|e| e.to_string())?;
+            }
+        }
+
+        let description = format!("Reverted merge of category {} into {}", source.category_name, details.target_name);
+        log_budget_change(conn, source.budget_id, "category_merge_revert", Some("budget_categories"),
+                         Some(&details.target_name), Some(&source.category_name), &description)?;
+    }
+
+    for formula in &details.formulas {
+        conn.execute(
+            "UPDATE budget_categories SET formula = ?1 WHERE category_id = ?2",
+            rusqlite::params![formula.formula, formula.category_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    for source in &details.templates {
+        match source.merged_into {
+            Some(merged_into) => {
+                conn.execute(
+                    "INSERT INTO template_categories (template_category_id, template_id, global_category_id, allocated_amount, category_type, sort_order)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        source.template_category_id, source.template_id, source_id,
+                        source.allocated_amount, source.category_type, source.sort_order
+                    ],
+                ).map_err(|e| e.to_string())?;
+                conn.execute(
+                    "UPDATE template_categories SET allocated_amount = allocated_amount - ?1 WHERE template_category_id = ?2",
+                    rusqlite::params![source.allocated_amount, merged_into],
+                ).map_err(|e| e.to_string())?;
+            }
+            None => {
+                conn.execute(
+                    "UPDATE template_categories SET global_category_id = ?1 WHERE template_category_id = ?2",
+                    rusqlite::params![source_id, source.template_category_id],
+                ).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    recompute_budgets(conn, details.budgets.iter().map(|b| b.budget_id).collect())?;
+
+    Ok(())
+}
+
+fn revert_split(conn: &Connection, source_id: i64, new_category_id: i64, details: SplitDetails) -> Result<(), String> {
+    let (source_name, new_name, new_allocated): (String, String, f64) = conn.query_row(
+        "SELECT s.category_name, n.category_name, n.allocated_amount
+         FROM budget_categories s, budget_categories n
+         WHERE s.category_id = ?1 AND n.category_id = ?2",
+        rusqlite::params![source_id, new_category_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|_| "The categories involved in this split no longer exist".to_string())?;
+
+    let children: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM budget_categories WHERE parent_category_id = ?1",
+        [new_category_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    if children > 0 {
+        return Err(format!("Category {} has sub-categories; move them before reverting the split", new_name));
+    }
+
+    // Everything recorded in the new category goes back, including entries added after the split
+    let moved = conn.execute(
+        "UPDATE expenses SET category_id = ?1 WHERE category_id = ?2",
+        rusqlite::params![source_id, new_category_id],
+    ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE entry_splits SET category_id = ?1 WHERE category_id = ?2",
+        rusqlite::params![source_id, new_category_id],
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE budget_categories SET allocated_amount = allocated_amount + ?1 WHERE category_id = ?2",
+        rusqlite::params![new_allocated, source_id],
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM budget_categories WHERE category_id = ?1", [new_category_id])
+        .map_err(|e| e.to_string())?;
+
+    let description = format!("Reverted split of {} from {} ({} entries, ${:.2} returned; {} entries originally moved)",
+                            new_name, source_name, moved, new_allocated, details.entry_ids.len());
+    log_budget_change(conn, details.budget_id, "category_split_revert", Some("budget_categories"),
+                     Some(&new_name), Some(&source_name), &description)?;
+
+    recompute_budgets(conn, vec![details.budget_id])?;
+
+    Ok(())
+}
@@ -0,0 +1,646 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::macros::format_description;
+use time::{Date, Month};
+
+use crate::modules::commands::budget::log_budget_change;
+use crate::modules::commands::history::{attach_row_snapshots, row_snapshot};
+use crate::modules::commands::search::check_date;
+use crate::modules::database::DbState;
+
+// Plans that would take longer than this are reported as never paying off
+const MAX_PLAN_MONTHS: usize = 600;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebtArgs {
+    pub name: String,
+    pub principal: f64,
+    #[serde(default)]
+    pub balance_date: Option<String>, // "YYYY-MM-DD" the principal was read off; default today
+    pub interest_rate: f64,           // yearly, in percent
+    pub minimum_payment: f64,
+    pub due_day: u32,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Debt {
+    pub debt_id: i64,
+    pub name: String,
+    pub principal: f64,
+    pub balance_date: String,
+    pub interest_rate: f64,
+    pub minimum_payment: f64,
+    pub due_day: u32,
+    pub notes: Option<String>,
+    pub paid_amount: f64, // linked payments dated on or after balance_date
+    pub payments_count: i64,
+    pub current_balance: f64,
+    pub next_due_date: Option<String>, // None once the debt is paid off
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoffPlanArgs {
+    pub monthly_budget: f64, // total available for all debts each month
+    #[serde(default)]
+    pub debt_ids: Option<Vec<i64>>, // default all debts with a balance
+    #[serde(default)]
+    pub include_schedule: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedPayment {
+    pub debt_id: i64,
+    pub payment: f64,
+    pub interest: f64,
+    pub balance: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanMonth {
+    pub month: String, // "YYYY-MM"
+    pub payments: Vec<PlannedPayment>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebtPayoff {
+    pub debt_id: i64,
+    pub name: String,
+    pub payoff_month: Option<String>,
+    pub interest_paid: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoffStrategy {
+    pub strategy: String, // "avalanche" | "snowball" | "minimum"
+    pub pays_off: bool,
+    pub months: usize,
+    pub payoff_month: Option<String>,
+    pub total_interest: f64,
+    pub total_paid: f64,
+    pub interest_saved: f64, // against paying only the minimums
+    pub debts: Vec<DebtPayoff>, // in the order they are paid off
+    pub schedule: Vec<PlanMonth>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoffPlan {
+    pub monthly_budget: f64,
+    pub total_balance: f64,
+    pub total_minimum_payment: f64,
+    pub avalanche: PayoffStrategy,
+    pub snowball: PayoffStrategy,
+    pub minimum_only: PayoffStrategy,
+}
+
+fn today(conn: &Connection) -> Result<String, String> {
+    conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+fn due_date_in(year: i32, month: Month, due_day: u32) -> Result<Date, String> {
+    let day = due_day.min(time::util::days_in_year_month(year, month) as u32) as u8;
+    Date::from_calendar_date(year, month, day).map_err(|e| e.to_string())
+}
+
+// The first due date on or after today
+fn next_due_date(today: &str, due_day: u32) -> Result<String, String> {
+    let date_format = format_description!("[year]-[month]-[day]");
+    let today = Date::parse(today, date_format).map_err(|e| e.to_string())?;
+
+    let mut due = due_date_in(today.year(), today.month(), due_day)?;
+    if due < today {
+        let (year, month) = match today.month() {
+            Month::December => (today.year() + 1, Month::January),
+            month => (today.year(), month.next()),
+        };
+        due = due_date_in(year, month, due_day)?;
+    }
+    due.format(date_format).map_err(|e| e.to_string())
+}
+
+fn load_debt(conn: &Connection, debt_id: i64, today: &str) -> Result<Debt, String> {
+    let mut debt = conn.query_row(
+        "SELECT d.debt_id, d.name, d.principal, d.balance_date, d.interest_rate, d.minimum_payment, d.due_day, d.notes,
+                COALESCE(SUM(e.amount), 0), COUNT(e.expense_id)
+         FROM debts d
+         LEFT JOIN (SELECT p.debt_id, e.expense_id, e.amount, e.date
+                    FROM debt_payments p
+                    JOIN expenses e ON e.expense_id = p.expense_id
+                    JOIN budget_categories c ON c.category_id = e.category_id
+                    JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+                    WHERE e.deleted_at IS NULL AND c.deleted_at IS NULL AND b.deleted_at IS NULL) e
+           ON e.debt_id = d.debt_id AND e.date >= d.balance_date
+         WHERE d.debt_id = ?1
+         GROUP BY d.debt_id",
+        [debt_id],
+        |row| {
+            let principal: f64 = row.get(2)?;
+            let paid_amount: f64 = row.get(8)?;
+            Ok(Debt {
+                debt_id: row.get(0)?,
+                name: row.get(1)?,
+                principal,
+                balance_date: row.get(3)?,
+                interest_rate: row.get(4)?,
+                minimum_payment: row.get(5)?,
+                due_day: row.get(6)?,
+                notes: row.get(7)?,
+                paid_amount,
+                payments_count: row.get(9)?,
+                current_balance: (principal - paid_amount).max(0.0),
+                next_due_date: None,
+            })
+        }
+    ).map_err(|_| format!("Debt {} not found", debt_id))?;
+
+    if debt.current_balance > 0.005 {
+        debt.next_due_date = Some(next_due_date(today, debt.due_day)?);
+    }
+    Ok(debt)
+}
+
+fn validate_debt(args: &DebtArgs) -> Result<String, String> {
+    let name = args.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Debt name cannot be empty".to_string());
+    }
+    if args.principal < 0.0 {
+        return Err("Principal cannot be negative".to_string());
+    }
+    if !(0.0..=100.0).contains(&args.interest_rate) {
+        return Err("Interest rate must be between 0 and 100 percent".to_string());
+    }
+    if args.minimum_payment < 0.0 {
+        return Err("Minimum payment cannot be negative".to_string());
+    }
+    if !(1..=31).contains(&args.due_day) {
+        return Err("Due day must be between 1 and 31".to_string());
+    }
+    if let Some(date) = &args.balance_date {
+        check_date(date)?;
+    }
+    Ok(name)
+}
+
+#[tauri::command]
+pub fn list_debts(db: State<DbState>) -> Result<Vec<Debt>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let today = today(&conn)?;
+
+    let ids: Vec<i64> = {
+        let mut stmt = conn.prepare("SELECT debt_id FROM debts ORDER BY name COLLATE NOCASE ASC")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| e.to_string())?);
+        }
+        ids
+    };
+
+    ids.into_iter().map(|id| load_debt(&conn, id, &today)).collect()
+}
+
+#[tauri::command]
+pub fn create_debt(args: DebtArgs, db: State<DbState>) -> Result<Debt, String> {
+    println!("=== CREATE_DEBT COMMAND CALLED ===");
+    println!("Debt: {:?}", args);
+
+    let name = validate_debt(&args)?;
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let today = today(&conn)?;
+
+    conn.execute(
+        "INSERT INTO debts (name, principal, balance_date, interest_rate, minimum_payment, due_day, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![name, args.principal, args.balance_date.as_deref().unwrap_or(&today),
+                          args.interest_rate, args.minimum_payment, args.due_day, args.notes],
+    ).map_err(|e| format!("Failed to create debt: {}", e))?;
+
+    let debt_id = conn.last_insert_rowid();
+    println!("Created debt {}", debt_id);
+    load_debt(&conn, debt_id, &today)
+}
+
+#[tauri::command]
+pub fn update_debt(debt_id: i64, args: DebtArgs, db: State<DbState>) -> Result<Debt, String> {
+    println!("=== UPDATE_DEBT COMMAND CALLED ===");
+    println!("Debt {}: {:?}", debt_id, args);
+
+    let name = validate_debt(&args)?;
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let today = today(&conn)?;
+
+    // Without a new balance date the principal stays anchored to the old one
+    let rows_affected = conn.execute(
+        "UPDATE debts
+         SET name = ?1, principal = ?2, balance_date = COALESCE(?3, balance_date), interest_rate = ?4,
+             minimum_payment = ?5, due_day = ?6, notes = ?7, updated_at = datetime('now')
+         WHERE debt_id = ?8",
+        rusqlite::params![name, args.principal, args.balance_date, args.interest_rate,
+                          args.minimum_payment, args.due_day, args.notes, debt_id],
+    ).map_err(|e| format!("Failed to update debt: {}", e))?;
+
+    if rows_affected == 0 {
+        return Err(format!("Debt {} not found", debt_id));
+    }
+    load_debt(&conn, debt_id, &today)
+}
+
+#[tauri::command]
+pub fn delete_debt(debt_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== DELETE_DEBT COMMAND CALLED ===");
+    println!("Deleting debt ID: {}", debt_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    // Linked payments stay in the ledger as ordinary entries
+    let rows_affected = conn.execute("DELETE FROM debts WHERE debt_id = ?1", [debt_id])
+        .map_err(|e| e.to_string())?;
+    if rows_affected == 0 {
+        return Err(format!("Debt {} not found", debt_id));
+    }
+    Ok(())
+}
+
+/// Marks a ledger entry as a payment towards a debt, or, without a debt, unmarks it.
+#[tauri::command]
+pub fn set_entry_debt(entry_id: i64, debt_id: Option<i64>, db: State<DbState>) -> Result<(), String> {
+    println!("=== SET_ENTRY_DEBT COMMAND CALLED ===");
+    println!("Linking entry {} to debt {:?}", entry_id, debt_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (entry_type, budget_id, what): (String, i64, String) = tx.query_row(
+        "SELECT e.entry_type, c.budget_id, e.description FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE e.expense_id = ?1 AND e.deleted_at IS NULL",
+        [entry_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|_| format!("Entry {} not found or deleted", entry_id))?;
+
+    let current_debt: Option<i64> = tx.query_row(
+        "SELECT debt_id FROM debt_payments WHERE expense_id = ?1",
+        [entry_id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+    if current_debt == debt_id {
+        return Ok(());
+    }
+
+    let before = row_snapshot(&tx, "debt_payments", entry_id)?;
+    let description = match debt_id {
+        Some(debt_id) => {
+            if entry_type != "expense" {
+                return Err("Only expense entries can pay off a debt".to_string());
+            }
+            tx.query_row("SELECT 1 FROM debts WHERE debt_id = ?1", [debt_id], |_| Ok(()))
+                .map_err(|_| format!("Debt {} not found", debt_id))?;
+            tx.execute(
+                "INSERT INTO debt_payments (expense_id, debt_id) VALUES (?1, ?2)
+                 ON CONFLICT(expense_id) DO UPDATE SET debt_id = excluded.debt_id",
+                rusqlite::params![entry_id, debt_id],
+            ).map_err(|e| e.to_string())?;
+            format!("Entry {} ({}) now pays off debt {}", entry_id, what, debt_id)
+        }
+        None => {
+            tx.execute("DELETE FROM debt_payments WHERE expense_id = ?1", [entry_id])
+                .map_err(|e| e.to_string())?;
+            format!("Entry {} ({}) no longer pays off a debt", entry_id, what)
+        }
+    };
+
+    let change_id = log_budget_change(&tx, budget_id, "entry_debt", Some("debt_id"),
+                                      current_debt.map(|id| id.to_string()).as_deref(),
+                                      debt_id.map(|id| id.to_string()).as_deref(), &description)?;
+    attach_row_snapshots(&tx, change_id, "debt_payments", entry_id, before)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+struct PlanDebt {
+    debt_id: i64,
+    name: String,
+    balance: f64,
+    monthly_rate: f64,
+    minimum_payment: f64,
+    interest_paid: f64,
+    payoff_month: Option<usize>,
+}
+
+fn month_label(start: i64, offset: usize) -> String {
+    let index = start + offset as i64;
+    format!("{:04}-{:02}", index / 12, index % 12 + 1)
+}
+
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+// The minimum payments due this month, which the monthly budget has to cover
+fn check_budget_covers_minimums(debts: &[Debt], monthly_budget: f64) -> Result<f64, String> {
+    let total_minimum_payment: f64 = debts.iter().map(|d| d.minimum_payment.min(d.current_balance)).sum();
+    if monthly_budget + 0.005 < total_minimum_payment {
+        return Err(format!(
+            "The monthly budget of ${:.2} does not cover the minimum payments of ${:.2}",
+            monthly_budget, total_minimum_payment
+        ));
+    }
+    Ok(total_minimum_payment)
+}
+
+// Simulates paying the debts month by month: interest first, then every minimum, then whatever
+// is left of the budget goes to the first debt in the strategy's order
+fn simulate(
+    debts: &[Debt],
+    monthly_budget: f64,
+    strategy: &str,
+    start: i64,
+    include_schedule: bool,
+) -> PayoffStrategy {
+    let mut plan: Vec<PlanDebt> = debts
+        .iter()
+        .map(|d| PlanDebt {
+            debt_id: d.debt_id,
+            name: d.name.clone(),
+            balance: d.current_balance,
+            monthly_rate: d.interest_rate / 100.0 / 12.0,
+            minimum_payment: d.minimum_payment,
+            interest_paid: 0.0,
+            payoff_month: None,
+        })
+        .collect();
+
+    match strategy {
+        // Highest rate first saves the most interest
+        "avalanche" => plan.sort_by(|a, b| b.monthly_rate.total_cmp(&a.monthly_rate).then(a.balance.total_cmp(&b.balance))),
+        // Smallest balance first clears whole debts soonest
+        "snowball" => plan.sort_by(|a, b| a.balance.total_cmp(&b.balance).then(b.monthly_rate.total_cmp(&a.monthly_rate))),
+        _ => {}
+    }
+
+    let mut schedule = Vec::new();
+    let mut total_paid = 0.0;
+    let mut months = 0;
+
+    while months < MAX_PLAN_MONTHS && plan.iter().any(|d| d.balance > 0.005) {
+        let mut payments: Vec<PlannedPayment> = Vec::new();
+        let mut left = if strategy == "minimum" { f64::INFINITY } else { monthly_budget };
+        let mut paid = vec![0.0; plan.len()];
+        let mut interest = vec![0.0; plan.len()];
+
+        for (i, debt) in plan.iter_mut().enumerate() {
+            if debt.balance <= 0.005 {
+                continue;
+            }
+            interest[i] = round_cents(debt.balance * debt.monthly_rate);
+            debt.balance += interest[i];
+            debt.interest_paid += interest[i];
+
+            let minimum = debt.minimum_payment.min(debt.balance).min(left);
+            debt.balance -= minimum;
+            paid[i] += minimum;
+            left -= minimum;
+        }
+
+        if strategy != "minimum" {
+            for (i, debt) in plan.iter_mut().enumerate() {
+                if left <= 0.005 {
+                    break;
+                }
+                let extra = debt.balance.min(left);
+                debt.balance -= extra;
+                paid[i] += extra;
+                left -= extra;
+            }
+        }
+
+        for (i, debt) in plan.iter_mut().enumerate() {
+            if paid[i] <= 0.0 && interest[i] <= 0.0 {
+                continue;
+            }
+            total_paid += paid[i];
+            if debt.balance <= 0.005 && debt.payoff_month.is_none() {
+                debt.balance = 0.0;
+                debt.payoff_month = Some(months);
+            }
+            if include_schedule {
+                payments.push(PlannedPayment {
+                    debt_id: debt.debt_id,
+                    payment: round_cents(paid[i]),
+                    interest: interest[i],
+                    balance: round_cents(debt.balance),
+                });
+            }
+        }
+
+        if include_schedule {
+            schedule.push(PlanMonth { month: month_label(start, months), payments });
+        }
+        months += 1;
+    }
+
+    let pays_off = plan.iter().all(|d| d.balance <= 0.005);
+    let total_interest = plan.iter().map(|d| d.interest_paid).sum();
+
+    plan.sort_by_key(|d| d.payoff_month.unwrap_or(usize::MAX));
+    PayoffStrategy {
+        strategy: strategy.to_string(),
+        pays_off,
+        months,
+        payoff_month: if pays_off && months > 0 { Some(month_label(start, months - 1)) } else { None },
+        total_interest: round_cents(total_interest),
+        total_paid: round_cents(total_paid),
+        interest_saved: 0.0,
+        debts: plan
+            .iter()
+            .map(|d| DebtPayoff {
+                debt_id: d.debt_id,
+                name: d.name.clone(),
+                payoff_month: d.payoff_month.map(|m| month_label(start, m)),
+                interest_paid: round_cents(d.interest_paid),
+            })
+            .collect(),
+        schedule,
+    }
+}
+
+#[tauri::command]
+pub fn plan_debt_payoff(args: PayoffPlanArgs, db: State<DbState>) -> Result<PayoffPlan, String> {
+    println!("=== PLAN_DEBT_PAYOFF COMMAND CALLED ===");
+    println!("Payoff plan: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let today = today(&conn)?;
+
+    let ids: Vec<i64> = match args.debt_ids.as_ref().filter(|ids| !ids.is_empty()) {
+        Some(ids) => ids.clone(),
+        None => {
+            let mut stmt = conn.prepare("SELECT debt_id FROM debts ORDER BY debt_id ASC").map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+            let mut ids = Vec::new();
+            for row in rows {
+                ids.push(row.map_err(|e| e.to_string())?);
+            }
+            ids
+        }
+    };
+
+    let mut debts = Vec::new();
+    for id in ids {
+        let debt = load_debt(&conn, id, &today)?;
+        if debt.current_balance > 0.005 {
+            debts.push(debt);
+        }
+    }
+    if debts.is_empty() {
+        return Err("There are no debts with a balance to plan for".to_string());
+    }
+
+    let total_balance: f64 = debts.iter().map(|d| d.current_balance).sum();
+    let total_minimum_payment = check_budget_covers_minimums(&debts, args.monthly_budget)?;
+
+    // Plans start with next month's payments
+    let current: i64 = today[0..4].parse::<i64>().map_err(|e| e.to_string())? * 12
+        + today[5..7].parse::<i64>().map_err(|e| e.to_string())? - 1;
+    let start = current + 1;
+
+    let minimum_only = simulate(&debts, args.monthly_budget, "minimum", start, args.include_schedule);
+    let mut avalanche = simulate(&debts, args.monthly_budget, "avalanche", start, args.include_schedule);
+    let mut snowball = simulate(&debts, args.monthly_budget, "snowball", start, args.include_schedule);
+
+    // Paying only the minimums may never finish; then there is no honest number to compare to
+    if minimum_only.pays_off {
+        avalanche.interest_saved = round_cents(minimum_only.total_interest - avalanche.total_interest);
+        snowball.interest_saved = round_cents(minimum_only.total_interest - snowball.total_interest);
+    }
+
+    println!(
+        "Avalanche: {} months, ${:.2} interest; snowball: {} months, ${:.2} interest",
+        avalanche.months, avalanche.total_interest, snowball.months, snowball.total_interest
+    );
+
+    Ok(PayoffPlan {
+        monthly_budget: args.monthly_budget,
+        total_balance,
+        total_minimum_payment,
+        avalanche,
+        snowball,
+        minimum_only,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // January 2026, as counted by month_label
+    const START: i64 = 2026 * 12;
+
+    fn debt(debt_id: i64, balance: f64, interest_rate: f64, minimum_payment: f64) -> Debt {
+        Debt {
+            debt_id,
+            name: format!("Debt {}", debt_id),
+            principal: balance,
+            balance_date: "2025-12-01".to_string(),
+            interest_rate,
+            minimum_payment,
+            due_day: 1,
+            notes: None,
+            paid_amount: 0.0,
+            payments_count: 0,
+            current_balance: balance,
+            next_due_date: Some("2026-01-01".to_string()),
+        }
+    }
+
+    fn assert_cents(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 0.005, "expected {:.2}, got {:.2}", expected, actual);
+    }
+
+    #[test]
+    fn avalanche_pays_the_highest_rate_first() {
+        // 1% a month on the first debt, none on the second; $300 a month for both
+        let debts = vec![debt(2, 500.0, 0.0, 50.0), debt(1, 1000.0, 12.0, 100.0)];
+        let plan = simulate(&debts, 300.0, "avalanche", START, true);
+
+        // Balances of debt 1 by hand: 1000 + 10.00 - 250 = 760, 760 + 7.60 - 250 = 517.60,
+        // 517.60 + 5.18 - 250 = 272.78, 272.78 + 2.73 - 250 = 25.51, then 25.51 + 0.26 paid off;
+        // debt 2 pays $50 a month, takes the rest of month 5 and its last $25.77 in month 6
+        assert!(plan.pays_off);
+        assert_eq!(plan.months, 6);
+        assert_eq!(plan.payoff_month.as_deref(), Some("2026-06"));
+        assert_cents(plan.total_interest, 25.77);
+        assert_cents(plan.total_paid, 1525.77);
+
+        assert_eq!(plan.debts[0].debt_id, 1);
+        assert_eq!(plan.debts[0].payoff_month.as_deref(), Some("2026-05"));
+        assert_cents(plan.debts[0].interest_paid, 25.77);
+        assert_eq!(plan.debts[1].debt_id, 2);
+        assert_eq!(plan.debts[1].payoff_month.as_deref(), Some("2026-06"));
+        assert_cents(plan.debts[1].interest_paid, 0.0);
+
+        let first = &plan.schedule[0];
+        assert_eq!(first.month, "2026-01");
+        assert_eq!(first.payments[0].debt_id, 1);
+        assert_cents(first.payments[0].payment, 250.0);
+        assert_cents(first.payments[0].interest, 10.0);
+        assert_cents(first.payments[0].balance, 760.0);
+        assert_eq!(first.payments[1].debt_id, 2);
+        assert_cents(first.payments[1].payment, 50.0);
+        assert_cents(first.payments[1].balance, 450.0);
+
+        let expected_balances = [760.0, 517.60, 272.78, 25.51, 0.0];
+        for (month, expected) in plan.schedule.iter().zip(expected_balances) {
+            assert_cents(month.payments[0].balance, expected);
+        }
+
+        let fifth = &plan.schedule[4];
+        assert_cents(fifth.payments[0].payment, 25.77);
+        assert_cents(fifth.payments[1].payment, 274.23);
+        assert_cents(fifth.payments[1].balance, 25.77);
+
+        let last = &plan.schedule[5];
+        assert_eq!(last.payments.len(), 1);
+        assert_eq!(last.payments[0].debt_id, 2);
+        assert_cents(last.payments[0].payment, 25.77);
+        assert_cents(last.payments[0].balance, 0.0);
+    }
+
+    #[test]
+    fn budget_below_the_minimums_is_rejected() {
+        let debts = vec![debt(1, 1000.0, 12.0, 100.0), debt(2, 30.0, 0.0, 50.0)];
+
+        // The second minimum is capped at what is left of that debt
+        assert_cents(check_budget_covers_minimums(&debts, 130.0).unwrap(), 130.0);
+        let err = check_budget_covers_minimums(&debts, 120.0).unwrap_err();
+        assert!(err.contains("$120.00") && err.contains("$130.00"), "{}", err);
+    }
+
+    #[test]
+    fn minimums_that_never_pay_off_stop_at_the_cap() {
+        // 2% a month is $200 of interest against a $150 minimum, so the balance only grows
+        let debts = vec![debt(1, 10000.0, 24.0, 150.0)];
+        let plan = simulate(&debts, 150.0, "minimum", START, false);
+
+        assert!(!plan.pays_off);
+        assert_eq!(plan.months, MAX_PLAN_MONTHS);
+        assert_eq!(plan.payoff_month, None);
+        assert_eq!(plan.debts[0].payoff_month, None);
+        assert_cents(plan.total_paid, 150.0 * MAX_PLAN_MONTHS as f64);
+        assert!(plan.schedule.is_empty());
+    }
+}
@@ -0,0 +1,262 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::modules::commands::search::check_date;
+use crate::modules::database::DbState;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsGoalArgs {
+    pub name: String,
+    pub global_category_id: i64,
+    pub target_amount: f64,
+    pub target_date: String, // "YYYY-MM-DD"
+    #[serde(default)]
+    pub starting_amount: Option<f64>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalContribution {
+    pub budget_id: i64,
+    pub year: i32,
+    pub month: u32,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavingsGoal {
+    pub goal_id: i64,
+    pub name: String,
+    pub global_category_id: i64,
+    pub global_category_name: String,
+    pub target_amount: f64,
+    pub target_date: String,
+    pub starting_amount: f64,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub saved_amount: f64,
+    pub remaining_amount: f64,
+    pub percent_complete: f64,
+    pub months_left: i64, // including the current month
+    pub required_monthly: f64,
+    pub expected_amount: f64, // where a steady pace from the goal's start would be by now
+    pub status: String,       // "achieved" | "on_track" | "behind" | "overdue"
+    pub contributions: Vec<GoalContribution>,
+}
+
+// Month index (year * 12 + month - 1) of a "YYYY-MM-DD..." date
+fn month_index_of(date: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid date '{}'", date);
+    let year: i64 = date.get(0..4).ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+    let month: i64 = date.get(5..7).ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+    Ok(year * 12 + month - 1)
+}
+
+fn validate_goal(conn: &Connection, args: &SavingsGoalArgs) -> Result<String, String> {
+    let name = args.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Goal name cannot be empty".to_string());
+    }
+    if args.target_amount <= 0.0 {
+        return Err("Target amount must be positive".to_string());
+    }
+    if args.starting_amount.unwrap_or(0.0) < 0.0 {
+        return Err("Starting amount cannot be negative".to_string());
+    }
+    check_date(&args.target_date)?;
+
+    let today: String = conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if month_index_of(&args.target_date)? < month_index_of(&today)? {
+        return Err(format!("Target date {} is before the current month", args.target_date));
+    }
+
+    conn.query_row(
+        "SELECT 1 FROM global_categories WHERE global_category_id = ?1",
+        [args.global_category_id],
+        |_| Ok(())
+    ).map_err(|_| format!("Global category {} not found", args.global_category_id))?;
+
+    Ok(name)
+}
+
+// Money moved into the goal's savings categories, per budget. Expense entries put money aside,
+// income entries take it back out; transfers only shift allocation between categories.
+fn goal_contributions(conn: &Connection, global_category_id: i64) -> Result<Vec<GoalContribution>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT b.budget_id, b.year, b.month,
+                SUM(CASE l.entry_type WHEN 'expense' THEN l.amount WHEN 'income' THEN -l.amount ELSE 0 END)
+         FROM entry_lines l
+         JOIN budget_categories c ON c.category_id = l.category_id
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+         WHERE l.deleted_at IS NULL AND c.deleted_at IS NULL AND b.deleted_at IS NULL
+           AND c.category_type = 'savings' AND c.global_category_id = ?1
+         GROUP BY b.budget_id, b.year, b.month
+         ORDER BY b.year ASC, b.month ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([global_category_id], |row| {
+        Ok(GoalContribution {
+            budget_id: row.get(0)?,
+            year: row.get(1)?,
+            month: row.get(2)?,
+            amount: row.get(3)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+fn load_goal(conn: &Connection, goal_id: i64) -> Result<SavingsGoal, String> {
+    let (name, global_category_id, global_category_name, target_amount, target_date, starting_amount, notes, created_at):
+        (String, i64, String, f64, String, f64, Option<String>, String) = conn.query_row(
+        "SELECT s.name, s.global_category_id, g.name, s.target_amount, s.target_date, s.starting_amount, s.notes, s.created_at
+         FROM savings_goals s
+         JOIN global_categories g ON g.global_category_id = s.global_category_id
+         WHERE s.goal_id = ?1",
+        [goal_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?))
+    ).map_err(|_| format!("Savings goal {} not found", goal_id))?;
+
+    let today: String = conn.query_row("SELECT date('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    let contributions = goal_contributions(conn, global_category_id)?;
+    let saved_amount = starting_amount + contributions.iter().map(|c| c.amount).sum::<f64>();
+    let remaining_amount = (target_amount - saved_amount).max(0.0);
+
+    let current = month_index_of(&today)?;
+    let start = month_index_of(&created_at)?;
+    let target = month_index_of(&target_date)?;
+
+    let months_left = (target - current + 1).max(0);
+    let required_monthly = if months_left > 0 { remaining_amount / months_left as f64 } else { remaining_amount };
+
+    // Full months since the goal was set, against the months it has to run
+    // Both stay sane for a target date before the goal's start month
+    let span = (target - start + 1).max(1);
+    let elapsed = (current - start).max(0).min(span);
+    let expected_amount = starting_amount + (target_amount - starting_amount) * (elapsed as f64 / span as f64);
+
+    let status = if saved_amount >= target_amount - 0.005 {
+        "achieved"
+    } else if today > target_date {
+        "overdue"
+    } else if saved_amount >= expected_amount - 0.005 {
+        "on_track"
+    } else {
+        "behind"
+    };
+
+    Ok(SavingsGoal {
+        goal_id,
+        name,
+        global_category_id,
+        global_category_name,
+        target_amount,
+        target_date,
+        starting_amount,
+        notes,
+        created_at,
+        saved_amount,
+        remaining_amount,
+        percent_complete: (saved_amount / target_amount * 100.0).clamp(0.0, 100.0),
+        months_left,
+        required_monthly,
+        expected_amount,
+        status: status.to_string(),
+        contributions,
+    })
+}
+
+#[tauri::command]
+pub fn list_savings_goals(db: State<DbState>) -> Result<Vec<SavingsGoal>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let ids: Vec<i64> = {
+        let mut stmt = conn.prepare("SELECT goal_id FROM savings_goals ORDER BY target_date ASC, goal_id ASC")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| e.to_string())?);
+        }
+        ids
+    };
+
+    ids.into_iter().map(|id| load_goal(&conn, id)).collect()
+}
+
+#[tauri::command]
+pub fn get_savings_goal(goal_id: i64, db: State<DbState>) -> Result<SavingsGoal, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    load_goal(&conn, goal_id)
+}
+
+#[tauri::command]
+pub fn create_savings_goal(args: SavingsGoalArgs, db: State<DbState>) -> Result<SavingsGoal, String> {
+    println!("=== CREATE_SAVINGS_GOAL COMMAND CALLED ===");
+    println!("Goal: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let name = validate_goal(&conn, &args)?;
+
+    conn.execute(
+        "INSERT INTO savings_goals (name, global_category_id, target_amount, target_date, starting_amount, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![name, args.global_category_id, args.target_amount, args.target_date,
+                          args.starting_amount.unwrap_or(0.0), args.notes],
+    ).map_err(|e| format!("Failed to create savings goal: {}", e))?;
+
+    let goal_id = conn.last_insert_rowid();
+    println!("Created savings goal {}", goal_id);
+    load_goal(&conn, goal_id)
+}
+
+#[tauri::command]
+pub fn update_savings_goal(goal_id: i64, args: SavingsGoalArgs, db: State<DbState>) -> Result<SavingsGoal, String> {
+    println!("=== UPDATE_SAVINGS_GOAL COMMAND CALLED ===");
+    println!("Goal {}: {:?}", goal_id, args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let name = validate_goal(&conn, &args)?;
+
+    let rows_affected = conn.execute(
+        "UPDATE savings_goals
+         SET name = ?1, global_category_id = ?2, target_amount = ?3, target_date = ?4,
+             starting_amount = ?5, notes = ?6, updated_at = datetime('now')
+         WHERE goal_id = ?7",
+        rusqlite::params![name, args.global_category_id, args.target_amount, args.target_date,
+                          args.starting_amount.unwrap_or(0.0), args.notes, goal_id],
+    ).map_err(|e| format!("Failed to update savings goal: {}", e))?;
+
+    if rows_affected == 0 {
+        return Err(format!("Savings goal {} not found", goal_id));
+    }
+    load_goal(&conn, goal_id)
+}
+
+#[tauri::command]
+pub fn delete_savings_goal(goal_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== DELETE_SAVINGS_GOAL COMMAND CALLED ===");
+    println!("Deleting savings goal ID: {}", goal_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    // The savings entries themselves stay in the ledger
+    let rows_affected = conn.execute("DELETE FROM savings_goals WHERE goal_id = ?1", [goal_id])
+        .map_err(|e| e.to_string())?;
+    if rows_affected == 0 {
+        return Err(format!("Savings goal {} not found", goal_id));
+    }
+    Ok(())
+}
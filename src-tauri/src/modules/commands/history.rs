@@ -0,0 +1,677 @@
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, State};
+
+use crate::modules::commands::alert::raise_budget_alerts;
+use crate::modules::commands::budget::{log_budget_change, recompute_budget_allocations, BudgetChangeHistoryEntry};
+use crate::modules::commands::reconcile::check_not_reconciled;
+use crate::modules::commands::split::rebalance_entry_splits;
+use crate::modules::commands::transfer::is_transfer_type;
+use crate::modules::database::{table_columns, DbState};
+use crate::modules::security::audit;
+
+// Tables whose rows are snapshotted into BudgetChangeHistory, with their primary keys
+const SNAPSHOT_TABLES: [(&str, &str); 3] = [
+    ("MonthlyBudgets", "budget_id"),
+    ("budget_categories", "category_id"),
+    ("expenses", "expense_id"),
+];
+
+// Child tables of an entry whose rows are snapshotted together as the entry's set, with the
+// columns that make up a row and their order; an undo puts the whole set back
+const ENTRY_SET_TABLES: [(&str, &str, &str); 3] = [
+    ("entry_splits", "category_id, amount, note", "split_id"),
+    ("entry_tags", "tag_id", "tag_id"),
+    ("debt_payments", "debt_id", "debt_id"),
+];
+
+// Bookkeeping columns that every write touches; they never count as a conflict
+const BOOKKEEPING_COLUMNS: [&str; 2] = ["last_edited", "updated_at"];
+
+fn primary_key(table: &str) -> Result<&'static str, String> {
+    SNAPSHOT_TABLES
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, key)| *key)
+        .ok_or_else(|| format!("Rows of {} are not tracked in the change history", table))
+}
+
+fn entry_set_table(table: &str) -> Option<(&'static str, &'static str)> {
+    ENTRY_SET_TABLES
+        .iter()
+        .find(|(name, _, _)| *name == table)
+        .map(|(_, columns, order)| (*columns, *order))
+}
+
+fn read_row(row: &rusqlite::Row, columns: &[String]) -> rusqlite::Result<Map<String, Value>> {
+    let mut snapshot = Map::new();
+    for (i, column) in columns.iter().enumerate() {
+        let value = match row.get_ref(i)? {
+            ValueRef::Null => Value::Null,
+            ValueRef::Integer(v) => Value::from(v),
+            ValueRef::Real(v) => serde_json::Number::from_f64(v).map(Value::Number).unwrap_or(Value::Null),
+            ValueRef::Text(v) => Value::String(String::from_utf8_lossy(v).into_owned()),
+            ValueRef::Blob(v) => Value::from(v.to_vec()),
+        };
+        snapshot.insert(column.clone(), value);
+    }
+    Ok(snapshot)
+}
+
+/// Reads a whole row as a JSON object, or `None` if it does not exist. For the child
+/// tables of an entry, `id` is the entry and the object holds all of its rows.
+pub(crate) fn row_snapshot(conn: &Connection, table: &str, id: i64) -> Result<Option<Value>, String> {
+    if let Some((set_columns, order)) = entry_set_table(table) {
+        let mut stmt = conn
+            .prepare(&format!("SELECT {} FROM {} WHERE expense_id = ?1 ORDER BY {}", set_columns, table, order))
+            .map_err(|e| e.to_string())?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let rows = stmt
+            .query_map([id], |row| read_row(row, &columns).map(Value::Object))
+            .map_err(|e| e.to_string())?
+            .collect::<rusqlite::Result<Vec<Value>>>()
+            .map_err(|e| e.to_string())?;
+
+        let mut snapshot = Map::new();
+        snapshot.insert("expense_id".to_string(), Value::from(id));
+        snapshot.insert("rows".to_string(), Value::Array(rows));
+        return Ok(Some(Value::Object(snapshot)));
+    }
+
+    let key = primary_key(table)?;
+    let mut stmt = conn
+        .prepare(&format!("SELECT * FROM {} WHERE {} = ?1", table, key))
+        .map_err(|e| e.to_string())?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+
+    stmt.query_row([id], |row| read_row(row, &columns).map(Value::Object))
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+/// Stores the row's state before and after a change on an existing history entry.
+/// The "after" snapshot is read from the row as it is now.
+pub(crate) fn attach_row_snapshots(
+    conn: &Connection,
+    change_id: i64,
+    table: &str,
+    entity_id: i64,
+    before: Option<Value>,
+) -> Result<(), String> {
+    let after = row_snapshot(conn, table, entity_id)?;
+
+    conn.execute(
+        "UPDATE BudgetChangeHistory SET entity_table = ?1, entity_id = ?2, before_snapshot = ?3, after_snapshot = ?4 WHERE change_id = ?5",
+        rusqlite::params![
+            table,
+            entity_id,
+            before.map(|v| v.to_string()),
+            after.map(|v| v.to_string()),
+            change_id
+        ],
+    ).map_err(|e| format!("Failed to store change snapshots: {}", e))?;
+
+    // The row's hash has to cover the snapshots as well
+    audit::seal_history_row(conn, change_id).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn to_sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or(0.0)),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Array(bytes) => SqlValue::Blob(bytes.iter().filter_map(|b| b.as_u64()).map(|b| b as u8).collect()),
+        Value::Object(_) => SqlValue::Text(value.to_string()),
+    }
+}
+
+// DECIMAL columns hand back integers for whole amounts, so numbers compare by value
+fn same_value(a: Option<&Value>, b: Option<&Value>) -> bool {
+    match (a, b) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => x.as_f64() == y.as_f64(),
+        (a, b) => a.unwrap_or(&Value::Null) == b.unwrap_or(&Value::Null),
+    }
+}
+
+fn changed_columns(before: &Map<String, Value>, after: &Map<String, Value>) -> Vec<String> {
+    before
+        .keys()
+        .chain(after.keys())
+        .filter(|column| !BOOKKEEPING_COLUMNS.contains(&column.as_str()))
+        .filter(|column| !same_value(before.get(*column), after.get(*column)))
+        .fold(Vec::new(), |mut columns, column| {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+            columns
+        })
+}
+
+fn parse_snapshot(snapshot: Option<String>) -> Result<Option<Map<String, Value>>, String> {
+    match snapshot {
+        Some(text) => match serde_json::from_str(&text).map_err(|e| e.to_string())? {
+            Value::Object(map) => Ok(Some(map)),
+            _ => Err("Stored snapshot is not a row".to_string()),
+        },
+        None => Ok(None),
+    }
+}
+
+// Rows that would be orphaned if the given row disappeared
+fn dependent_rows(conn: &Connection, table: &str, id: i64) -> Result<i64, String> {
+    let sql = match table {
+        "budget_categories" => {
+            "SELECT (SELECT COUNT(*) FROM expenses WHERE category_id = ?1)
+                  + (SELECT COUNT(*) FROM entry_splits WHERE category_id = ?1)
+                  + (SELECT COUNT(*) FROM budget_categories WHERE parent_category_id = ?1)"
+        }
+        "expenses" => {
+            "SELECT (SELECT COUNT(*) FROM entry_tags WHERE expense_id = ?1)
+                  + (SELECT COUNT(*) FROM entry_splits WHERE expense_id = ?1)
+                  + (SELECT COUNT(*) FROM attachments WHERE owner_type = 'entry' AND owner_id = ?1)
+                  + (SELECT COUNT(*) FROM debt_payments WHERE expense_id = ?1)"
+        }
+        _ => return Ok(0),
+    };
+    conn.query_row(sql, [id], |row| row.get(0)).map_err(|e| e.to_string())
+}
+
+// Whether reverting between the two states would create, remove, trash or restore one side
+// of a transfer; both sides only ever move together
+fn moves_transfer_side(before: Option<&Map<String, Value>>, after: Option<&Map<String, Value>>) -> bool {
+    let is_transfer = |snapshot: Option<&Map<String, Value>>| {
+        snapshot
+            .and_then(|row| row.get("entry_type"))
+            .and_then(Value::as_str)
+            .is_some_and(is_transfer_type)
+    };
+    match (before, after) {
+        (Some(before), Some(after)) => {
+            (is_transfer(Some(before)) || is_transfer(Some(after)))
+                && !same_value(before.get("deleted_at"), after.get("deleted_at"))
+        }
+        (before, after) => is_transfer(before) || is_transfer(after),
+    }
+}
+
+// Replaces an entry's rows in one of its child tables with the `target` set, provided the
+// set is still the `expected` one
+fn restore_entry_set(
+    conn: &Connection,
+    table: &str,
+    entry_id: i64,
+    expected: Option<&Map<String, Value>>,
+    target: Option<&Map<String, Value>>,
+) -> Result<(), String> {
+    let rows_of = |snapshot: Option<&Map<String, Value>>| -> Vec<Map<String, Value>> {
+        snapshot
+            .and_then(|set| set.get("rows"))
+            .and_then(Value::as_array)
+            .map(|rows| rows.iter().filter_map(|row| row.as_object().cloned()).collect())
+            .unwrap_or_default()
+    };
+    let expected_rows = rows_of(expected);
+    let target_rows = rows_of(target);
+    let current_rows = match row_snapshot(conn, table, entry_id)? {
+        Some(Value::Object(set)) => rows_of(Some(&set)),
+        _ => Vec::new(),
+    };
+
+    let unchanged = current_rows.len() == expected_rows.len()
+        && current_rows.iter().zip(&expected_rows).all(|(a, b)| changed_columns(a, b).is_empty());
+    if !unchanged {
+        return Err("A later change modified the same fields; undo that change first".to_string());
+    }
+
+    let entry_live: bool = conn.query_row(
+        "SELECT COUNT(*) FROM expenses WHERE expense_id = ?1 AND deleted_at IS NULL",
+        [entry_id],
+        |row| row.get::<_, i64>(0)
+    ).map_err(|e| e.to_string())? > 0;
+    if !entry_live {
+        return Err("The entry is in the trash or no longer exists".to_string());
+    }
+
+    if table == "entry_splits" {
+        for row in &target_rows {
+            let category_live: bool = conn.query_row(
+                "SELECT COUNT(*) FROM budget_categories WHERE category_id = ?1 AND deleted_at IS NULL",
+                [row.get("category_id").and_then(Value::as_i64).unwrap_or(0)],
+                |r| r.get::<_, i64>(0)
+            ).map_err(|e| e.to_string())? > 0;
+            if !category_live {
+                return Err("A category of this split is in the trash or no longer exists".to_string());
+            }
+        }
+    }
+    if table == "debt_payments" {
+        for row in &target_rows {
+            let debt_exists: bool = conn.query_row(
+                "SELECT COUNT(*) FROM debts WHERE debt_id = ?1",
+                [row.get("debt_id").and_then(Value::as_i64).unwrap_or(0)],
+                |r| r.get::<_, i64>(0)
+            ).map_err(|e| e.to_string())? > 0;
+            if !debt_exists {
+                return Err("The debt this entry paid off no longer exists".to_string());
+            }
+        }
+    }
+
+    conn.execute(&format!("DELETE FROM {} WHERE expense_id = ?1", table), [entry_id])
+        .map_err(|e| e.to_string())?;
+    for row in &target_rows {
+        let names: Vec<&String> = row.keys().collect();
+        let placeholders: Vec<String> = (2..=names.len() + 1).map(|i| format!("?{}", i)).collect();
+        let mut values = vec![SqlValue::Integer(entry_id)];
+        values.extend(names.iter().map(|c| to_sql_value(&row[c.as_str()])));
+        conn.execute(
+            &format!(
+                "INSERT INTO {} (expense_id, {}) VALUES (?1, {})",
+                table,
+                names.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", "),
+                placeholders.join(", ")
+            ),
+            rusqlite::params_from_iter(values),
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Puts a row back into the `target` state, provided it is still in the `expected` state
+/// for every column the original change touched.
+fn restore_row(
+    conn: &Connection,
+    table: &str,
+    id: i64,
+    expected: Option<&Map<String, Value>>,
+    target: Option<&Map<String, Value>>,
+) -> Result<(), String> {
+    if entry_set_table(table).is_some() {
+        return restore_entry_set(conn, table, id, expected, target);
+    }
+
+    let key = primary_key(table)?;
+    let current = match row_snapshot(conn, table, id)? {
+        Some(Value::Object(map)) => Some(map),
+        _ => None,
+    };
+    let columns = table_columns(conn, table).map_err(|e| e.to_string())?;
+
+    match (expected, target) {
+        // Removing a budget row would strand its history, including this undo, without a budget
+        (Some(_), None) if table == "MonthlyBudgets" => {
+            return Err("Removing a budget would erase its history; move it to the trash instead".to_string());
+        }
+        // Undoing an insert removes the row again
+        (Some(expected), None) => {
+            let current = current.ok_or("The row created by this change no longer exists")?;
+            if !changed_columns(expected, &current).is_empty() {
+                return Err("The row has been modified by a later change".to_string());
+            }
+            if dependent_rows(conn, table, id)? > 0 {
+                return Err("Other records now depend on this row; remove them first".to_string());
+            }
+            conn.execute(&format!("DELETE FROM {} WHERE {} = ?1", table, key), [id])
+                .map_err(|e| e.to_string())?;
+        }
+        // Undoing a delete inserts the row with its original id
+        (None, Some(target)) => {
+            if current.is_some() {
+                return Err("A row with the same id exists again".to_string());
+            }
+            let names: Vec<&String> = target.keys().filter(|c| columns.contains(c)).collect();
+            let placeholders: Vec<String> = (1..=names.len()).map(|i| format!("?{}", i)).collect();
+            let values: Vec<SqlValue> = names.iter().map(|c| to_sql_value(&target[c.as_str()])).collect();
+            conn.execute(
+                &format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    table,
+                    names.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", "),
+                    placeholders.join(", ")
+                ),
+                rusqlite::params_from_iter(values),
+            ).map_err(|e| e.to_string())?;
+        }
+        (Some(expected), Some(target)) => {
+            let current = current.ok_or("The row changed by this change no longer exists")?;
+            let touched: Vec<String> = changed_columns(expected, target)
+                .into_iter()
+                .filter(|c| columns.contains(c) && c != key)
+                .collect();
+
+            if touched.iter().any(|c| !same_value(current.get(c), expected.get(c))) {
+                return Err("A later change modified the same fields; undo that change first".to_string());
+            }
+            if touched.is_empty() {
+                return Ok(());
+            }
+
+            let mut assignments: Vec<String> = touched
+                .iter()
+                .enumerate()
+                .map(|(i, c)| format!("{} = ?{}", c, i + 1))
+                .collect();
+            for column in BOOKKEEPING_COLUMNS {
+                if columns.iter().any(|c| c == column) {
+                    assignments.push(format!("{} = datetime('now')", column));
+                }
+            }
+            let mut values: Vec<SqlValue> = touched
+                .iter()
+                .map(|c| to_sql_value(target.get(c).unwrap_or(&Value::Null)))
+                .collect();
+            values.push(SqlValue::Integer(id));
+
+            conn.execute(
+                &format!("UPDATE {} SET {} WHERE {} = ?{}", table, assignments.join(", "), key, values.len()),
+                rusqlite::params_from_iter(values),
+            ).map_err(|e| e.to_string())?;
+        }
+        (None, None) => return Err("This change has no row snapshots to restore".to_string()),
+    }
+
+    Ok(())
+}
+
+struct RecordedChange {
+    budget_id: i64,
+    change_type: String,
+    field_name: Option<String>,
+    old_value: Option<String>,
+    new_value: Option<String>,
+    description: String,
+    entity_table: Option<String>,
+    entity_id: Option<i64>,
+    before_snapshot: Option<String>,
+    after_snapshot: Option<String>,
+    reverts_change_id: Option<i64>,
+}
+
+fn load_change(conn: &Connection, change_id: i64) -> Result<RecordedChange, String> {
+    conn.query_row(
+        "SELECT budget_id, change_type, field_name, old_value, new_value, change_description,
+                entity_table, entity_id, before_snapshot, after_snapshot, reverts_change_id
+         FROM BudgetChangeHistory WHERE change_id = ?1",
+        [change_id],
+        |row| Ok(RecordedChange {
+            budget_id: row.get(0)?,
+            change_type: row.get(1)?,
+            field_name: row.get(2)?,
+            old_value: row.get(3)?,
+            new_value: row.get(4)?,
+            description: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            entity_table: row.get(6)?,
+            entity_id: row.get(7)?,
+            before_snapshot: row.get(8)?,
+            after_snapshot: row.get(9)?,
+            reverts_change_id: row.get(10)?,
+        })
+    ).map_err(|_| format!("Change {} not found", change_id))
+}
+
+// Applies the inverse of a recorded change and logs it as `kind` ("undo" or "redo")
+fn revert_change(conn: &Connection, change_id: i64, change: RecordedChange, kind: &str) -> Result<i64, String> {
+    let (table, entity_id) = match (change.entity_table.as_deref(), change.entity_id) {
+        (Some(table), Some(entity_id)) => (table, entity_id),
+        _ if change.change_type.starts_with("category_merge") || change.change_type.starts_with("category_split") => {
+            return Err("Category merges and splits are reverted with revert_category_operation".to_string());
+        }
+        // The history only records which file was attached or removed, not the file itself
+        _ if change.change_type.starts_with("attachment_") => {
+            return Err("Attachment changes cannot be undone; attach or delete the file again instead".to_string());
+        }
+        _ => return Err(format!("Change {} was recorded without snapshots and cannot be reverted", change_id)),
+    };
+
+    let before = parse_snapshot(change.before_snapshot)?;
+    let after = parse_snapshot(change.after_snapshot)?;
+    let current = row_snapshot(conn, table, entity_id)?;
+
+    // An undo is an edit like any other, so a reconciled entry stays locked
+    if table == "expenses" {
+        check_not_reconciled(conn, entity_id)?;
+        if moves_transfer_side(before.as_ref(), after.as_ref()) {
+            return Err("Both sides of a transfer move together; delete it or restore it from the trash instead".to_string());
+        }
+    }
+
+    restore_row(conn, table, entity_id, after.as_ref(), before.as_ref())?;
+    if table == "expenses" || table == "entry_splits" {
+        rebalance_entry_splits(conn, entity_id)?;
+    }
+
+    let label = if kind == "undo" { "Undid" } else { "Redid" };
+    let description = format!("{}: {}", label, change.description);
+    let new_change_id = log_budget_change(conn, change.budget_id, kind, change.field_name.as_deref(),
+                                          change.new_value.as_deref(), change.old_value.as_deref(), &description)?;
+    attach_row_snapshots(conn, new_change_id, table, entity_id, current)?;
+
+    conn.execute(
+        "UPDATE BudgetChangeHistory SET reverts_change_id = ?1 WHERE change_id = ?2",
+        rusqlite::params![change_id, new_change_id],
+    ).map_err(|e| e.to_string())?;
+    audit::seal_history_row(conn, new_change_id).map_err(|e| e.to_string())?;
+
+    // Formula allocations depend on incomes and other categories' amounts
+    if table == "MonthlyBudgets" || table == "budget_categories" {
+        let budget_exists: bool = conn.query_row(
+            "SELECT COUNT(*) FROM MonthlyBudgets WHERE budget_id = ?1",
+            [change.budget_id],
+            |row| row.get::<_, i64>(0)
+        ).map_err(|e| e.to_string())? > 0;
+
+        if budget_exists {
+            recompute_budget_allocations(conn, change.budget_id)?;
+        }
+    }
+
+    Ok(new_change_id)
+}
+
+#[tauri::command]
+pub fn undo_change(change_id: i64, app: AppHandle, db: State<DbState>) -> Result<i64, String> {
+    println!("=== UNDO_CHANGE COMMAND CALLED ===");
+    println!("Undoing change ID: {}", change_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let change = load_change(&tx, change_id)?;
+    if change.change_type == "undo" {
+        return Err("This change is itself an undo; use redo_change to re-apply the original".to_string());
+    }
+
+    let budget_id = change.budget_id;
+    let undo_id = revert_change(&tx, change_id, change, "undo")?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    raise_budget_alerts(&app, &conn, budget_id);
+
+    println!("Successfully undid change {} (history entry {})", change_id, undo_id);
+    Ok(undo_id)
+}
+
+/// Re-applies an undone change. Accepts either the undo entry or the original change.
+#[tauri::command]
+pub fn redo_change(change_id: i64, app: AppHandle, db: State<DbState>) -> Result<i64, String> {
+    println!("=== REDO_CHANGE COMMAND CALLED ===");
+    println!("Redoing change ID: {}", change_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut undo_id = change_id;
+    let mut change = load_change(&tx, change_id)?;
+    if change.change_type != "undo" {
+        undo_id = tx.query_row(
+            "SELECT MAX(change_id) FROM BudgetChangeHistory WHERE change_type = 'undo' AND reverts_change_id = ?1",
+            [change_id],
+            |row| row.get::<_, Option<i64>>(0)
+        ).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Change {} has not been undone", change_id))?;
+        change = load_change(&tx, undo_id)?;
+    }
+
+    let original_id = change.reverts_change_id;
+    let budget_id = change.budget_id;
+    let redo_id = revert_change(&tx, undo_id, change, "redo")?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    raise_budget_alerts(&app, &conn, budget_id);
+
+    println!("Successfully redid change {:?} (history entry {})", original_id, redo_id);
+    Ok(redo_id)
+}
+
+const DEFAULT_HISTORY_PAGE_SIZE: u32 = 50;
+const MAX_HISTORY_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryQueryArgs {
+    pub budget_id: Option<i64>,             // None searches every budget
+    pub change_types: Option<Vec<String>>,  // e.g. ["entry_add", "entry_delete"]
+    pub field_name: Option<String>,
+    pub from: Option<String>,               // "YYYY-MM-DD" or "YYYY-MM-DD HH:MM:SS", inclusive
+    pub to: Option<String>,                 // inclusive; a bare date covers the whole day
+    pub search: Option<String>,             // every word must appear in the description
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub ascending: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    pub entries: Vec<BudgetChangeHistoryEntry>,
+    pub total_count: i64,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+pub(crate) fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn check_datetime(conn: &Connection, value: &str) -> Result<(), String> {
+    let parsed: Option<String> = conn
+        .query_row("SELECT datetime(?1)", [value], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    parsed.map(|_| ()).ok_or_else(|| format!("Invalid date: {}", value))
+}
+
+#[tauri::command]
+pub fn query_change_history(args: HistoryQueryArgs, db: State<DbState>) -> Result<HistoryPage, String> {
+    println!("=== QUERY_CHANGE_HISTORY COMMAND CALLED ===");
+    println!("History query: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut conditions: Vec<String> = Vec::new();
+    let mut params: Vec<SqlValue> = Vec::new();
+
+    if let Some(budget_id) = args.budget_id {
+        params.push(SqlValue::Integer(budget_id));
+        conditions.push(format!("budget_id = ?{}", params.len()));
+    }
+
+    if let Some(types) = args.change_types.as_ref().filter(|t| !t.is_empty()) {
+        let mut placeholders = Vec::new();
+        for change_type in types {
+            params.push(SqlValue::Text(change_type.clone()));
+            placeholders.push(format!("?{}", params.len()));
+        }
+        conditions.push(format!("change_type IN ({})", placeholders.join(", ")));
+    }
+
+    if let Some(field_name) = &args.field_name {
+        params.push(SqlValue::Text(field_name.clone()));
+        conditions.push(format!("field_name = ?{}", params.len()));
+    }
+
+    if let Some(from) = &args.from {
+        check_datetime(&conn, from)?;
+        params.push(SqlValue::Text(from.clone()));
+        conditions.push(format!("changed_at >= datetime(?{})", params.len()));
+    }
+
+    if let Some(to) = &args.to {
+        check_datetime(&conn, to)?;
+        params.push(SqlValue::Text(to.clone()));
+        if to.trim().len() == 10 {
+            conditions.push(format!("changed_at < datetime(?{}, '+1 day')", params.len()));
+        } else {
+            conditions.push(format!("changed_at <= datetime(?{})", params.len()));
+        }
+    }
+
+    if let Some(search) = &args.search {
+        for term in search.split_whitespace() {
+            params.push(SqlValue::Text(format!("%{}%", escape_like(term))));
+            conditions.push(format!("change_description LIKE ?{} ESCAPE '\\'", params.len()));
+        }
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let total_count: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM BudgetChangeHistory {}", where_clause),
+        rusqlite::params_from_iter(params.iter()),
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    let limit = args.limit.unwrap_or(DEFAULT_HISTORY_PAGE_SIZE).clamp(1, MAX_HISTORY_PAGE_SIZE);
+    let offset = args.offset.unwrap_or(0);
+    let direction = if args.ascending { "ASC" } else { "DESC" };
+
+    params.push(SqlValue::Integer(limit as i64));
+    params.push(SqlValue::Integer(offset as i64));
+    let query = format!(
+        "SELECT change_id, budget_id, change_type, field_name, old_value, new_value, change_description, changed_at,
+                entity_table, entity_id, before_snapshot, after_snapshot, reverts_change_id
+         FROM BudgetChangeHistory {}
+         ORDER BY changed_at {}, change_id {}
+         LIMIT ?{} OFFSET ?{}",
+        where_clause, direction, direction, params.len() - 1, params.len()
+    );
+
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+        Ok(BudgetChangeHistoryEntry {
+            change_id: row.get(0)?,
+            budget_id: row.get(1)?,
+            change_type: row.get(2)?,
+            field_name: row.get(3)?,
+            old_value: row.get(4)?,
+            new_value: row.get(5)?,
+            change_description: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+            changed_at: row.get(7)?,
+            entity_table: row.get(8)?,
+            entity_id: row.get(9)?,
+            before_snapshot: row.get(10)?,
+            after_snapshot: row.get(11)?,
+            reverts_change_id: row.get(12)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row.map_err(|e| e.to_string())?);
+    }
+
+    println!("History query matched {} rows, returning {}", total_count, entries.len());
+    Ok(HistoryPage { entries, total_count, limit, offset })
+}
@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::modules::commands::search::check_date;
+use crate::modules::database::DbState;
+
+const DEFAULT_SUGGESTION_LIMIT: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Merchant {
+    pub merchant_id: i64,
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub entries_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceVariant {
+    pub place: String,
+    pub entries_count: i64,
+    pub merchant_id: Option<i64>, // set when the spelling is already an alias
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerchantSuggestion {
+    pub suggested_name: String,
+    pub merchant_id: Option<i64>, // the existing merchant the variants could join, if any
+    pub variants: Vec<PlaceVariant>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerchantReportArgs {
+    #[serde(default)]
+    pub budget_id: Option<i64>,
+    #[serde(default)]
+    pub from: Option<String>, // "YYYY-MM-DD"
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub merchant_ids: Option<Vec<i64>>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerchantTotal {
+    pub merchant_id: Option<i64>, // None for places no merchant claims yet
+    pub merchant: String,
+    pub total_spent: f64,
+    pub visits: i64,
+    pub average_ticket: f64,
+    pub budgets_count: i64,
+    pub first_visit: String,
+    pub last_visit: String,
+}
+
+/// The form aliases are stored and matched in. Matches SQLite's LOWER(TRIM(place)), which
+/// only lowercases ASCII, so the two always agree.
+pub(crate) fn place_key(place: &str) -> String {
+    place.trim_matches(' ').to_ascii_lowercase()
+}
+
+fn clean_name(name: &str) -> Result<String, String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        return Err("Merchant name cannot be empty".to_string());
+    }
+    Ok(name)
+}
+
+fn load_merchant(conn: &Connection, merchant_id: i64) -> Result<Merchant, String> {
+    let (name, entries_count): (String, i64) = conn.query_row(
+        "SELECT m.name,
+                (SELECT COUNT(*) FROM entry_merchants em
+                 JOIN expenses e ON e.expense_id = em.expense_id
+                 WHERE em.merchant_id = m.merchant_id AND e.deleted_at IS NULL)
+         FROM merchants m WHERE m.merchant_id = ?1",
+        [merchant_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|_| format!("Merchant {} not found", merchant_id))?;
+
+    let mut stmt = conn.prepare("SELECT alias FROM merchant_aliases WHERE merchant_id = ?1 ORDER BY alias ASC")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([merchant_id], |row| row.get(0)).map_err(|e| e.to_string())?;
+    let mut aliases = Vec::new();
+    for row in rows {
+        aliases.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(Merchant { merchant_id, name, aliases, entries_count })
+}
+
+// Points the alias at the merchant, refusing to take it away from a different one
+fn claim_alias(conn: &Connection, merchant_id: i64, alias: &str) -> Result<(), String> {
+    let key = place_key(alias);
+    if key.is_empty() {
+        return Err("Alias cannot be empty".to_string());
+    }
+
+    let owner: Option<(i64, String)> = conn.query_row(
+        "SELECT m.merchant_id, m.name FROM merchant_aliases a
+         JOIN merchants m ON m.merchant_id = a.merchant_id
+         WHERE a.alias = ?1",
+        [&key],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).optional().map_err(|e| e.to_string())?;
+
+    match owner {
+        Some((owner_id, _)) if owner_id == merchant_id => Ok(()),
+        Some((_, owner_name)) => Err(format!("'{}' already belongs to {}", alias.trim(), owner_name)),
+        None => {
+            conn.execute(
+                "INSERT INTO merchant_aliases (alias, merchant_id) VALUES (?1, ?2)",
+                rusqlite::params![key, merchant_id],
+            ).map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_merchants(db: State<DbState>) -> Result<Vec<Merchant>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let ids: Vec<i64> = {
+        let mut stmt = conn.prepare("SELECT merchant_id FROM merchants ORDER BY name COLLATE NOCASE ASC")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| e.to_string())?);
+        }
+        ids
+    };
+
+    ids.into_iter().map(|id| load_merchant(&conn, id)).collect()
+}
+
+#[tauri::command]
+pub fn create_merchant(name: String, aliases: Option<Vec<String>>, db: State<DbState>) -> Result<Merchant, String> {
+    println!("=== CREATE_MERCHANT COMMAND CALLED ===");
+    println!("Creating merchant {} with aliases {:?}", name, aliases);
+
+    let name = clean_name(&name)?;
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute("INSERT INTO merchants (name) VALUES (?1)", [&name])
+        .map_err(|e| format!("Failed to create merchant: {}", e))?;
+    let merchant_id = tx.last_insert_rowid();
+
+    // The name itself is always a spelling of the merchant
+    claim_alias(&tx, merchant_id, &name)?;
+    for alias in aliases.unwrap_or_default() {
+        claim_alias(&tx, merchant_id, &alias)?;
+    }
+
+    let merchant = load_merchant(&tx, merchant_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Created merchant {} with {} aliases", merchant_id, merchant.aliases.len());
+    Ok(merchant)
+}
+
+#[tauri::command]
+pub fn rename_merchant(merchant_id: i64, name: String, db: State<DbState>) -> Result<Merchant, String> {
+    println!("=== RENAME_MERCHANT COMMAND CALLED ===");
+    println!("Renaming merchant {} to {}", merchant_id, name);
+
+    let name = clean_name(&name)?;
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let rows_affected = tx.execute(
+        "UPDATE merchants SET name = ?1 WHERE merchant_id = ?2",
+        rusqlite::params![name, merchant_id],
+    ).map_err(|e| format!("Failed to rename merchant: {}", e))?;
+    if rows_affected == 0 {
+        return Err(format!("Merchant {} not found", merchant_id));
+    }
+    claim_alias(&tx, merchant_id, &name)?;
+
+    let merchant = load_merchant(&tx, merchant_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(merchant)
+}
+
+#[tauri::command]
+pub fn delete_merchant(merchant_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== DELETE_MERCHANT COMMAND CALLED ===");
+    println!("Deleting merchant ID: {}", merchant_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    // Entries keep their place text; they just show up under it again
+    let rows_affected = conn.execute("DELETE FROM merchants WHERE merchant_id = ?1", [merchant_id])
+        .map_err(|e| e.to_string())?;
+    if rows_affected == 0 {
+        return Err(format!("Merchant {} not found", merchant_id));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn add_merchant_aliases(merchant_id: i64, aliases: Vec<String>, db: State<DbState>) -> Result<Merchant, String> {
+    println!("=== ADD_MERCHANT_ALIASES COMMAND CALLED ===");
+    println!("Adding aliases {:?} to merchant {}", aliases, merchant_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    load_merchant(&tx, merchant_id)?;
+    for alias in &aliases {
+        claim_alias(&tx, merchant_id, alias)?;
+    }
+
+    let merchant = load_merchant(&tx, merchant_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(merchant)
+}
+
+#[tauri::command]
+pub fn remove_merchant_alias(alias: String, db: State<DbState>) -> Result<(), String> {
+    println!("=== REMOVE_MERCHANT_ALIAS COMMAND CALLED ===");
+    println!("Removing alias: {}", alias);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let key = place_key(&alias);
+
+    let is_name: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM merchant_aliases a JOIN merchants m ON m.merchant_id = a.merchant_id
+                        WHERE a.alias = ?1 AND LOWER(m.name) = a.alias)",
+        [&key],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if is_name {
+        return Err(format!("'{}' is the merchant's name; rename the merchant instead", alias.trim()));
+    }
+
+    let rows_affected = conn.execute("DELETE FROM merchant_aliases WHERE alias = ?1", [&key])
+        .map_err(|e| e.to_string())?;
+    if rows_affected == 0 {
+        return Err(format!("Alias '{}' not found", alias.trim()));
+    }
+    Ok(())
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// The leading word of a place, e.g. "migros" for "MIGROS ZH" and "Migros Bahnhof"
+fn stem(key: &str) -> String {
+    key.split(|c: char| !c.is_alphanumeric())
+        .find(|word| !word.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn similar_stems(a: &str, b: &str) -> bool {
+    a == b || (a.chars().count() >= 5 && b.chars().count() >= 5 && levenshtein(a, b) <= 1)
+}
+
+/// Groups place spellings that probably mean the same merchant: the same leading word, or
+/// leading words one typo apart. Only groups that are not already a single merchant are returned.
+#[tauri::command]
+pub fn suggest_merchant_aliases(limit: Option<usize>, db: State<DbState>) -> Result<Vec<MerchantSuggestion>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT);
+
+    let variants: Vec<(String, PlaceVariant)> = {
+        let mut stmt = conn.prepare(
+            "SELECT em.place_key, MIN(TRIM(e.place)), COUNT(*), em.merchant_id
+             FROM entry_merchants em
+             JOIN expenses e ON e.expense_id = em.expense_id
+             WHERE e.deleted_at IS NULL
+             GROUP BY em.place_key
+             ORDER BY COUNT(*) DESC"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, PlaceVariant {
+                place: row.get(1)?,
+                entries_count: row.get(2)?,
+                merchant_id: row.get(3)?,
+            }))
+        }).map_err(|e| e.to_string())?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    let mut groups: Vec<(String, Vec<PlaceVariant>)> = Vec::new();
+    for (key, variant) in variants {
+        let variant_stem = stem(&key);
+        if variant_stem.chars().count() < 3 {
+            continue;
+        }
+        match groups.iter_mut().find(|(group_stem, _)| similar_stems(group_stem, &variant_stem)) {
+            Some((_, members)) => members.push(variant),
+            None => groups.push((variant_stem, vec![variant])),
+        }
+    }
+
+    let merchant_names: HashMap<i64, String> = {
+        let mut stmt = conn.prepare("SELECT merchant_id, name FROM merchants").map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).map_err(|e| e.to_string())?;
+        let mut names = HashMap::new();
+        for row in rows {
+            let (id, name) = row.map_err(|e| e.to_string())?;
+            names.insert(id, name);
+        }
+        names
+    };
+
+    let mut suggestions = Vec::new();
+    for (_, variants) in groups {
+        if variants.len() < 2 {
+            continue;
+        }
+        let first_merchant = variants[0].merchant_id;
+        if first_merchant.is_some() && variants.iter().all(|v| v.merchant_id == first_merchant) {
+            continue;
+        }
+
+        // Join the merchant that already has the most entries, else name it after the most used spelling
+        let merchant_id = variants.iter().find_map(|v| v.merchant_id);
+        let suggested_name = merchant_id
+            .and_then(|id| merchant_names.get(&id).cloned())
+            .unwrap_or_else(|| variants[0].place.clone());
+
+        suggestions.push(MerchantSuggestion { suggested_name, merchant_id, variants });
+        if suggestions.len() >= limit {
+            break;
+        }
+    }
+
+    Ok(suggestions)
+}
+
+#[tauri::command]
+pub fn get_merchant_report(args: MerchantReportArgs, db: State<DbState>) -> Result<Vec<MerchantTotal>, String> {
+    println!("=== GET_MERCHANT_REPORT COMMAND CALLED ===");
+    println!("Merchant report: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut conditions: Vec<String> = vec![
+        "e.deleted_at IS NULL".to_string(),
+        "c.deleted_at IS NULL".to_string(),
+        "b.deleted_at IS NULL".to_string(),
+        "e.entry_type = 'expense'".to_string(),
+    ];
+    let mut params: Vec<SqlValue> = Vec::new();
+
+    if let Some(budget_id) = args.budget_id {
+        params.push(SqlValue::Integer(budget_id));
+        conditions.push(format!("c.budget_id = ?{}", params.len()));
+    }
+
+    if let Some(from) = &args.from {
+        check_date(from)?;
+        params.push(SqlValue::Text(from.clone()));
+        conditions.push(format!("e.date >= ?{}", params.len()));
+    }
+
+    if let Some(to) = &args.to {
+        check_date(to)?;
+        params.push(SqlValue::Text(to.clone()));
+        conditions.push(format!("e.date <= ?{}", params.len()));
+    }
+
+    if let Some(ids) = args.merchant_ids.as_ref().filter(|ids| !ids.is_empty()) {
+        let mut placeholders = Vec::new();
+        for id in ids {
+            params.push(SqlValue::Integer(*id));
+            placeholders.push(format!("?{}", params.len()));
+        }
+        conditions.push(format!("em.merchant_id IN ({})", placeholders.join(", ")));
+    }
+
+    params.push(SqlValue::Integer(args.limit.unwrap_or(-1)));
+
+    // Places without a merchant are grouped by their spelling, case-insensitively
+    let query = format!(
+        "SELECT em.merchant_id, MIN(em.merchant), SUM(e.amount), COUNT(*), COUNT(DISTINCT c.budget_id),
+                MIN(e.date), MAX(e.date)
+         FROM entry_merchants em
+         JOIN expenses e ON e.expense_id = em.expense_id
+         JOIN budget_categories c ON c.category_id = e.category_id
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+         WHERE {}
+         GROUP BY COALESCE(CAST(em.merchant_id AS TEXT), 'place:' || em.place_key)
+         ORDER BY 3 DESC
+         LIMIT ?{}",
+        conditions.join(" AND "),
+        params.len()
+    );
+
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+        let total_spent: f64 = row.get(2)?;
+        let visits: i64 = row.get(3)?;
+        Ok(MerchantTotal {
+            merchant_id: row.get(0)?,
+            merchant: row.get(1)?,
+            total_spent,
+            visits,
+            average_ticket: total_spent / visits as f64,
+            budgets_count: row.get(4)?,
+            first_visit: row.get(5)?,
+            last_visit: row.get(6)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+
+    println!("Merchant report covers {} merchants", results.len());
+    Ok(results)
+}
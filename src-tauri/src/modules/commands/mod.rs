@@ -1,6 +1,23 @@
+pub mod account;
+pub mod alert;
+pub mod analytics;
+pub mod attachment;
 pub mod budget;
+pub mod category;
+pub mod debt;
 pub mod expense;
+pub mod goal;
+pub mod history;
+pub mod merchant;
+pub mod reconcile;
+pub mod reminder;
+pub mod report;
+pub mod search;
 pub mod security;
+pub mod split;
+pub mod tag;
+pub mod transfer;
+pub mod trash;
 
 // Simple greet for sanity
 #[tauri::command]
@@ -0,0 +1,432 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::modules::commands::account::{is_liability, ACCOUNT_FLOW_SQL};
+use crate::modules::commands::budget::log_budget_change;
+use crate::modules::commands::history::{attach_row_snapshots, row_snapshot};
+use crate::modules::commands::search::check_date;
+use crate::modules::database::DbState;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcileEntry {
+    pub entry_id: i64,
+    pub budget_id: i64,
+    pub category_name: String,
+    pub entry_type: String,
+    pub what: String,
+    pub r#where: Option<String>,
+    pub amount: f64,
+    pub date: String,
+    pub cleared: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reconciliation {
+    pub reconciliation_id: i64,
+    pub account_id: i64,
+    pub account_name: String,
+    pub statement_date: String,
+    pub statement_balance: f64,
+    pub status: String,           // "open" | "finished"
+    pub reconciled_balance: f64,  // opening balance moved by every entry reconciled before
+    pub cleared_balance: f64,     // that plus the entries cleared in this reconciliation
+    pub difference: f64,          // statement balance minus cleared balance; finish at zero
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub entries: Vec<ReconcileEntry>, // unreconciled entries up to the statement date
+}
+
+/// Fails when the entry was reconciled against a statement and has not been unlocked since.
+pub(crate) fn check_not_reconciled(conn: &Connection, entry_id: i64) -> Result<(), String> {
+    let reconciled_at: Option<String> = conn.query_row(
+        "SELECT reconciled_at FROM expenses WHERE expense_id = ?1",
+        [entry_id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?.flatten();
+
+    match reconciled_at {
+        Some(_) => Err(format!("Entry {} is reconciled against a bank statement; unlock it before editing", entry_id)),
+        None => Ok(()),
+    }
+}
+
+/// Fails when the budget, or just the given category of it, holds reconciled entries. Once
+/// in the trash they would be purged along with it.
+pub(crate) fn check_no_reconciled_entries(conn: &Connection, budget_id: i64, category_id: Option<i64>) -> Result<(), String> {
+    let reconciled: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE c.budget_id = ?1 AND (?2 IS NULL OR c.category_id = ?2) AND e.reconciled_at IS NOT NULL",
+        rusqlite::params![budget_id, category_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    if reconciled > 0 {
+        return Err(format!("It holds {} reconciled entries; unlock them before deleting it", reconciled));
+    }
+    Ok(())
+}
+
+// Budget and description of an entry, for the change history
+fn entry_info(conn: &Connection, entry_id: i64) -> Result<(i64, String), String> {
+    conn.query_row(
+        "SELECT c.budget_id, e.description FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE e.expense_id = ?1",
+        [entry_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|_| format!("Entry {} not found", entry_id))
+}
+
+// Signed movement of an account's balance by its entries that match `filter`
+fn account_flow(conn: &Connection, account_id: i64, filter: &str) -> Result<f64, String> {
+    conn.query_row(
+        &format!(
+            "SELECT COALESCE(SUM({}), 0)
+             FROM expenses e
+             JOIN budget_categories c ON c.category_id = e.category_id
+             JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+             WHERE e.account_id = ?1 AND e.deleted_at IS NULL AND c.deleted_at IS NULL AND b.deleted_at IS NULL
+               AND {}",
+            ACCOUNT_FLOW_SQL,
+            filter
+        ),
+        [account_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())
+}
+
+fn load_reconciliation(conn: &Connection, reconciliation_id: i64) -> Result<Reconciliation, String> {
+    let (account_id, account_name, account_type, opening_balance, statement_date, statement_balance, status, started_at, finished_at):
+        (i64, String, String, f64, String, f64, String, String, Option<String>) = conn.query_row(
+        "SELECT r.account_id, a.name, a.account_type, a.opening_balance, r.statement_date, r.statement_balance,
+                r.status, r.started_at, r.finished_at
+         FROM reconciliations r
+         JOIN accounts a ON a.account_id = r.account_id
+         WHERE r.reconciliation_id = ?1",
+        [reconciliation_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?,
+                  row.get(6)?, row.get(7)?, row.get(8)?))
+    ).map_err(|_| format!("Reconciliation {} not found", reconciliation_id))?;
+
+    // Credit cards and loans are reconciled on the amount owed
+    let sign = if is_liability(&account_type) { -1.0 } else { 1.0 };
+    let finished = status == "finished";
+
+    let (reconciled_filter, cleared_filter) = if finished {
+        (
+            format!("e.reconciled_at IS NOT NULL AND COALESCE(e.reconciliation_id, 0) < {}", reconciliation_id),
+            format!("e.reconciliation_id = {}", reconciliation_id),
+        )
+    } else {
+        (
+            "e.reconciled_at IS NOT NULL".to_string(),
+            "e.reconciled_at IS NULL AND e.cleared_at IS NOT NULL".to_string(),
+        )
+    };
+    let reconciled_balance = opening_balance + sign * account_flow(conn, account_id, &reconciled_filter)?;
+    let cleared_balance = reconciled_balance + sign * account_flow(conn, account_id, &cleared_filter)?;
+
+    let entries = if finished {
+        Vec::new()
+    } else {
+        let mut stmt = conn.prepare(
+            "SELECT e.expense_id, c.budget_id, c.category_name, e.entry_type, e.description, e.place, e.amount, e.date,
+                    e.cleared_at IS NOT NULL
+             FROM expenses e
+             JOIN budget_categories c ON c.category_id = e.category_id
+             JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+             WHERE e.account_id = ?1 AND e.deleted_at IS NULL AND c.deleted_at IS NULL AND b.deleted_at IS NULL
+               AND e.reconciled_at IS NULL AND (e.date <= ?2 OR e.cleared_at IS NOT NULL)
+             ORDER BY e.date ASC, e.expense_id ASC"
+        ).map_err(|e| e.to_string())?;
+
+        let rows = stmt.query_map(rusqlite::params![account_id, statement_date], |row| {
+            Ok(ReconcileEntry {
+                entry_id: row.get(0)?,
+                budget_id: row.get(1)?,
+                category_name: row.get(2)?,
+                entry_type: row.get(3)?,
+                what: row.get(4)?,
+                r#where: row.get(5)?,
+                amount: row.get(6)?,
+                date: row.get(7)?,
+                cleared: row.get(8)?,
+            })
+        }).map_err(|e| e.to_string())?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    Ok(Reconciliation {
+        reconciliation_id,
+        account_id,
+        account_name,
+        statement_date,
+        statement_balance,
+        status,
+        reconciled_balance,
+        cleared_balance,
+        difference: statement_balance - cleared_balance,
+        started_at,
+        finished_at,
+        entries,
+    })
+}
+
+fn open_reconciliation_of(conn: &Connection, reconciliation_id: i64) -> Result<i64, String> {
+    let (account_id, status): (i64, String) = conn.query_row(
+        "SELECT account_id, status FROM reconciliations WHERE reconciliation_id = ?1",
+        [reconciliation_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|_| format!("Reconciliation {} not found", reconciliation_id))?;
+
+    if status != "open" {
+        return Err(format!("Reconciliation {} is already finished", reconciliation_id));
+    }
+    Ok(account_id)
+}
+
+/// Starts reconciling an account against a statement, or updates the statement of the
+/// reconciliation already open for it.
+#[tauri::command]
+pub fn start_reconciliation(
+    account_id: i64,
+    statement_date: String,
+    statement_balance: f64,
+    db: State<DbState>,
+) -> Result<Reconciliation, String> {
+    println!("=== START_RECONCILIATION COMMAND CALLED ===");
+    println!("Reconciling account {} against {:.2} on {}", account_id, statement_balance, statement_date);
+
+    check_date(&statement_date)?;
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    conn.query_row("SELECT 1 FROM accounts WHERE account_id = ?1", [account_id], |_| Ok(()))
+        .map_err(|_| format!("Account {} not found", account_id))?;
+
+    let last_statement: Option<String> = conn.query_row(
+        "SELECT MAX(statement_date) FROM reconciliations WHERE account_id = ?1 AND status = 'finished'",
+        [account_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if let Some(last) = last_statement.filter(|last| statement_date < *last) {
+        return Err(format!("The account was already reconciled up to {}", last));
+    }
+
+    let open: Option<i64> = conn.query_row(
+        "SELECT reconciliation_id FROM reconciliations WHERE account_id = ?1 AND status = 'open'",
+        [account_id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+
+    let reconciliation_id = match open {
+        Some(id) => {
+            conn.execute(
+                "UPDATE reconciliations SET statement_date = ?1, statement_balance = ?2 WHERE reconciliation_id = ?3",
+                rusqlite::params![statement_date, statement_balance, id],
+            ).map_err(|e| e.to_string())?;
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO reconciliations (account_id, statement_date, statement_balance) VALUES (?1, ?2, ?3)",
+                rusqlite::params![account_id, statement_date, statement_balance],
+            ).map_err(|e| e.to_string())?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    load_reconciliation(&conn, reconciliation_id)
+}
+
+#[tauri::command]
+pub fn get_reconciliation(reconciliation_id: i64, db: State<DbState>) -> Result<Reconciliation, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    load_reconciliation(&conn, reconciliation_id)
+}
+
+#[tauri::command]
+pub fn list_reconciliations(account_id: i64, db: State<DbState>) -> Result<Vec<Reconciliation>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let ids: Vec<i64> = {
+        let mut stmt = conn.prepare(
+            "SELECT reconciliation_id FROM reconciliations WHERE account_id = ?1
+             ORDER BY statement_date DESC, reconciliation_id DESC"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([account_id], |row| row.get(0)).map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| e.to_string())?);
+        }
+        ids
+    };
+
+    ids.into_iter().map(|id| load_reconciliation(&conn, id)).collect()
+}
+
+#[tauri::command]
+pub fn set_entries_cleared(
+    reconciliation_id: i64,
+    entry_ids: Vec<i64>,
+    cleared: bool,
+    db: State<DbState>,
+) -> Result<Reconciliation, String> {
+    println!("=== SET_ENTRIES_CLEARED COMMAND CALLED ===");
+    println!("Marking entries {:?} as {}", entry_ids, if cleared { "cleared" } else { "not cleared" });
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let account_id = open_reconciliation_of(&tx, reconciliation_id)?;
+
+    for entry_id in &entry_ids {
+        let was_cleared: bool = tx.query_row(
+            "SELECT cleared_at IS NOT NULL FROM expenses
+             WHERE expense_id = ?1 AND account_id = ?2 AND deleted_at IS NULL AND reconciled_at IS NULL",
+            rusqlite::params![entry_id, account_id],
+            |row| row.get(0)
+        ).optional().map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Entry {} is not an unreconciled entry of this account", entry_id))?;
+        if was_cleared == cleared {
+            continue;
+        }
+
+        let before = row_snapshot(&tx, "expenses", *entry_id)?;
+        tx.execute(
+            "UPDATE expenses SET cleared_at = CASE WHEN ?1 THEN datetime('now') END WHERE expense_id = ?2",
+            rusqlite::params![cleared, entry_id],
+        ).map_err(|e| e.to_string())?;
+
+        let (budget_id, what) = entry_info(&tx, *entry_id)?;
+        let (change_type, description) = if cleared {
+            ("entry_clear", format!("Marked entry {} ({}) as cleared", entry_id, what))
+        } else {
+            ("entry_unclear", format!("Marked entry {} ({}) as not cleared", entry_id, what))
+        };
+        let change_id = log_budget_change(&tx, budget_id, change_type, Some("cleared_at"), None, None, &description)?;
+        attach_row_snapshots(&tx, change_id, "expenses", *entry_id, before)?;
+    }
+
+    let reconciliation = load_reconciliation(&tx, reconciliation_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(reconciliation)
+}
+
+/// Locks the cleared entries once they add up to the statement balance, and records the
+/// statement balance as a balance snapshot of the account.
+#[tauri::command]
+pub fn finish_reconciliation(reconciliation_id: i64, db: State<DbState>) -> Result<Reconciliation, String> {
+    println!("=== FINISH_RECONCILIATION COMMAND CALLED ===");
+    println!("Finishing reconciliation ID: {}", reconciliation_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let account_id = open_reconciliation_of(&tx, reconciliation_id)?;
+    let current = load_reconciliation(&tx, reconciliation_id)?;
+    if current.difference.abs() >= 0.005 {
+        return Err(format!(
+            "Cleared balance ${:.2} is ${:.2} away from the statement balance ${:.2}",
+            current.cleared_balance, current.difference, current.statement_balance
+        ));
+    }
+
+    let cleared_ids: Vec<i64> = {
+        let mut stmt = tx.prepare(
+            "SELECT expense_id FROM expenses
+             WHERE account_id = ?1 AND deleted_at IS NULL AND reconciled_at IS NULL AND cleared_at IS NOT NULL
+             ORDER BY expense_id ASC"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([account_id], |row| row.get(0)).map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| e.to_string())?);
+        }
+        ids
+    };
+
+    for entry_id in &cleared_ids {
+        let before = row_snapshot(&tx, "expenses", *entry_id)?;
+        tx.execute(
+            "UPDATE expenses SET reconciled_at = datetime('now'), reconciliation_id = ?1 WHERE expense_id = ?2",
+            rusqlite::params![reconciliation_id, entry_id],
+        ).map_err(|e| e.to_string())?;
+
+        let (budget_id, what) = entry_info(&tx, *entry_id)?;
+        let description = format!("Reconciled entry {} ({}) against the statement of {}", entry_id, what, current.statement_date);
+        let change_id = log_budget_change(&tx, budget_id, "entry_reconcile", Some("reconciled_at"), None, None, &description)?;
+        attach_row_snapshots(&tx, change_id, "expenses", *entry_id, before)?;
+    }
+    let locked = cleared_ids.len();
+
+    tx.execute(
+        "UPDATE reconciliations SET status = 'finished', cleared_balance = ?1, finished_at = datetime('now')
+         WHERE reconciliation_id = ?2",
+        rusqlite::params![current.cleared_balance, reconciliation_id],
+    ).map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO account_balances (account_id, balance_date, balance, note) VALUES (?1, ?2, ?3, 'Reconciled statement')
+         ON CONFLICT(account_id, balance_date) DO UPDATE SET balance = excluded.balance, note = excluded.note",
+        rusqlite::params![account_id, current.statement_date, current.statement_balance],
+    ).map_err(|e| e.to_string())?;
+
+    let reconciliation = load_reconciliation(&tx, reconciliation_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Reconciliation {} finished, {} entries locked", reconciliation_id, locked);
+    Ok(reconciliation)
+}
+
+/// Drops an open reconciliation. Cleared marks stay for the next attempt.
+#[tauri::command]
+pub fn cancel_reconciliation(reconciliation_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== CANCEL_RECONCILIATION COMMAND CALLED ===");
+    println!("Cancelling reconciliation ID: {}", reconciliation_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    open_reconciliation_of(&conn, reconciliation_id)?;
+
+    conn.execute("DELETE FROM reconciliations WHERE reconciliation_id = ?1", [reconciliation_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Unlocks a reconciled entry so it can be edited again. It stays cleared, so it counts
+/// towards the next reconciliation of the account.
+#[tauri::command]
+pub fn unlock_reconciled_entry(entry_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== UNLOCK_RECONCILED_ENTRY COMMAND CALLED ===");
+    println!("Unlocking entry ID: {}", entry_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = row_snapshot(&tx, "expenses", entry_id)?;
+    let rows_affected = tx.execute(
+        "UPDATE expenses SET reconciled_at = NULL, reconciliation_id = NULL
+         WHERE expense_id = ?1 AND reconciled_at IS NOT NULL",
+        [entry_id],
+    ).map_err(|e| e.to_string())?;
+
+    if rows_affected == 0 {
+        return Err(format!("Entry {} is not reconciled", entry_id));
+    }
+
+    let (budget_id, what) = entry_info(&tx, entry_id)?;
+    let change_id = log_budget_change(&tx, budget_id, "entry_unlock", Some("reconciled_at"), None, None,
+                                      &format!("Unlocked reconciled entry {} ({})", entry_id, what))?;
+    attach_row_snapshots(&tx, change_id, "expenses", entry_id, before)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
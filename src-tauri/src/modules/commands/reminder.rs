@@ -0,0 +1,448 @@
+use std::time::Duration;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use time::macros::format_description;
+use time::{Date, Month, PrimitiveDateTime};
+
+use crate::modules::database::DbState;
+use crate::modules::utils::notify::notify;
+
+pub const REMINDER_EVENT: &str = "reminder-due";
+const REMINDER_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const REMINDER_KINDS: [&str; 3] = ["invoice", "recurring_bill", "custom"]; // budget deadlines are created by the scheduler
+const DEFAULT_REMINDER_TIME: &str = "09:00:00";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderArgs {
+    pub kind: String,
+    #[serde(default)]
+    pub title: Option<String>, // bill reminders default to the entry's description
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub amount: Option<f64>,
+    pub due_at: String, // "YYYY-MM-DD" or "YYYY-MM-DD HH:MM", local time
+    #[serde(default)]
+    pub repeat_months: Option<u32>, // bill reminders repeat monthly unless told otherwise
+    #[serde(default)]
+    pub entry_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    pub reminder_id: i64,
+    pub kind: String,
+    pub title: String,
+    pub notes: Option<String>,
+    pub amount: Option<f64>,
+    pub due_at: String,
+    pub repeat_months: u32,
+    pub budget_id: Option<i64>,
+    pub entry_id: Option<i64>,
+    pub snoozed_until: Option<String>,
+    pub last_fired_at: Option<String>,
+    pub dismissed_at: Option<String>,
+    pub created_at: String,
+    pub status: String, // "upcoming" | "snoozed" | "due" | "dismissed"
+    pub missed: bool,   // fired on catch-up after the app was closed past its time
+}
+
+const REMINDER_SELECT_SQL: &str = "
+    SELECT reminder_id, kind, title, notes, amount, due_at, repeat_months, budget_id, entry_id,
+           snoozed_until, last_fired_at, dismissed_at, created_at,
+           CASE
+               WHEN dismissed_at IS NOT NULL THEN 'dismissed'
+               WHEN snoozed_until > datetime('now', 'localtime') THEN 'snoozed'
+               WHEN due_at > datetime('now', 'localtime') THEN 'upcoming'
+               ELSE 'due'
+           END
+    FROM reminders";
+
+fn reminder_from_row(row: &rusqlite::Row) -> rusqlite::Result<Reminder> {
+    Ok(Reminder {
+        reminder_id: row.get(0)?,
+        kind: row.get(1)?,
+        title: row.get(2)?,
+        notes: row.get(3)?,
+        amount: row.get(4)?,
+        due_at: row.get(5)?,
+        repeat_months: row.get(6)?,
+        budget_id: row.get(7)?,
+        entry_id: row.get(8)?,
+        snoozed_until: row.get(9)?,
+        last_fired_at: row.get(10)?,
+        dismissed_at: row.get(11)?,
+        created_at: row.get(12)?,
+        status: row.get(13)?,
+        missed: false,
+    })
+}
+
+fn load_reminder(conn: &Connection, reminder_id: i64) -> Result<Reminder, String> {
+    conn.query_row(
+        &format!("{} WHERE reminder_id = ?1", REMINDER_SELECT_SQL),
+        [reminder_id],
+        reminder_from_row
+    ).map_err(|_| format!("Reminder {} not found", reminder_id))
+}
+
+// Accepts a date (reminded at 09:00) or a date and time, with a space or a "T" between them
+fn normalize_due_at(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    let full = match input.len() {
+        10 => format!("{} {}", input, DEFAULT_REMINDER_TIME),
+        16 => format!("{}:00", input),
+        _ => input.to_string(),
+    }
+    .replacen('T', " ", 1);
+
+    let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+    PrimitiveDateTime::parse(&full, format)
+        .map_err(|_| format!("Invalid due date '{}'; expected YYYY-MM-DD or YYYY-MM-DD HH:MM", input))?
+        .format(format)
+        .map_err(|e| e.to_string())
+}
+
+// The occurrence `months` after `due_at`, on the anchor day or the last day of shorter months
+fn advance_months(due_at: &str, months: u32, anchor_day: u8) -> Result<String, String> {
+    let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+    let current = PrimitiveDateTime::parse(due_at, format).map_err(|e| e.to_string())?;
+
+    let index = current.year() as i64 * 12 + u8::from(current.month()) as i64 - 1 + months as i64;
+    let year = (index / 12) as i32;
+    let month = Month::try_from((index % 12 + 1) as u8).map_err(|e| e.to_string())?;
+    let day = anchor_day.min(time::util::days_in_year_month(year, month));
+    let date = Date::from_calendar_date(year, month, day).map_err(|e| e.to_string())?;
+
+    PrimitiveDateTime::new(date, current.time()).format(format).map_err(|e| e.to_string())
+}
+
+fn anchor_day_of(due_at: &str) -> Result<u8, String> {
+    due_at.get(8..10).and_then(|d| d.parse().ok()).ok_or_else(|| format!("Invalid due date '{}'", due_at))
+}
+
+// Reminder text, falling back to the linked entry for bills
+fn validate_reminder(conn: &Connection, args: &ReminderArgs) -> Result<(String, Option<f64>, u32), String> {
+    if !REMINDER_KINDS.contains(&args.kind.as_str()) {
+        return Err(format!("Unknown reminder kind '{}'", args.kind));
+    }
+    if args.amount.is_some_and(|amount| amount < 0.0) {
+        return Err("Reminder amount cannot be negative".to_string());
+    }
+
+    let entry: Option<(String, f64)> = match args.entry_id {
+        Some(entry_id) => Some(conn.query_row(
+            "SELECT description, amount FROM expenses WHERE expense_id = ?1 AND deleted_at IS NULL",
+            [entry_id],
+            |row| Ok((row.get(0)?, row.get(1)?))
+        ).map_err(|_| format!("Entry {} not found", entry_id))?),
+        None => None,
+    };
+
+    let title = args.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).map(str::to_string)
+        .or_else(|| entry.as_ref().map(|(what, _)| what.clone()))
+        .ok_or_else(|| "Reminder title cannot be empty".to_string())?;
+    let amount = args.amount.or_else(|| entry.as_ref().map(|(_, amount)| *amount));
+    let repeat_months = args.repeat_months.unwrap_or(if args.kind == "recurring_bill" { 1 } else { 0 });
+
+    Ok((title, amount, repeat_months))
+}
+
+/// Creates a reminder for every budget whose month is over but which was never finished,
+/// and dismisses the ones whose budget has been finished since.
+fn sync_budget_deadlines(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        &format!(
+            "INSERT OR IGNORE INTO reminders (kind, title, due_at, budget_id)
+             SELECT 'budget_deadline',
+                    'Finish the ' || COALESCE(NULLIF(TRIM(b.name), ''), printf('%04d-%02d', b.year, b.month)) || ' budget',
+                    date(printf('%04d-%02d-01', b.year, b.month), '+1 month') || ' {}',
+                    b.budget_id
+             FROM MonthlyBudgets b
+             WHERE b.finished_at IS NULL AND b.deleted_at IS NULL
+               AND date(printf('%04d-%02d-01', b.year, b.month), '+1 month') <= date('now', 'localtime')",
+            DEFAULT_REMINDER_TIME
+        ),
+        [],
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE reminders SET dismissed_at = datetime('now', 'localtime')
+         WHERE kind = 'budget_deadline' AND dismissed_at IS NULL
+           AND budget_id IN (SELECT budget_id FROM MonthlyBudgets WHERE finished_at IS NOT NULL OR deleted_at IS NOT NULL)",
+        [],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// A repeating reminder left pending past its next occurrence moves on to the latest one that
+// has come, so that occurrence fires rather than the reminder staying on a stale date
+fn advance_passed_repeats(conn: &Connection) -> Result<(), String> {
+    let now: String = conn
+        .query_row("SELECT datetime('now', 'localtime')", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    let repeating: Vec<(i64, String, u32, Option<u8>)> = {
+        let mut stmt = conn.prepare(
+            "SELECT reminder_id, due_at, repeat_months, anchor_day FROM reminders
+             WHERE dismissed_at IS NULL AND repeat_months > 0 AND due_at <= ?1"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&now], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .map_err(|e| e.to_string())?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    for (reminder_id, due_at, repeat_months, anchor_day) in repeating {
+        let anchor_day = match anchor_day {
+            Some(day) => day,
+            None => anchor_day_of(&due_at)?,
+        };
+
+        let mut latest = due_at.clone();
+        loop {
+            let next = advance_months(&latest, repeat_months, anchor_day)?;
+            if next > now {
+                break;
+            }
+            latest = next;
+        }
+
+        if latest != due_at {
+            conn.execute(
+                "UPDATE reminders SET due_at = ?1, snoozed_until = NULL, last_fired_at = NULL WHERE reminder_id = ?2",
+                rusqlite::params![latest, reminder_id],
+            ).map_err(|e| e.to_string())?;
+            println!("Reminder {} skipped ahead from {} to {}", reminder_id, due_at, latest);
+        }
+    }
+    Ok(())
+}
+
+/// Fires every reminder whose time (or snooze) has come and that has not fired for it yet.
+/// On the first run after start-up these are the reminders missed while the app was closed;
+/// a repeating one fires once, for its latest occurrence.
+fn fire_due_reminders(app: &AppHandle, conn: &Connection, catch_up: bool) -> Result<usize, String> {
+    sync_budget_deadlines(conn)?;
+    advance_passed_repeats(conn)?;
+
+    let mut due: Vec<Reminder> = {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE dismissed_at IS NULL
+                AND COALESCE(snoozed_until, due_at) <= datetime('now', 'localtime')
+                AND (last_fired_at IS NULL OR last_fired_at < COALESCE(snoozed_until, due_at))
+              ORDER BY due_at ASC, reminder_id ASC",
+            REMINDER_SELECT_SQL
+        )).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], reminder_from_row).map_err(|e| e.to_string())?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    for reminder in &mut due {
+        conn.execute(
+            "UPDATE reminders SET last_fired_at = datetime('now', 'localtime') WHERE reminder_id = ?1",
+            [reminder.reminder_id],
+        ).map_err(|e| e.to_string())?;
+        reminder.missed = catch_up;
+
+        let title = if catch_up { "Missed reminder" } else { "Reminder" };
+        let body = match reminder.amount {
+            Some(amount) => format!("{} (${:.2}) — due {}", reminder.title, amount, reminder.due_at),
+            None => format!("{} — due {}", reminder.title, reminder.due_at),
+        };
+        notify(app, REMINDER_EVENT, &*reminder, title, &body);
+    }
+
+    Ok(due.len())
+}
+
+fn reminders_ready(conn: &Connection) -> bool {
+    conn.query_row("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'reminders'", [], |_| Ok(()))
+        .is_ok()
+}
+
+/// Starts the background thread that fires reminders. It waits for the migrations to have
+/// created the reminders table, then catches up on missed reminders and keeps polling.
+pub fn start_reminder_scheduler(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut catch_up = true;
+        loop {
+            let conn = app.state::<DbState>().get_conn();
+            match conn {
+                Ok(conn) if reminders_ready(&conn) => {
+                    match fire_due_reminders(&app, &conn, catch_up) {
+                        Ok(fired) if fired > 0 => println!("Fired {} reminder(s)", fired),
+                        Ok(_) => {}
+                        Err(e) => println!("Warning: failed to fire reminders: {}", e),
+                    }
+                    catch_up = false;
+                }
+                Ok(_) => {}
+                Err(e) => println!("Warning: reminder scheduler could not open the database: {}", e),
+            }
+            std::thread::sleep(REMINDER_POLL_INTERVAL);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn list_reminders(include_dismissed: bool, db: State<DbState>) -> Result<Vec<Reminder>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    sync_budget_deadlines(&conn)?;
+
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE ?1 OR dismissed_at IS NULL ORDER BY COALESCE(snoozed_until, due_at) ASC, reminder_id ASC",
+        REMINDER_SELECT_SQL
+    )).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([include_dismissed], reminder_from_row).map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn create_reminder(args: ReminderArgs, db: State<DbState>) -> Result<Reminder, String> {
+    println!("=== CREATE_REMINDER COMMAND CALLED ===");
+    println!("Reminder: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let (title, amount, repeat_months) = validate_reminder(&conn, &args)?;
+    let due_at = normalize_due_at(&args.due_at)?;
+    let anchor_day = anchor_day_of(&due_at)?;
+
+    conn.execute(
+        "INSERT INTO reminders (kind, title, notes, amount, due_at, repeat_months, anchor_day, entry_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![args.kind, title, args.notes, amount, due_at, repeat_months, anchor_day, args.entry_id],
+    ).map_err(|e| format!("Failed to create reminder: {}", e))?;
+
+    let reminder_id = conn.last_insert_rowid();
+    println!("Created reminder {}", reminder_id);
+    load_reminder(&conn, reminder_id)
+}
+
+/// Rescheduling a reminder lets it fire again, even if it had fired or been dismissed.
+#[tauri::command]
+pub fn update_reminder(reminder_id: i64, args: ReminderArgs, db: State<DbState>) -> Result<Reminder, String> {
+    println!("=== UPDATE_REMINDER COMMAND CALLED ===");
+    println!("Reminder {}: {:?}", reminder_id, args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let current = load_reminder(&conn, reminder_id)?;
+    if current.kind == "budget_deadline" {
+        return Err("Budget deadline reminders follow their budget; snooze or dismiss them instead".to_string());
+    }
+
+    let (title, amount, repeat_months) = validate_reminder(&conn, &args)?;
+    let due_at = normalize_due_at(&args.due_at)?;
+    let anchor_day = anchor_day_of(&due_at)?;
+    let rescheduled = due_at != current.due_at;
+
+    conn.execute(
+        "UPDATE reminders
+         SET kind = ?1, title = ?2, notes = ?3, amount = ?4, due_at = ?5, repeat_months = ?6, anchor_day = ?7, entry_id = ?8,
+             snoozed_until = CASE WHEN ?9 THEN NULL ELSE snoozed_until END,
+             last_fired_at = CASE WHEN ?9 THEN NULL ELSE last_fired_at END,
+             dismissed_at = CASE WHEN ?9 THEN NULL ELSE dismissed_at END
+         WHERE reminder_id = ?10",
+        rusqlite::params![args.kind, title, args.notes, amount, due_at, repeat_months, anchor_day, args.entry_id,
+                          rescheduled, reminder_id],
+    ).map_err(|e| format!("Failed to update reminder: {}", e))?;
+
+    load_reminder(&conn, reminder_id)
+}
+
+#[tauri::command]
+pub fn delete_reminder(reminder_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== DELETE_REMINDER COMMAND CALLED ===");
+    println!("Deleting reminder ID: {}", reminder_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let rows_affected = conn.execute("DELETE FROM reminders WHERE reminder_id = ?1", [reminder_id])
+        .map_err(|e| e.to_string())?;
+    if rows_affected == 0 {
+        return Err(format!("Reminder {} not found", reminder_id));
+    }
+    Ok(())
+}
+
+/// Puts a reminder off for the given number of minutes; it fires again once they are up.
+#[tauri::command]
+pub fn snooze_reminder(reminder_id: i64, minutes: u32, db: State<DbState>) -> Result<Reminder, String> {
+    println!("=== SNOOZE_REMINDER COMMAND CALLED ===");
+    println!("Snoozing reminder {} for {} minutes", reminder_id, minutes);
+
+    if minutes == 0 {
+        return Err("Snooze time must be at least one minute".to_string());
+    }
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let rows_affected = conn.execute(
+        "UPDATE reminders SET snoozed_until = datetime('now', 'localtime', '+' || ?1 || ' minutes')
+         WHERE reminder_id = ?2 AND dismissed_at IS NULL",
+        rusqlite::params![minutes, reminder_id],
+    ).map_err(|e| e.to_string())?;
+
+    if rows_affected == 0 {
+        return Err(format!("Reminder {} not found or already dismissed", reminder_id));
+    }
+    load_reminder(&conn, reminder_id)
+}
+
+/// Dismisses the pending occurrence. Repeating reminders move on to their next date instead
+/// of going away.
+#[tauri::command]
+pub fn dismiss_reminder(reminder_id: i64, db: State<DbState>) -> Result<Reminder, String> {
+    println!("=== DISMISS_REMINDER COMMAND CALLED ===");
+    println!("Dismissing reminder ID: {}", reminder_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let current = load_reminder(&conn, reminder_id)?;
+    if current.dismissed_at.is_some() {
+        return Err(format!("Reminder {} is already dismissed", reminder_id));
+    }
+
+    if current.repeat_months > 0 {
+        let anchor_day: Option<u8> = conn.query_row(
+            "SELECT anchor_day FROM reminders WHERE reminder_id = ?1",
+            [reminder_id],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+        let anchor_day = match anchor_day {
+            Some(day) => day,
+            None => anchor_day_of(&current.due_at)?,
+        };
+        let next_due = advance_months(&current.due_at, current.repeat_months, anchor_day)?;
+
+        conn.execute(
+            "UPDATE reminders SET due_at = ?1, snoozed_until = NULL, last_fired_at = NULL WHERE reminder_id = ?2",
+            rusqlite::params![next_due, reminder_id],
+        ).map_err(|e| e.to_string())?;
+        println!("Reminder {} moved to {}", reminder_id, next_due);
+    } else {
+        conn.execute(
+            "UPDATE reminders SET dismissed_at = datetime('now', 'localtime') WHERE reminder_id = ?1",
+            [reminder_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    load_reminder(&conn, reminder_id)
+}
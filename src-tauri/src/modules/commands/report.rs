@@ -0,0 +1,515 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::modules::commands::analytics::parse_month;
+use crate::modules::database::DbState;
+use crate::modules::utils::pdf::{self, PdfLine};
+
+const DEFAULT_TOP_COUNT: u32 = 10;
+const MAX_TOP_COUNT: u32 = 100;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodReportArgs {
+    pub period: String, // "year" | "fiscal_year" | "quarter" | "custom"
+    #[serde(default)]
+    pub year: Option<i32>, // for fiscal years, the year the fiscal year starts in
+    #[serde(default)]
+    pub quarter: Option<u32>, // 1-4
+    #[serde(default)]
+    pub fiscal_year_start_month: Option<u32>, // 1-12, default 1
+    #[serde(default)]
+    pub from_month: Option<String>, // "YYYY-MM", custom periods only
+    #[serde(default)]
+    pub to_month: Option<String>,
+    #[serde(default)]
+    pub top_count: Option<u32>, // length of the top lists, default 10
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodMonth {
+    pub budget_id: i64,
+    pub year: i32,
+    pub month: u32,
+    pub income: f64, // budgeted income plus income entries
+    pub expenses: f64,
+    pub savings: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportCategory {
+    pub global_category_id: Option<i64>,
+    pub name: String,
+    pub spent: f64,
+    pub share_of_expenses: f64,
+    pub entries_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportPlace {
+    pub place: String,
+    pub spent: f64,
+    pub visits: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportTransaction {
+    pub entry_id: i64,
+    pub budget_id: i64,
+    pub category_name: String,
+    pub date: String,
+    pub what: String,
+    pub r#where: Option<String>,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodReport {
+    pub label: String,
+    pub from_month: String,
+    pub to_month: String,
+    pub budgets_count: usize,
+    pub budgeted_income: f64,
+    pub other_income: f64, // income entries booked to categories
+    pub total_income: f64,
+    pub total_expenses: f64,
+    pub net_savings: f64,
+    pub savings_rate: Option<f64>, // percent of total income; None without income
+    pub months: Vec<PeriodMonth>,
+    pub top_categories: Vec<ReportCategory>,
+    pub top_places: Vec<ReportPlace>,
+    pub largest_transactions: Vec<ReportTransaction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportExport {
+    pub file_name: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+fn format_month(index: i64) -> String {
+    format!("{:04}-{:02}", index / 12, index % 12 + 1)
+}
+
+fn month_label(index: i64) -> String {
+    format!("{} {}", MONTH_NAMES[(index % 12) as usize], index / 12)
+}
+
+/// Turns the requested period into an inclusive range of month indexes and a label for it.
+fn resolve_period(args: &PeriodReportArgs) -> Result<(i64, i64, String), String> {
+    let year = || args.year.map(|y| y as i64).ok_or_else(|| format!("A {} report needs a year", args.period.replace('_', " ")));
+
+    match args.period.as_str() {
+        "year" => {
+            let year = year()?;
+            Ok((year * 12, year * 12 + 11, year.to_string()))
+        }
+        "fiscal_year" => {
+            let year = year()?;
+            let start_month = args.fiscal_year_start_month.unwrap_or(1);
+            if !(1..=12).contains(&start_month) {
+                return Err(format!("Invalid fiscal year start month {}", start_month));
+            }
+            let from = year * 12 + start_month as i64 - 1;
+            let label = if start_month == 1 {
+                format!("FY {}", year)
+            } else {
+                format!("FY {}/{:02}", year, (year + 1) % 100)
+            };
+            Ok((from, from + 11, label))
+        }
+        "quarter" => {
+            let year = year()?;
+            let quarter = args.quarter.ok_or("A quarter report needs a quarter")?;
+            if !(1..=4).contains(&quarter) {
+                return Err(format!("Invalid quarter {}", quarter));
+            }
+            let from = year * 12 + (quarter as i64 - 1) * 3;
+            Ok((from, from + 2, format!("Q{} {}", quarter, year)))
+        }
+        "custom" => {
+            let from = parse_month(args.from_month.as_deref().ok_or("A custom report needs a start month")?)?;
+            let to = parse_month(args.to_month.as_deref().ok_or("A custom report needs an end month")?)?;
+            if from > to {
+                return Err("The start month is after the end month".to_string());
+            }
+            let label = if from == to {
+                month_label(from)
+            } else {
+                format!("{} - {}", month_label(from), month_label(to))
+            };
+            Ok((from, to, label))
+        }
+        other => Err(format!("Unknown report period '{}'", other)),
+    }
+}
+
+// Entries that belong to the period: live entries in live categories of live budgets in range.
+// Expects ?1 = first and ?2 = last month index.
+const PERIOD_ENTRIES_SQL: &str = "
+    JOIN budget_categories c ON c.category_id = e.category_id
+    JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+    WHERE e.deleted_at IS NULL AND c.deleted_at IS NULL AND b.deleted_at IS NULL
+      AND b.year * 12 + b.month - 1 BETWEEN ?1 AND ?2";
+
+fn build_report(conn: &Connection, args: &PeriodReportArgs) -> Result<PeriodReport, String> {
+    let (from, to, label) = resolve_period(args)?;
+    let top_count = args.top_count.unwrap_or(DEFAULT_TOP_COUNT).clamp(1, MAX_TOP_COUNT);
+
+    // Transfers move money between categories and are neither income nor spending. Money put
+    // into savings categories is not spent either, so it shows up in the savings instead
+    let months: Vec<PeriodMonth> = {
+        let mut stmt = conn.prepare(
+            "SELECT b.budget_id, b.year, b.month, b.total_income,
+                    COALESCE((SELECT SUM(e.amount) FROM expenses e
+                              JOIN budget_categories c ON c.category_id = e.category_id
+                              WHERE c.budget_id = b.budget_id AND c.deleted_at IS NULL
+                                AND e.deleted_at IS NULL AND e.entry_type = 'income'), 0),
+                    COALESCE((SELECT SUM(e.amount) FROM entry_lines e
+                              JOIN budget_categories c ON c.category_id = e.category_id
+                              WHERE c.budget_id = b.budget_id AND c.deleted_at IS NULL
+                                AND e.deleted_at IS NULL AND e.entry_type = 'expense'
+                                AND c.category_type IS NOT 'savings'), 0)
+             FROM MonthlyBudgets b
+             WHERE b.deleted_at IS NULL AND b.year * 12 + b.month - 1 BETWEEN ?1 AND ?2
+             ORDER BY b.year ASC, b.month ASC"
+        ).map_err(|e| e.to_string())?;
+
+        let rows = stmt.query_map([from, to], |row| {
+            let budgeted: f64 = row.get(3)?;
+            let other: f64 = row.get(4)?;
+            let expenses: f64 = row.get(5)?;
+            Ok(PeriodMonth {
+                budget_id: row.get(0)?,
+                year: row.get(1)?,
+                month: row.get(2)?,
+                income: budgeted + other,
+                expenses,
+                savings: budgeted + other - expenses,
+            })
+        }).map_err(|e| e.to_string())?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    let budgeted_income: f64 = conn.query_row(
+        "SELECT COALESCE(SUM(total_income), 0) FROM MonthlyBudgets
+         WHERE deleted_at IS NULL AND year * 12 + month - 1 BETWEEN ?1 AND ?2",
+        [from, to],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    let total_income: f64 = months.iter().map(|m| m.income).sum();
+    let total_expenses: f64 = months.iter().map(|m| m.expenses).sum();
+    let net_savings = total_income - total_expenses;
+    let savings_rate = if total_income.abs() < 0.005 { None } else { Some(net_savings / total_income * 100.0) };
+
+    // Categories are compared across months through their global category; split entries
+    // count towards each part's category
+    let top_categories: Vec<ReportCategory> = {
+        let query = format!(
+            "SELECT c.global_category_id,
+                    COALESCE((SELECT g.name FROM global_categories g WHERE g.global_category_id = c.global_category_id),
+                             MIN(c.category_name)),
+                    SUM(e.amount), COUNT(DISTINCT e.expense_id)
+             FROM entry_lines e
+             {}
+               AND e.entry_type = 'expense' AND c.category_type IS NOT 'savings'
+             GROUP BY COALESCE(CAST(c.global_category_id AS TEXT), 'name:' || LOWER(c.category_name))
+             ORDER BY 3 DESC
+             LIMIT ?3",
+            PERIOD_ENTRIES_SQL
+        );
+        let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(rusqlite::params![from, to, top_count], |row| {
+            let spent: f64 = row.get(2)?;
+            Ok(ReportCategory {
+                global_category_id: row.get(0)?,
+                name: row.get(1)?,
+                spent,
+                share_of_expenses: if total_expenses.abs() < 0.005 { 0.0 } else { spent / total_expenses * 100.0 },
+                entries_count: row.get(3)?,
+            })
+        }).map_err(|e| e.to_string())?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    // Places are grouped by merchant, so the spellings of one shop count together
+    let top_places: Vec<ReportPlace> = {
+        let query = format!(
+            "SELECT MIN(em.merchant), SUM(e.amount), COUNT(*)
+             FROM entry_merchants em
+             JOIN expenses e ON e.expense_id = em.expense_id
+             {}
+               AND e.entry_type = 'expense' AND c.category_type IS NOT 'savings'
+             GROUP BY COALESCE(CAST(em.merchant_id AS TEXT), 'place:' || em.place_key)
+             ORDER BY 2 DESC
+             LIMIT ?3",
+            PERIOD_ENTRIES_SQL
+        );
+        let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(rusqlite::params![from, to, top_count], |row| {
+            Ok(ReportPlace {
+                place: row.get(0)?,
+                spent: row.get(1)?,
+                visits: row.get(2)?,
+            })
+        }).map_err(|e| e.to_string())?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    let largest_transactions: Vec<ReportTransaction> = {
+        let query = format!(
+            "SELECT e.expense_id, b.budget_id, c.category_name, e.date, e.description, e.place, e.amount
+             FROM expenses e
+             {}
+               AND e.entry_type = 'expense' AND c.category_type IS NOT 'savings'
+             ORDER BY e.amount DESC, e.date ASC
+             LIMIT ?3",
+            PERIOD_ENTRIES_SQL
+        );
+        let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(rusqlite::params![from, to, top_count], |row| {
+            Ok(ReportTransaction {
+                entry_id: row.get(0)?,
+                budget_id: row.get(1)?,
+                category_name: row.get(2)?,
+                date: row.get(3)?,
+                what: row.get(4)?,
+                r#where: row.get(5)?,
+                amount: row.get(6)?,
+            })
+        }).map_err(|e| e.to_string())?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| e.to_string())?);
+        }
+        results
+    };
+
+    Ok(PeriodReport {
+        label,
+        from_month: format_month(from),
+        to_month: format_month(to),
+        budgets_count: months.len(),
+        budgeted_income,
+        other_income: total_income - budgeted_income,
+        total_income,
+        total_expenses,
+        net_savings,
+        savings_rate,
+        months,
+        top_categories,
+        top_places,
+        largest_transactions,
+    })
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(fields: &[String]) -> String {
+    let mut row = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+    row.push_str("\r\n");
+    row
+}
+
+// One CSV file with a section per part of the report, separated by blank lines
+fn report_to_csv(report: &PeriodReport) -> String {
+    let money = |amount: f64| format!("{:.2}", amount);
+    let mut out = String::new();
+
+    out.push_str(&csv_row(&["Report".into(), report.label.clone()]));
+    out.push_str(&csv_row(&["From".into(), report.from_month.clone()]));
+    out.push_str(&csv_row(&["To".into(), report.to_month.clone()]));
+    out.push_str(&csv_row(&["Budgets".into(), report.budgets_count.to_string()]));
+    out.push_str(&csv_row(&["Budgeted income".into(), money(report.budgeted_income)]));
+    out.push_str(&csv_row(&["Other income".into(), money(report.other_income)]));
+    out.push_str(&csv_row(&["Total income".into(), money(report.total_income)]));
+    out.push_str(&csv_row(&["Total expenses".into(), money(report.total_expenses)]));
+    out.push_str(&csv_row(&["Net savings".into(), money(report.net_savings)]));
+    out.push_str(&csv_row(&[
+        "Savings rate %".into(),
+        report.savings_rate.map(|r| format!("{:.1}", r)).unwrap_or_default(),
+    ]));
+
+    out.push_str("\r\n");
+    out.push_str(&csv_row(&["Month".into(), "Income".into(), "Expenses".into(), "Savings".into()]));
+    for month in &report.months {
+        out.push_str(&csv_row(&[
+            format!("{:04}-{:02}", month.year, month.month),
+            money(month.income),
+            money(month.expenses),
+            money(month.savings),
+        ]));
+    }
+
+    out.push_str("\r\n");
+    out.push_str(&csv_row(&["Category".into(), "Spent".into(), "Share %".into(), "Entries".into()]));
+    for category in &report.top_categories {
+        out.push_str(&csv_row(&[
+            category.name.clone(),
+            money(category.spent),
+            format!("{:.1}", category.share_of_expenses),
+            category.entries_count.to_string(),
+        ]));
+    }
+
+    out.push_str("\r\n");
+    out.push_str(&csv_row(&["Place".into(), "Spent".into(), "Visits".into()]));
+    for place in &report.top_places {
+        out.push_str(&csv_row(&[place.place.clone(), money(place.spent), place.visits.to_string()]));
+    }
+
+    out.push_str("\r\n");
+    out.push_str(&csv_row(&["Date".into(), "What".into(), "Where".into(), "Category".into(), "Amount".into()]));
+    for transaction in &report.largest_transactions {
+        out.push_str(&csv_row(&[
+            transaction.date.clone(),
+            transaction.what.clone(),
+            transaction.r#where.clone().unwrap_or_default(),
+            transaction.category_name.clone(),
+            money(transaction.amount),
+        ]));
+    }
+
+    out
+}
+
+fn clip(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut clipped: String = text.chars().take(width.saturating_sub(1)).collect();
+        clipped.push('~');
+        clipped
+    }
+}
+
+fn report_to_pdf(report: &PeriodReport) -> Vec<u8> {
+    let mut lines = vec![
+        PdfLine::Heading(format!("Budget report {}", report.label)),
+        PdfLine::Text(format!("{} to {}, {} budgets", report.from_month, report.to_month, report.budgets_count)),
+        PdfLine::Blank,
+        PdfLine::Text(format!("{:<20}{:>14.2}", "Budgeted income", report.budgeted_income)),
+        PdfLine::Text(format!("{:<20}{:>14.2}", "Other income", report.other_income)),
+        PdfLine::Text(format!("{:<20}{:>14.2}", "Total income", report.total_income)),
+        PdfLine::Text(format!("{:<20}{:>14.2}", "Total expenses", report.total_expenses)),
+        PdfLine::Text(format!("{:<20}{:>14.2}", "Net savings", report.net_savings)),
+        PdfLine::Text(format!(
+            "{:<20}{:>14}",
+            "Savings rate",
+            report.savings_rate.map(|r| format!("{:.1}%", r)).unwrap_or_else(|| "-".to_string())
+        )),
+        PdfLine::Blank,
+        PdfLine::Heading("Months".to_string()),
+        PdfLine::Text(format!("{:<10}{:>14}{:>14}{:>14}", "Month", "Income", "Expenses", "Savings")),
+    ];
+    for month in &report.months {
+        lines.push(PdfLine::Text(format!(
+            "{:<10}{:>14.2}{:>14.2}{:>14.2}",
+            format!("{:04}-{:02}", month.year, month.month), month.income, month.expenses, month.savings
+        )));
+    }
+
+    lines.push(PdfLine::Blank);
+    lines.push(PdfLine::Heading("Top categories".to_string()));
+    lines.push(PdfLine::Text(format!("{:<32}{:>14}{:>9}{:>9}", "Category", "Spent", "Share", "Entries")));
+    for category in &report.top_categories {
+        lines.push(PdfLine::Text(format!(
+            "{:<32}{:>14.2}{:>8.1}%{:>9}",
+            clip(&category.name, 31), category.spent, category.share_of_expenses, category.entries_count
+        )));
+    }
+
+    lines.push(PdfLine::Blank);
+    lines.push(PdfLine::Heading("Top places".to_string()));
+    lines.push(PdfLine::Text(format!("{:<32}{:>14}{:>9}", "Place", "Spent", "Visits")));
+    for place in &report.top_places {
+        lines.push(PdfLine::Text(format!("{:<32}{:>14.2}{:>9}", clip(&place.place, 31), place.spent, place.visits)));
+    }
+
+    lines.push(PdfLine::Blank);
+    lines.push(PdfLine::Heading("Largest transactions".to_string()));
+    lines.push(PdfLine::Text(format!("{:<11}{:<28}{:<20}{:>14}", "Date", "What", "Category", "Amount")));
+    for transaction in &report.largest_transactions {
+        lines.push(PdfLine::Text(format!(
+            "{:<11}{:<28}{:<20}{:>14.2}",
+            transaction.date, clip(&transaction.what, 27), clip(&transaction.category_name, 19), transaction.amount
+        )));
+    }
+
+    pdf::render_lines(&lines)
+}
+
+#[tauri::command]
+pub fn get_period_report(args: PeriodReportArgs, db: State<DbState>) -> Result<PeriodReport, String> {
+    println!("=== GET_PERIOD_REPORT COMMAND CALLED ===");
+    println!("Report: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let report = build_report(&conn, &args)?;
+
+    println!("Report {} covers {} budgets", report.label, report.budgets_count);
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn export_period_report(args: PeriodReportArgs, format: String, db: State<DbState>) -> Result<ReportExport, String> {
+    println!("=== EXPORT_PERIOD_REPORT COMMAND CALLED ===");
+    println!("Exporting report as {}: {:?}", format, args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let report = build_report(&conn, &args)?;
+
+    let base_name = format!("budget-report-{}", report.label.replace(['/', ' '], "-").to_lowercase());
+    let export = match format.as_str() {
+        "csv" => ReportExport {
+            file_name: format!("{}.csv", base_name),
+            mime_type: "text/csv".to_string(),
+            data: report_to_csv(&report).into_bytes(),
+        },
+        "pdf" => ReportExport {
+            file_name: format!("{}.pdf", base_name),
+            mime_type: "application/pdf".to_string(),
+            data: report_to_pdf(&report),
+        },
+        other => return Err(format!("Unsupported export format '{}', expected csv or pdf", other)),
+    };
+
+    println!("Exported {} ({} bytes)", export.file_name, export.data.len());
+    Ok(export)
+}
@@ -0,0 +1,315 @@
+use rusqlite::types::Value as SqlValue;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use time::macros::format_description;
+
+use crate::modules::commands::history::escape_like;
+use crate::modules::commands::tag::{push_tag_filters, split_tags, ENTRY_TAGS_SQL};
+use crate::modules::database::{rebuild_search_index as rebuild_index, DbState};
+
+const DEFAULT_SEARCH_PAGE_SIZE: u32 = 50;
+const MAX_SEARCH_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchEntriesArgs {
+    pub text: Option<String>,              // every word must appear in what, where or notes
+    pub query: Option<String>,             // full-text: words, "exact phrases" and prefix* terms
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    pub from: Option<String>,              // "YYYY-MM-DD", inclusive
+    pub to: Option<String>,                // "YYYY-MM-DD", inclusive
+    pub entry_types: Option<Vec<String>>,  // "expense" | "income" | "adjustment"
+    pub global_category_id: Option<i64>,
+    pub tags: Option<Vec<String>>,         // entries must carry every one of them
+    pub budget_id: Option<i64>,            // None searches every budget
+    pub finished: Option<bool>,            // None matches finished and open budgets
+    pub deleted: Option<String>,           // "exclude" (default) | "only" | "include"
+    pub sort: Option<String>,              // same keys as get_category_ledger, plus "relevance"
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchEntryResult {
+    pub entry_id: i64,
+    pub budget_id: i64,
+    pub budget_name: Option<String>,
+    pub month: u8,
+    pub year: i32,
+    pub category_id: i64,
+    pub category_name: String,
+    pub global_category_id: Option<i64>,
+    pub entry_type: String,
+    pub what: String,
+    pub r#where: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+    pub amount: f64,
+    pub date: String,
+    pub is_recurring: bool,
+    pub created_at: String,
+    pub deleted_at: Option<String>, // the entry's own, or its category's or budget's
+    pub rank: Option<f64>,          // bm25 score for full-text queries, lower is more relevant
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchTotals {
+    pub count: i64,
+    pub total_expenses: f64,
+    pub total_income: f64,
+    pub total_adjustments: f64,
+    pub net_amount: f64, // income minus expenses, as in the category stats
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchEntriesPage {
+    pub entries: Vec<SearchEntryResult>,
+    pub totals: SearchTotals,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+// Turns user input into a safe FTS5 expression. Quoted text becomes a phrase, a trailing
+// '*' a prefix search, and everything else is a plain term; all of them must match
+fn to_fts_query(input: &str) -> Option<String> {
+    let quote = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+    let mut parts = Vec::new();
+
+    for (i, chunk) in input.split('"').enumerate() {
+        if i % 2 == 1 {
+            if !chunk.trim().is_empty() {
+                parts.push(quote(chunk.trim()));
+            }
+            continue;
+        }
+        for word in chunk.split_whitespace() {
+            let term = word.trim_end_matches('*');
+            if term.is_empty() {
+                continue;
+            }
+            if word.ends_with('*') {
+                parts.push(format!("{}*", quote(term)));
+            } else {
+                parts.push(quote(term));
+            }
+        }
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" "))
+    }
+}
+
+pub(crate) fn check_date(value: &str) -> Result<(), String> {
+    time::Date::parse(value, format_description!("[year]-[month]-[day]"))
+        .map(|_| ())
+        .map_err(|_| format!("Invalid date: {}", value))
+}
+
+#[tauri::command]
+pub fn search_entries(args: SearchEntriesArgs, db: State<DbState>) -> Result<SearchEntriesPage, String> {
+    println!("=== SEARCH_ENTRIES COMMAND CALLED ===");
+    println!("Entry search: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut conditions: Vec<String> = Vec::new();
+    let mut params: Vec<SqlValue> = Vec::new();
+
+    if let Some(text) = &args.text {
+        for term in text.split_whitespace() {
+            params.push(SqlValue::Text(format!("%{}%", escape_like(term))));
+            let n = params.len();
+            conditions.push(format!(
+                "(e.description LIKE ?{n} ESCAPE '\\' OR e.place LIKE ?{n} ESCAPE '\\' OR e.notes LIKE ?{n} ESCAPE '\\')"
+            ));
+        }
+    }
+
+    if let (Some(min), Some(max)) = (args.min_amount, args.max_amount) {
+        if min > max {
+            return Err(format!("Minimum amount {} is greater than maximum amount {}", min, max));
+        }
+    }
+
+    if let Some(min) = args.min_amount {
+        params.push(SqlValue::Real(min));
+        conditions.push(format!("e.amount >= ?{}", params.len()));
+    }
+
+    if let Some(max) = args.max_amount {
+        params.push(SqlValue::Real(max));
+        conditions.push(format!("e.amount <= ?{}", params.len()));
+    }
+
+    if let Some(from) = &args.from {
+        check_date(from)?;
+        params.push(SqlValue::Text(from.clone()));
+        conditions.push(format!("e.date >= ?{}", params.len()));
+    }
+
+    if let Some(to) = &args.to {
+        check_date(to)?;
+        params.push(SqlValue::Text(to.clone()));
+        conditions.push(format!("e.date <= ?{}", params.len()));
+    }
+
+    if let Some(types) = args.entry_types.as_ref().filter(|t| !t.is_empty()) {
+        let mut placeholders = Vec::new();
+        for entry_type in types {
+            params.push(SqlValue::Text(entry_type.clone()));
+            placeholders.push(format!("?{}", params.len()));
+        }
+        conditions.push(format!("e.entry_type IN ({})", placeholders.join(", ")));
+    }
+
+    if let Some(global_category_id) = args.global_category_id {
+        params.push(SqlValue::Integer(global_category_id));
+        conditions.push(format!("c.global_category_id = ?{}", params.len()));
+    }
+
+    if let Some(tags) = &args.tags {
+        push_tag_filters(tags, &mut conditions, &mut params)?;
+    }
+
+    if let Some(budget_id) = args.budget_id {
+        params.push(SqlValue::Integer(budget_id));
+        conditions.push(format!("c.budget_id = ?{}", params.len()));
+    }
+
+    match args.finished {
+        Some(true) => conditions.push("b.finished_at IS NOT NULL".to_string()),
+        Some(false) => conditions.push("b.finished_at IS NULL".to_string()),
+        None => {}
+    }
+
+    // An entry in a deleted category or budget is as good as deleted itself
+    match args.deleted.as_deref().unwrap_or("exclude") {
+        "exclude" => conditions.push("COALESCE(e.deleted_at, c.deleted_at, b.deleted_at) IS NULL".to_string()),
+        "only" => conditions.push("COALESCE(e.deleted_at, c.deleted_at, b.deleted_at) IS NOT NULL".to_string()),
+        "include" => {}
+        other => return Err(format!("Unknown deleted filter: {}", other)),
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    // Matches in the description weigh more than the place, and the place more than notes
+    let fts_query = args.query.as_deref().and_then(to_fts_query);
+    let mut from_clause = "FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id".to_string();
+    if let Some(fts_query) = &fts_query {
+        params.push(SqlValue::Text(fts_query.clone()));
+        from_clause.push_str(&format!(
+            "
+         JOIN (SELECT rowid AS expense_id, bm25(expenses_fts, 10.0, 5.0, 1.0) AS rank
+               FROM expenses_fts WHERE expenses_fts MATCH ?{}) m ON m.expense_id = e.expense_id",
+            params.len()
+        ));
+    }
+
+    let totals = conn.query_row(
+        &format!(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(CASE WHEN e.entry_type = 'expense' THEN e.amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN e.entry_type = 'income' THEN e.amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN e.entry_type = 'adjustment' THEN e.amount ELSE 0 END), 0)
+             {} {}",
+            from_clause, where_clause
+        ),
+        rusqlite::params_from_iter(params.iter()),
+        |row| {
+            let total_expenses: f64 = row.get(1)?;
+            let total_income: f64 = row.get(2)?;
+            Ok(SearchTotals {
+                count: row.get(0)?,
+                total_expenses,
+                total_income,
+                total_adjustments: row.get(3)?,
+                net_amount: total_income - total_expenses,
+            })
+        }
+    ).map_err(|e| e.to_string())?;
+
+    let default_sort = if fts_query.is_some() { "relevance" } else { "date_desc" };
+    let order_clause = match args.sort.as_deref().unwrap_or(default_sort) {
+        "relevance" if fts_query.is_some() => "ORDER BY m.rank ASC, e.date DESC, e.expense_id DESC",
+        "date_asc" => "ORDER BY e.date ASC, e.created_at ASC, e.expense_id ASC",
+        "amount_asc" => "ORDER BY e.amount ASC, e.expense_id ASC",
+        "amount_desc" => "ORDER BY e.amount DESC, e.expense_id DESC",
+        "created_asc" => "ORDER BY e.created_at ASC, e.expense_id ASC",
+        "created_desc" => "ORDER BY e.created_at DESC, e.expense_id DESC",
+        _ => "ORDER BY e.date DESC, e.created_at DESC, e.expense_id DESC", // default
+    };
+
+    let limit = args.limit.unwrap_or(DEFAULT_SEARCH_PAGE_SIZE).clamp(1, MAX_SEARCH_PAGE_SIZE);
+    let offset = args.offset.unwrap_or(0);
+
+    params.push(SqlValue::Integer(limit as i64));
+    params.push(SqlValue::Integer(offset as i64));
+    let query = format!(
+        "SELECT e.expense_id, c.budget_id, b.name, b.month, b.year, c.category_id, c.category_name, c.global_category_id,
+                e.entry_type, e.description, e.place, e.notes, e.amount, e.date, e.is_recurring, e.created_at,
+                COALESCE(e.deleted_at, c.deleted_at, b.deleted_at), {}, {}
+         {} {}
+         {}
+         LIMIT ?{} OFFSET ?{}",
+        if fts_query.is_some() { "m.rank" } else { "NULL" },
+        ENTRY_TAGS_SQL,
+        from_clause, where_clause, order_clause, params.len() - 1, params.len()
+    );
+
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+        Ok(SearchEntryResult {
+            entry_id: row.get(0)?,
+            budget_id: row.get(1)?,
+            budget_name: row.get(2)?,
+            month: row.get(3)?,
+            year: row.get(4)?,
+            category_id: row.get(5)?,
+            category_name: row.get(6)?,
+            global_category_id: row.get(7)?,
+            entry_type: row.get(8)?,
+            what: row.get(9)?,
+            r#where: row.get(10)?,
+            notes: row.get(11)?,
+            amount: row.get(12)?,
+            date: row.get(13)?,
+            is_recurring: row.get(14)?,
+            created_at: row.get(15)?,
+            deleted_at: row.get(16)?,
+            rank: row.get(17)?,
+            tags: split_tags(row.get(18)?),
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row.map_err(|e| e.to_string())?);
+    }
+
+    println!("Entry search matched {} rows, returning {}", totals.count, entries.len());
+    Ok(SearchEntriesPage { entries, totals, limit, offset })
+}
+
+#[tauri::command]
+pub fn rebuild_search_index(db: State<DbState>) -> Result<i64, String> {
+    println!("=== REBUILD_SEARCH_INDEX COMMAND CALLED ===");
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let indexed = rebuild_index(&conn).map_err(|e| e.to_string())?;
+
+    println!("Rebuilt full-text index over {} entries", indexed);
+    Ok(indexed)
+}
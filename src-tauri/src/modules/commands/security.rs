@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
-use crate::modules::security::auth;
+use crate::modules::security::{audit, auth};
 use crate::modules::database::DbState;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,4 +17,10 @@ pub fn verify_master_password(args: VerifyPasswordArgs, db: State<DbState>) -> R
     auth::verify_password(&conn, &args.username, &args.master_password).map_err(|e| e.to_string())
 }
 
-
+#[tauri::command]
+pub fn verify_history_chain(budget_id: i64, db: State<DbState>) -> Result<audit::ChainReport, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    let report = audit::verify_chain(&conn, budget_id).map_err(|e| e.to_string())?;
+    println!("Verified {} history rows for budget {}: {} breaks", report.rows_checked, budget_id, report.breaks.len());
+    Ok(report)
+}
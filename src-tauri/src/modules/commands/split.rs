@@ -0,0 +1,238 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::modules::commands::alert::raise_budget_alerts;
+use crate::modules::commands::budget::log_budget_change;
+use crate::modules::commands::history::{attach_row_snapshots, row_snapshot};
+use crate::modules::commands::reconcile::check_not_reconciled;
+use crate::modules::commands::transfer::is_transfer_type;
+use crate::modules::database::DbState;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitPartArgs {
+    pub category_id: i64,
+    pub amount: f64,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitEntryArgs {
+    pub entry_id: i64,
+    pub parts: Vec<SplitPartArgs>, // replaces any existing parts; must sum to the entry's amount
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntrySplitPart {
+    pub split_id: i64,
+    pub entry_id: i64,
+    pub category_id: i64,
+    pub category_name: String,
+    pub amount: f64,
+    pub note: Option<String>,
+}
+
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+fn load_parts(conn: &Connection, entry_id: i64) -> Result<Vec<EntrySplitPart>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT s.split_id, s.expense_id, s.category_id, c.category_name, s.amount, s.note
+         FROM entry_splits s
+         JOIN budget_categories c ON c.category_id = s.category_id
+         WHERE s.expense_id = ?1
+         ORDER BY s.split_id ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([entry_id], |row| {
+        Ok(EntrySplitPart {
+            split_id: row.get(0)?,
+            entry_id: row.get(1)?,
+            category_id: row.get(2)?,
+            category_name: row.get(3)?,
+            amount: row.get(4)?,
+            note: row.get(5)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut parts = Vec::new();
+    for row in rows {
+        parts.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(parts)
+}
+
+/// Scales an entry's parts so they sum to its amount again, after the amount was changed
+/// by an edit or an undo. The rounding difference goes to the largest part.
+pub(crate) fn rebalance_entry_splits(conn: &Connection, entry_id: i64) -> Result<(), String> {
+    let parts = load_parts(conn, entry_id)?;
+    if parts.is_empty() {
+        return Ok(());
+    }
+
+    let amount: f64 = conn.query_row(
+        "SELECT amount FROM expenses WHERE expense_id = ?1",
+        [entry_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    let parts_total: f64 = parts.iter().map(|p| p.amount).sum();
+    if (parts_total - amount).abs() < 0.005 {
+        return Ok(());
+    }
+
+    let mut scaled: Vec<(i64, f64)> = parts
+        .iter()
+        .map(|p| {
+            let share = if parts_total.abs() < 0.005 { 1.0 / parts.len() as f64 } else { p.amount / parts_total };
+            (p.split_id, round_cents(amount * share))
+        })
+        .collect();
+
+    let remainder = round_cents(amount - scaled.iter().map(|(_, a)| a).sum::<f64>());
+    if let Some(largest) = scaled.iter_mut().max_by(|a, b| a.1.total_cmp(&b.1)) {
+        largest.1 = round_cents(largest.1 + remainder);
+    }
+
+    for (split_id, part_amount) in scaled {
+        conn.execute(
+            "UPDATE entry_splits SET amount = ?1 WHERE split_id = ?2",
+            rusqlite::params![part_amount, split_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    println!("Rebalanced {} parts of entry {} to ${:.2}", parts.len(), entry_id, amount);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn split_category_entry(args: SplitEntryArgs, app: AppHandle, db: State<DbState>) -> Result<Vec<EntrySplitPart>, String> {
+    println!("=== SPLIT_CATEGORY_ENTRY COMMAND CALLED ===");
+    println!("Splitting entry: {:?}", args);
+
+    if args.parts.len() < 2 {
+        return Err("A split needs at least two parts; use unsplit_category_entry to remove one".to_string());
+    }
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (budget_id, what, amount, entry_type): (i64, String, f64, String) = tx.query_row(
+        "SELECT c.budget_id, e.description, e.amount, e.entry_type FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE e.expense_id = ?1 AND e.deleted_at IS NULL",
+        [args.entry_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+    ).map_err(|e| format!("Entry not found or deleted: {}", e))?;
+
+    if is_transfer_type(&entry_type) {
+        return Err("Transfers cannot be split".to_string());
+    }
+    check_not_reconciled(&tx, args.entry_id)?;
+
+    let mut seen = Vec::new();
+    for part in &args.parts {
+        if part.amount <= 0.0 {
+            return Err(format!("Part amounts must be positive, got {:.2}", part.amount));
+        }
+        if seen.contains(&part.category_id) {
+            return Err(format!("Category {} appears in more than one part", part.category_id));
+        }
+        seen.push(part.category_id);
+
+        let part_budget: i64 = tx.query_row(
+            "SELECT budget_id FROM budget_categories WHERE category_id = ?1 AND deleted_at IS NULL",
+            [part.category_id],
+            |row| row.get(0)
+        ).map_err(|_| format!("Category {} not found", part.category_id))?;
+        if part_budget != budget_id {
+            return Err(format!("Category {} belongs to a different budget", part.category_id));
+        }
+    }
+
+    let parts_total: f64 = args.parts.iter().map(|p| p.amount).sum();
+    if (parts_total - amount).abs() >= 0.005 {
+        return Err(format!("Parts add up to ${:.2} but the entry is ${:.2}", parts_total, amount));
+    }
+
+    let before: Vec<String> = load_parts(&tx, args.entry_id)?
+        .iter()
+        .map(|p| format!("{} ${:.2}", p.category_name, p.amount))
+        .collect();
+    let before_snapshot = row_snapshot(&tx, "entry_splits", args.entry_id)?;
+
+    tx.execute("DELETE FROM entry_splits WHERE expense_id = ?1", [args.entry_id])
+        .map_err(|e| e.to_string())?;
+    for part in &args.parts {
+        tx.execute(
+            "INSERT INTO entry_splits (expense_id, category_id, amount, note) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![args.entry_id, part.category_id, round_cents(part.amount), part.note],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    let parts = load_parts(&tx, args.entry_id)?;
+    let after: Vec<String> = parts.iter().map(|p| format!("{} ${:.2}", p.category_name, p.amount)).collect();
+
+    let old_value = if before.is_empty() { None } else { Some(before.join(", ")) };
+    let description = format!("Split entry {} ({}) into {}", args.entry_id, what, after.join(", "));
+    let change_id = log_budget_change(&tx, budget_id, "entry_split", Some("entry_splits"),
+                                      old_value.as_deref(), Some(&after.join(", ")), &description)?;
+    attach_row_snapshots(&tx, change_id, "entry_splits", args.entry_id, before_snapshot)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    raise_budget_alerts(&app, &conn, budget_id);
+
+    println!("Successfully split entry {} into {} parts", args.entry_id, parts.len());
+    Ok(parts)
+}
+
+#[tauri::command]
+pub fn unsplit_category_entry(entry_id: i64, app: AppHandle, db: State<DbState>) -> Result<(), String> {
+    println!("=== UNSPLIT_CATEGORY_ENTRY COMMAND CALLED ===");
+    println!("Removing split from entry ID: {}", entry_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (budget_id, what, category_name): (i64, String, String) = tx.query_row(
+        "SELECT c.budget_id, e.description, c.category_name FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE e.expense_id = ?1 AND e.deleted_at IS NULL",
+        [entry_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).map_err(|e| format!("Entry not found or deleted: {}", e))?;
+    check_not_reconciled(&tx, entry_id)?;
+
+    let before: Vec<String> = load_parts(&tx, entry_id)?
+        .iter()
+        .map(|p| format!("{} ${:.2}", p.category_name, p.amount))
+        .collect();
+    if before.is_empty() {
+        return Err(format!("Entry {} is not split", entry_id));
+    }
+    let before_snapshot = row_snapshot(&tx, "entry_splits", entry_id)?;
+
+    tx.execute("DELETE FROM entry_splits WHERE expense_id = ?1", [entry_id])
+        .map_err(|e| e.to_string())?;
+
+    let description = format!("Removed split of entry {} ({}); it counts in full towards {} again", entry_id, what, category_name);
+    let change_id = log_budget_change(&tx, budget_id, "entry_unsplit", Some("entry_splits"), Some(&before.join(", ")), None, &description)?;
+    attach_row_snapshots(&tx, change_id, "entry_splits", entry_id, before_snapshot)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    raise_budget_alerts(&app, &conn, budget_id);
+
+    println!("Successfully removed split from entry {}", entry_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_entry_splits(entry_id: i64, db: State<DbState>) -> Result<Vec<EntrySplitPart>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+    load_parts(&conn, entry_id)
+}
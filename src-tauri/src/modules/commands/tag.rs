@@ -0,0 +1,286 @@
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::modules::commands::budget::log_budget_change;
+use crate::modules::commands::history::{attach_row_snapshots, escape_like, row_snapshot};
+use crate::modules::commands::search::check_date;
+use crate::modules::database::DbState;
+
+const MAX_TAG_LENGTH: usize = 50;
+const DEFAULT_SUGGESTION_LIMIT: u32 = 10;
+
+/// Comma-separated tag names of the entry aliased `e`; pass the result through `split_tags`.
+pub(crate) const ENTRY_TAGS_SQL: &str = "(SELECT GROUP_CONCAT(t.name, ',') FROM entry_tags et
+      JOIN tags t ON t.tag_id = et.tag_id WHERE et.expense_id = e.expense_id)";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagSuggestion {
+    pub tag_id: i64,
+    pub name: String,
+    pub usage_count: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TagReportArgs {
+    pub budget_id: Option<i64>,    // None reports across every budget
+    pub from: Option<String>,      // "YYYY-MM-DD", inclusive
+    pub to: Option<String>,        // "YYYY-MM-DD", inclusive
+    pub tags: Option<Vec<String>>, // None reports every tag
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagTotal {
+    pub tag_id: i64,
+    pub name: String,
+    pub entries_count: i64,
+    pub budgets_count: i64,
+    pub total_expenses: f64,
+    pub total_income: f64,
+    pub net_amount: f64,
+}
+
+// "  Vacation 2026 " -> "vacation-2026"
+pub(crate) fn normalize_tag(tag: &str) -> Result<String, String> {
+    let name = tag.split_whitespace().collect::<Vec<_>>().join("-").to_lowercase();
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
+    if name.contains(',') {
+        return Err(format!("Tag '{}' cannot contain a comma", name));
+    }
+    if name.chars().count() > MAX_TAG_LENGTH {
+        return Err(format!("Tag '{}' is longer than {} characters", name, MAX_TAG_LENGTH));
+    }
+    Ok(name)
+}
+
+pub(crate) fn split_tags(tags: Option<String>) -> Vec<String> {
+    let mut names: Vec<String> = tags
+        .unwrap_or_default()
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Adds a condition per tag so that only entries carrying every one of them match.
+pub(crate) fn push_tag_filters(tags: &[String], conditions: &mut Vec<String>, params: &mut Vec<SqlValue>) -> Result<(), String> {
+    for tag in tags {
+        params.push(SqlValue::Text(normalize_tag(tag)?));
+        conditions.push(format!(
+            "EXISTS (SELECT 1 FROM entry_tags et JOIN tags t ON t.tag_id = et.tag_id
+                     WHERE et.expense_id = e.expense_id AND t.name = ?{})",
+            params.len()
+        ));
+    }
+    Ok(())
+}
+
+fn entry_tags(conn: &Connection, entry_id: i64) -> Result<Vec<String>, String> {
+    let tags: Option<String> = conn.query_row(
+        &format!("SELECT {} FROM expenses e WHERE e.expense_id = ?1", ENTRY_TAGS_SQL),
+        [entry_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    Ok(split_tags(tags))
+}
+
+// Budget and description of a live entry, for the change history
+fn entry_info(conn: &Connection, entry_id: i64) -> Result<(i64, String), String> {
+    conn.query_row(
+        "SELECT c.budget_id, e.description FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE e.expense_id = ?1 AND e.deleted_at IS NULL",
+        [entry_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| format!("Entry not found or deleted: {}", e))
+}
+
+#[tauri::command]
+pub fn add_entry_tags(entry_id: i64, tags: Vec<String>, db: State<DbState>) -> Result<Vec<String>, String> {
+    println!("=== ADD_ENTRY_TAGS COMMAND CALLED ===");
+    println!("Tagging entry ID: {} with {:?}", entry_id, tags);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (budget_id, what) = entry_info(&tx, entry_id)?;
+    let before = entry_tags(&tx, entry_id)?;
+    let before_snapshot = row_snapshot(&tx, "entry_tags", entry_id)?;
+
+    for tag in &tags {
+        let name = normalize_tag(tag)?;
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [&name])
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT OR IGNORE INTO entry_tags (expense_id, tag_id) SELECT ?1, tag_id FROM tags WHERE name = ?2",
+            rusqlite::params![entry_id, name],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    let after = entry_tags(&tx, entry_id)?;
+    if after != before {
+        let change_id = log_budget_change(&tx, budget_id, "entry_tag", Some("tags"), Some(&before.join(", ")), Some(&after.join(", ")),
+                                          &format!("Tagged entry {} ({}) with {}", entry_id, what, after.join(", ")))?;
+        attach_row_snapshots(&tx, change_id, "entry_tags", entry_id, before_snapshot)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Entry {} now has tags {:?}", entry_id, after);
+    Ok(after)
+}
+
+#[tauri::command]
+pub fn remove_entry_tags(entry_id: i64, tags: Vec<String>, db: State<DbState>) -> Result<Vec<String>, String> {
+    println!("=== REMOVE_ENTRY_TAGS COMMAND CALLED ===");
+    println!("Removing tags {:?} from entry ID: {}", tags, entry_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (budget_id, what) = entry_info(&tx, entry_id)?;
+    let before = entry_tags(&tx, entry_id)?;
+    let before_snapshot = row_snapshot(&tx, "entry_tags", entry_id)?;
+
+    for tag in &tags {
+        let name = normalize_tag(tag)?;
+        tx.execute(
+            "DELETE FROM entry_tags WHERE expense_id = ?1 AND tag_id = (SELECT tag_id FROM tags WHERE name = ?2)",
+            rusqlite::params![entry_id, name],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    let after = entry_tags(&tx, entry_id)?;
+    if after != before {
+        let removed: Vec<String> = before.iter().filter(|t| !after.contains(t)).cloned().collect();
+        let change_id = log_budget_change(&tx, budget_id, "entry_untag", Some("tags"), Some(&before.join(", ")), Some(&after.join(", ")),
+                                          &format!("Removed {} from entry {} ({})", removed.join(", "), entry_id, what))?;
+        attach_row_snapshots(&tx, change_id, "entry_tags", entry_id, before_snapshot)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Entry {} now has tags {:?}", entry_id, after);
+    Ok(after)
+}
+
+#[tauri::command]
+pub fn search_tags(prefix: Option<String>, limit: Option<u32>, db: State<DbState>) -> Result<Vec<TagSuggestion>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let pattern = format!("{}%", escape_like(&prefix.unwrap_or_default().trim().to_lowercase()));
+    let limit = limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT);
+
+    // Tags whose entries were all purged no longer show up as suggestions
+    let mut stmt = conn.prepare(
+        "SELECT t.tag_id, t.name, COUNT(et.expense_id) AS usage_count
+         FROM tags t
+         JOIN entry_tags et ON et.tag_id = t.tag_id
+         WHERE t.name LIKE ?1 ESCAPE '\\'
+         GROUP BY t.tag_id, t.name
+         ORDER BY usage_count DESC, t.name ASC
+         LIMIT ?2"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(rusqlite::params![pattern, limit], |row| {
+        Ok(TagSuggestion {
+            tag_id: row.get(0)?,
+            name: row.get(1)?,
+            usage_count: row.get(2)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn get_tag_report(args: TagReportArgs, db: State<DbState>) -> Result<Vec<TagTotal>, String> {
+    println!("=== GET_TAG_REPORT COMMAND CALLED ===");
+    println!("Tag report: {:?}", args);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut conditions: Vec<String> = vec![
+        "e.deleted_at IS NULL".to_string(),
+        "c.deleted_at IS NULL".to_string(),
+        "b.deleted_at IS NULL".to_string(),
+    ];
+    let mut params: Vec<SqlValue> = Vec::new();
+
+    if let Some(budget_id) = args.budget_id {
+        params.push(SqlValue::Integer(budget_id));
+        conditions.push(format!("c.budget_id = ?{}", params.len()));
+    }
+
+    if let Some(from) = &args.from {
+        check_date(from)?;
+        params.push(SqlValue::Text(from.clone()));
+        conditions.push(format!("e.date >= ?{}", params.len()));
+    }
+
+    if let Some(to) = &args.to {
+        check_date(to)?;
+        params.push(SqlValue::Text(to.clone()));
+        conditions.push(format!("e.date <= ?{}", params.len()));
+    }
+
+    if let Some(tags) = args.tags.as_ref().filter(|t| !t.is_empty()) {
+        let mut placeholders = Vec::new();
+        for tag in tags {
+            params.push(SqlValue::Text(normalize_tag(tag)?));
+            placeholders.push(format!("?{}", params.len()));
+        }
+        conditions.push(format!("t.name IN ({})", placeholders.join(", ")));
+    }
+
+    // An entry with several tags counts towards each of them
+    let query = format!(
+        "SELECT t.tag_id, t.name, COUNT(*), COUNT(DISTINCT c.budget_id),
+                COALESCE(SUM(CASE WHEN e.entry_type = 'expense' THEN e.amount ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN e.entry_type = 'income' THEN e.amount ELSE 0 END), 0)
+         FROM tags t
+         JOIN entry_tags et ON et.tag_id = t.tag_id
+         JOIN expenses e ON e.expense_id = et.expense_id
+         JOIN budget_categories c ON c.category_id = e.category_id
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+         WHERE {}
+         GROUP BY t.tag_id, t.name
+         ORDER BY 5 DESC, t.name ASC",
+        conditions.join(" AND ")
+    );
+
+    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+        let total_expenses: f64 = row.get(4)?;
+        let total_income: f64 = row.get(5)?;
+        Ok(TagTotal {
+            tag_id: row.get(0)?,
+            name: row.get(1)?,
+            entries_count: row.get(2)?,
+            budgets_count: row.get(3)?,
+            total_expenses,
+            total_income,
+            net_amount: total_income - total_expenses,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+
+    println!("Tag report covers {} tags", results.len());
+    Ok(results)
+}
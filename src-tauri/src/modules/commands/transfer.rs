@@ -0,0 +1,129 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::modules::commands::alert::raise_budget_alerts;
+use crate::modules::commands::budget::log_budget_change;
+use crate::modules::commands::history::attach_row_snapshots;
+use crate::modules::database::DbState;
+
+/// Entry types of the two sides of a transfer. They move money between categories
+/// without counting as spending or income.
+pub const TRANSFER_OUT: &str = "transfer_out";
+pub const TRANSFER_IN: &str = "transfer_in";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferArgs {
+    pub from_category_id: i64,
+    pub to_category_id: i64, // may belong to a different budget
+    pub amount: f64,         // positive
+    pub date: String,        // "YYYY-MM-DD"
+    #[serde(default)]
+    pub what: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferResult {
+    pub from_entry_id: i64,
+    pub to_entry_id: i64,
+}
+
+pub(crate) fn is_transfer_type(entry_type: &str) -> bool {
+    entry_type == TRANSFER_OUT || entry_type == TRANSFER_IN
+}
+
+/// The other side of a transfer entry, if the entry is one and that side is currently
+/// in the trash (`deleted`) or not.
+pub(crate) fn linked_entry(conn: &Connection, entry_id: i64, deleted: bool) -> Result<Option<i64>, String> {
+    conn.query_row(
+        "SELECT l.expense_id FROM expenses e
+         JOIN expenses l ON l.expense_id = e.linked_entry_id
+         WHERE e.expense_id = ?1 AND (l.deleted_at IS NOT NULL) = ?2",
+        rusqlite::params![entry_id, deleted],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())
+}
+
+// Name and budget of a category that can take part in a transfer
+fn live_category(conn: &Connection, category_id: i64) -> Result<(String, i64), String> {
+    conn.query_row(
+        "SELECT c.category_name, c.budget_id FROM budget_categories c
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+         WHERE c.category_id = ?1 AND c.deleted_at IS NULL AND b.deleted_at IS NULL",
+        [category_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|_| format!("Category {} not found", category_id))
+}
+
+fn insert_side(
+    conn: &Connection,
+    category_id: i64,
+    entry_type: &str,
+    what: &str,
+    args: &TransferArgs,
+) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO expenses (category_id, entry_type, description, notes, amount, date, is_recurring, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, datetime('now'))",
+        rusqlite::params![category_id, entry_type, what, args.notes, args.amount, args.date],
+    ).map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[tauri::command]
+pub fn transfer_between_categories(args: TransferArgs, app: AppHandle, db: State<DbState>) -> Result<TransferResult, String> {
+    println!("=== TRANSFER_BETWEEN_CATEGORIES COMMAND CALLED ===");
+    println!("Transfer: {:?}", args);
+
+    if args.amount <= 0.0 {
+        return Err("Transfer amount must be positive".to_string());
+    }
+    if args.from_category_id == args.to_category_id {
+        return Err("Cannot transfer a category to itself".to_string());
+    }
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (from_name, from_budget) = live_category(&tx, args.from_category_id)?;
+    let (to_name, to_budget) = live_category(&tx, args.to_category_id)?;
+
+    let what = args
+        .what
+        .as_deref()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Transfer from {} to {}", from_name, to_name));
+
+    let from_entry_id = insert_side(&tx, args.from_category_id, TRANSFER_OUT, &what, &args)?;
+    let to_entry_id = insert_side(&tx, args.to_category_id, TRANSFER_IN, &what, &args)?;
+
+    tx.execute(
+        "UPDATE expenses SET linked_entry_id = ?1 WHERE expense_id = ?2",
+        rusqlite::params![to_entry_id, from_entry_id],
+    ).map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE expenses SET linked_entry_id = ?1 WHERE expense_id = ?2",
+        rusqlite::params![from_entry_id, to_entry_id],
+    ).map_err(|e| e.to_string())?;
+
+    // Each budget records the side that belongs to it
+    let description = format!("Transferred ${:.2} from {} to {}", args.amount, from_name, to_name);
+    let amount = format!("{:.2}", args.amount);
+    for (budget_id, entry_id) in [(from_budget, from_entry_id), (to_budget, to_entry_id)] {
+        let change_id = log_budget_change(&tx, budget_id, "transfer", Some("expenses"), None, Some(&amount), &description)?;
+        attach_row_snapshots(&tx, change_id, "expenses", entry_id, None)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    // Only the side giving money away can cross a threshold
+    raise_budget_alerts(&app, &conn, from_budget);
+
+    println!("Successfully created transfer entries {} and {}", from_entry_id, to_entry_id);
+    Ok(TransferResult { from_entry_id, to_entry_id })
+}
@@ -0,0 +1,399 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::modules::commands::alert::raise_entry_budget_alerts;
+use crate::modules::commands::attachment::collect_orphaned_files;
+use crate::modules::commands::budget::{log_budget_change, recompute_budget_allocations};
+use crate::modules::commands::history::{attach_row_snapshots, row_snapshot};
+use crate::modules::commands::transfer::linked_entry;
+use crate::modules::database::DbState;
+
+/// How long deleted budgets, categories and entries stay in the trash before
+/// the purge that runs on startup removes them for good.
+pub const TRASH_RETENTION_DAYS: u32 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedEntry {
+    pub entry_id: i64,
+    pub category_id: i64,
+    pub category_name: String,
+    pub entry_type: String,
+    pub what: String,
+    pub r#where: Option<String>,
+    pub amount: f64,
+    pub date: String,
+    pub deleted_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedCategory {
+    pub category_id: i64,
+    pub category_name: String,
+    pub allocated_amount: f64,
+    pub entries_count: i64,
+    pub deleted_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetTrash {
+    pub budget_id: i64,
+    pub entries: Vec<TrashedEntry>,
+    pub categories: Vec<TrashedCategory>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedBudget {
+    pub budget_id: i64,
+    pub month: u8,
+    pub year: i32,
+    pub total_income: f64,
+    pub name: Option<String>,
+    pub deleted_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeSummary {
+    pub budgets: usize,
+    pub categories: usize,
+    pub entries: usize,
+}
+
+#[tauri::command]
+pub fn get_budget_trash(budget_id: i64, db: State<DbState>) -> Result<BudgetTrash, String> {
+    println!("=== GET_BUDGET_TRASH COMMAND CALLED ===");
+    println!("Fetching trash for budget ID: {}", budget_id);
+
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT e.expense_id, e.category_id, c.category_name, e.entry_type, e.description, e.place, e.amount, e.date, e.deleted_at
+         FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE c.budget_id = ?1 AND e.deleted_at IS NOT NULL
+         ORDER BY e.deleted_at DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([budget_id], |row| {
+        Ok(TrashedEntry {
+            entry_id: row.get(0)?,
+            category_id: row.get(1)?,
+            category_name: row.get(2)?,
+            entry_type: row.get(3)?,
+            what: row.get(4)?,
+            r#where: row.get(5)?,
+            amount: row.get(6)?,
+            date: row.get(7)?,
+            deleted_at: row.get(8)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row.map_err(|e| e.to_string())?);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT c.category_id, c.category_name, c.allocated_amount,
+                (SELECT COUNT(*) FROM expenses e WHERE e.category_id = c.category_id AND e.deleted_at IS NULL),
+                c.deleted_at
+         FROM budget_categories c
+         WHERE c.budget_id = ?1 AND c.deleted_at IS NOT NULL
+         ORDER BY c.deleted_at DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([budget_id], |row| {
+        Ok(TrashedCategory {
+            category_id: row.get(0)?,
+            category_name: row.get(1)?,
+            allocated_amount: row.get(2)?,
+            entries_count: row.get(3)?,
+            deleted_at: row.get(4)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut categories = Vec::new();
+    for row in rows {
+        categories.push(row.map_err(|e| e.to_string())?);
+    }
+
+    println!("Found {} trashed entries and {} trashed categories", entries.len(), categories.len());
+    Ok(BudgetTrash { budget_id, entries, categories })
+}
+
+#[tauri::command]
+pub fn list_trashed_budgets(db: State<DbState>) -> Result<Vec<TrashedBudget>, String> {
+    let conn = db.get_conn().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT budget_id, month, year, total_income, name, deleted_at
+         FROM MonthlyBudgets
+         WHERE deleted_at IS NOT NULL
+         ORDER BY deleted_at DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(TrashedBudget {
+            budget_id: row.get(0)?,
+            month: row.get(1)?,
+            year: row.get(2)?,
+            total_income: row.get(3)?,
+            name: row.get(4)?,
+            deleted_at: row.get(5)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut budgets = Vec::new();
+    for row in rows {
+        budgets.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(budgets)
+}
+
+#[tauri::command]
+pub fn restore_category_entry(entry_id: i64, app: AppHandle, db: State<DbState>) -> Result<(), String> {
+    println!("=== RESTORE_CATEGORY_ENTRY COMMAND CALLED ===");
+    println!("Restoring entry ID: {}", entry_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    restore_entry(&tx, entry_id)?;
+
+    // A transfer comes back with both of its sides
+    if let Some(linked_id) = linked_entry(&tx, entry_id, true)? {
+        restore_entry(&tx, linked_id)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    raise_entry_budget_alerts(&app, &conn, entry_id);
+
+    println!("Successfully restored entry with ID: {}", entry_id);
+    Ok(())
+}
+
+fn restore_entry(tx: &Connection, entry_id: i64) -> Result<(), String> {
+    let (category_name, budget_id, amount, category_deleted, budget_deleted): (String, i64, f64, Option<String>, Option<String>) = tx.query_row(
+        "SELECT c.category_name, c.budget_id, e.amount, c.deleted_at, b.deleted_at
+         FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+         WHERE e.expense_id = ?1 AND e.deleted_at IS NOT NULL",
+        [entry_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+    ).map_err(|_| "Entry not found in the trash".to_string())?;
+
+    if budget_deleted.is_some() {
+        return Err(format!("Budget {} is in the trash; restore it first", budget_id));
+    }
+    if category_deleted.is_some() {
+        return Err(format!("Category {} is in the trash; restore it first", category_name));
+    }
+
+    let before = row_snapshot(tx, "expenses", entry_id)?;
+
+    tx.execute(
+        "UPDATE expenses SET deleted_at = NULL WHERE expense_id = ?1",
+        [entry_id],
+    ).map_err(|e| e.to_string())?;
+
+    let description = format!("Restored entry {} to {}", entry_id, category_name);
+    let change_id = log_budget_change(tx, budget_id, "entry_restore", Some("expenses"),
+                                      None, Some(&format!("{:.2}", amount)), &description)?;
+    attach_row_snapshots(tx, change_id, "expenses", entry_id, before)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn restore_budget_category(category_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== RESTORE_BUDGET_CATEGORY COMMAND CALLED ===");
+    println!("Restoring category ID: {}", category_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (category_name, budget_id, parent_deleted, budget_deleted): (String, i64, Option<String>, Option<String>) = tx.query_row(
+        "SELECT c.category_name, c.budget_id, p.deleted_at, b.deleted_at
+         FROM budget_categories c
+         JOIN MonthlyBudgets b ON b.budget_id = c.budget_id
+         LEFT JOIN budget_categories p ON p.category_id = c.parent_category_id
+         WHERE c.category_id = ?1 AND c.deleted_at IS NOT NULL",
+        [category_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+    ).map_err(|_| "Category not found in the trash".to_string())?;
+
+    if budget_deleted.is_some() {
+        return Err(format!("Budget {} is in the trash; restore it first", budget_id));
+    }
+    if parent_deleted.is_some() {
+        return Err(format!("The parent of {} is in the trash; restore it first", category_name));
+    }
+
+    let before = row_snapshot(&tx, "budget_categories", category_id)?;
+
+    tx.execute(
+        "UPDATE budget_categories SET deleted_at = NULL WHERE category_id = ?1",
+        [category_id],
+    ).map_err(|e| e.to_string())?;
+
+    let description = format!("Restored category '{}' from the trash", category_name);
+    let change_id = log_budget_change(&tx, budget_id, "category_restore", Some("budget_categories"),
+                                      None, Some(&category_name), &description)?;
+    attach_row_snapshots(&tx, change_id, "budget_categories", category_id, before)?;
+
+    recompute_budget_allocations(&tx, budget_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Successfully restored category with ID: {}", category_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn restore_monthly_budget(budget_id: i64, db: State<DbState>) -> Result<(), String> {
+    println!("=== RESTORE_MONTHLY_BUDGET COMMAND CALLED ===");
+    println!("Restoring budget ID: {}", budget_id);
+
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = row_snapshot(&tx, "MonthlyBudgets", budget_id)?;
+
+    let rows_affected = tx.execute(
+        "UPDATE MonthlyBudgets SET deleted_at = NULL, last_edited = datetime('now') WHERE budget_id = ?1 AND deleted_at IS NOT NULL",
+        [budget_id],
+    ).map_err(|e| e.to_string())?;
+
+    if rows_affected == 0 {
+        return Err(format!("Budget with ID {} is not in the trash", budget_id));
+    }
+
+    let change_id = log_budget_change(&tx, budget_id, "budget_restore", Some("deleted_at"),
+                                      Some("current_timestamp"), Some("null"), "Budget restored from the trash")?;
+    attach_row_snapshots(&tx, change_id, "MonthlyBudgets", budget_id, before)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("Successfully restored budget with ID: {}", budget_id);
+    Ok(())
+}
+
+fn expired_ids(conn: &Connection, sql: &str, cutoff: &str) -> Result<Vec<(i64, i64, String)>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([cutoff], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut ids = Vec::new();
+    for row in rows {
+        ids.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(ids)
+}
+
+/// Permanently removes everything that has been in the trash for more than `days` days.
+/// Purged rows cannot be undone; the history keeps a record of what was removed.
+fn purge_expired_trash(conn: &Connection, days: u32) -> Result<PurgeSummary, String> {
+    let cutoff = format!("-{} days", days);
+    // Reconciled entries, and whatever holds them, stay until they are unlocked
+    let mut summary = PurgeSummary::default();
+
+    let budgets = expired_ids(
+        conn,
+        "SELECT budget_id, budget_id, COALESCE(name, month || '/' || year) FROM MonthlyBudgets
+         WHERE deleted_at IS NOT NULL AND deleted_at <= datetime('now', ?1)
+           AND NOT EXISTS (SELECT 1 FROM expenses e JOIN budget_categories c ON c.category_id = e.category_id
+                           WHERE c.budget_id = MonthlyBudgets.budget_id AND e.reconciled_at IS NOT NULL)",
+        &cutoff,
+    )?;
+    for (budget_id, _, name) in budgets {
+        let entries = conn.execute(
+            "DELETE FROM expenses WHERE category_id IN (SELECT category_id FROM budget_categories WHERE budget_id = ?1)",
+            [budget_id],
+        ).map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM budget_categories WHERE budget_id = ?1", [budget_id])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM MonthlyBudgets WHERE budget_id = ?1", [budget_id])
+            .map_err(|e| e.to_string())?;
+
+        // Connections don't enforce foreign keys, so nothing cascades: the budget's change
+        // history is kept on purpose and its chain ends with a record of the purge
+        log_budget_change(conn, budget_id, "budget_purge", Some("MonthlyBudgets"), Some(&name), None,
+                          &format!("Budget '{}' and its {} entries permanently deleted from the trash", name, entries))?;
+
+        println!("Budget {} ({}) permanently deleted from the trash", budget_id, name);
+        summary.budgets += 1;
+    }
+
+    let categories = expired_ids(
+        conn,
+        "SELECT category_id, budget_id, category_name FROM budget_categories
+         WHERE deleted_at IS NOT NULL AND deleted_at <= datetime('now', ?1)
+           AND NOT EXISTS (SELECT 1 FROM expenses e
+                           WHERE e.category_id = budget_categories.category_id AND e.reconciled_at IS NOT NULL)",
+        &cutoff,
+    )?;
+    for (category_id, budget_id, name) in categories {
+        let entries = conn.execute("DELETE FROM expenses WHERE category_id = ?1", [category_id])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM budget_categories WHERE category_id = ?1", [category_id])
+            .map_err(|e| e.to_string())?;
+
+        log_budget_change(conn, budget_id, "category_purge", Some("budget_categories"), Some(&name), None,
+                          &format!("Category '{}' and its {} entries permanently deleted from the trash", name, entries))?;
+        summary.categories += 1;
+    }
+
+    let entries = expired_ids(
+        conn,
+        "SELECT e.expense_id, c.budget_id, e.description FROM expenses e
+         JOIN budget_categories c ON c.category_id = e.category_id
+         WHERE e.deleted_at IS NOT NULL AND e.deleted_at <= datetime('now', ?1) AND e.reconciled_at IS NULL",
+        &cutoff,
+    )?;
+    for (entry_id, budget_id, what) in entries {
+        conn.execute("DELETE FROM expenses WHERE expense_id = ?1", [entry_id])
+            .map_err(|e| e.to_string())?;
+
+        log_budget_change(conn, budget_id, "entry_purge", Some("expenses"), Some(&what), None,
+                          &format!("Entry {} ({}) permanently deleted from the trash", entry_id, what))?;
+        summary.entries += 1;
+    }
+
+    Ok(summary)
+}
+
+pub(crate) fn purge_trash_older_than(db: &DbState, days: u32) -> Result<PurgeSummary, String> {
+    let mut conn = db.get_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let summary = purge_expired_trash(&tx, days)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    // Purged entries took their attachment rows with them; their files go now. A failure
+    // here leaves files behind for the next purge rather than failing this one.
+    match collect_orphaned_files(&conn, &db.attachments_dir()) {
+        Ok(removed) if removed > 0 => println!("Removed {} orphaned attachment files", removed),
+        Ok(_) => {}
+        Err(e) => println!("Warning: failed to collect orphaned attachment files: {}", e),
+    }
+
+    println!("Purged {} budgets, {} categories and {} entries", summary.budgets, summary.categories, summary.entries);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn purge_trash(older_than_days: Option<u32>, db: State<DbState>) -> Result<PurgeSummary, String> {
+    println!("=== PURGE_TRASH COMMAND CALLED ===");
+    let days = older_than_days.unwrap_or(TRASH_RETENTION_DAYS);
+    println!("Purging trash older than {} days", days);
+
+    purge_trash_older_than(&db, days)
+}
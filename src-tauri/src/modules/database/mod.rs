@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 use thiserror::Error;
 
+use crate::modules::security::audit;
+
 #[derive(Debug, Error)]
 pub enum DbError {
     #[error("IO error: {0}")]
@@ -27,6 +29,10 @@ impl DbState {
         Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE)
             .map_err(|e| DbError::Sql(e.to_string()))
     }
+
+    pub fn attachments_dir(&self) -> PathBuf {
+        self.path.with_file_name("attachments")
+    }
 }
 
 pub fn init_state(app: &AppHandle) -> Result<DbState, DbError> {
@@ -183,6 +189,12 @@ pub fn run_migrations(state: &DbState) -> Result<(), DbError> {
         println!("Added first_finished_at column to MonthlyBudgets");
     }
 
+    if !existing_columns.contains(&"deleted_at".to_string()) {
+        conn.execute("ALTER TABLE MonthlyBudgets ADD COLUMN deleted_at TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add deleted_at column: {}", e)))?;
+        println!("Added deleted_at column to MonthlyBudgets");
+    }
+
     // Ensure all existing budgets have proper created_at timestamps
     conn.execute("UPDATE MonthlyBudgets SET created_at = datetime('now') WHERE created_at IS NULL", [])
         .map_err(|e| DbError::Sql(format!("Failed to update null created_at values: {}", e)))?;
@@ -223,11 +235,172 @@ pub fn run_migrations(state: &DbState) -> Result<(), DbError> {
             .map_err(|e| DbError::Sql(format!("Failed to add deleted_at column: {}", e)))?;
         println!("Added deleted_at column to Expenses");
     }
+
+    if !existing_expenses_columns.contains(&"is_recurring".to_string()) {
+        conn.execute("ALTER TABLE Expenses ADD COLUMN is_recurring INTEGER NOT NULL DEFAULT 0", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add is_recurring column: {}", e)))?;
+        println!("Added is_recurring column to Expenses");
+    }
+
+    // The two sides of a transfer point at each other
+    if !existing_expenses_columns.contains(&"linked_entry_id".to_string()) {
+        conn.execute("ALTER TABLE Expenses ADD COLUMN linked_entry_id INTEGER", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add linked_entry_id column: {}", e)))?;
+        println!("Added linked_entry_id column to Expenses");
+    }
+
+    // The account the money of an entry moved through, if any
+    if !existing_expenses_columns.contains(&"account_id".to_string()) {
+        conn.execute("ALTER TABLE Expenses ADD COLUMN account_id INTEGER REFERENCES accounts(account_id)", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add account_id column: {}", e)))?;
+        println!("Added account_id column to Expenses");
+    }
+
+    // Reconciliation marks: cleared once the bank shows the entry, reconciled (and locked)
+    // once a statement balance was matched with it
+    if !existing_expenses_columns.contains(&"cleared_at".to_string()) {
+        conn.execute("ALTER TABLE Expenses ADD COLUMN cleared_at TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add cleared_at column: {}", e)))?;
+        println!("Added cleared_at column to Expenses");
+    }
+
+    if !existing_expenses_columns.contains(&"reconciled_at".to_string()) {
+        conn.execute("ALTER TABLE Expenses ADD COLUMN reconciled_at TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add reconciled_at column: {}", e)))?;
+        println!("Added reconciled_at column to Expenses");
+    }
+
+    if !existing_expenses_columns.contains(&"reconciliation_id".to_string()) {
+        conn.execute("ALTER TABLE Expenses ADD COLUMN reconciliation_id INTEGER REFERENCES reconciliations(reconciliation_id)", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add reconciliation_id column: {}", e)))?;
+        println!("Added reconciliation_id column to Expenses");
+    }
+
+    // Columns added to budget_categories after its first release. The template columns are
+    // also added by run_migration, but cloning relies on them even if that has never run
+    let existing_category_columns = table_columns(&conn, "budget_categories")?;
+
+    if !existing_category_columns.contains(&"global_category_id".to_string()) {
+        conn.execute("ALTER TABLE budget_categories ADD COLUMN global_category_id INTEGER REFERENCES global_categories(global_category_id)", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add global_category_id column: {}", e)))?;
+        println!("Added global_category_id column to budget_categories");
+    }
+
+    if !existing_category_columns.contains(&"category_type".to_string()) {
+        conn.execute("ALTER TABLE budget_categories ADD COLUMN category_type TEXT DEFAULT 'expense'", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add category_type column: {}", e)))?;
+        println!("Added category_type column to budget_categories");
+    }
+
+    if !existing_category_columns.contains(&"allocation_type".to_string()) {
+        conn.execute("ALTER TABLE budget_categories ADD COLUMN allocation_type TEXT NOT NULL DEFAULT 'fixed'", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add allocation_type column: {}", e)))?;
+        println!("Added allocation_type column to budget_categories");
+    }
+
+    if !existing_category_columns.contains(&"formula".to_string()) {
+        conn.execute("ALTER TABLE budget_categories ADD COLUMN formula TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add formula column: {}", e)))?;
+        println!("Added formula column to budget_categories");
+    }
+
+    if !existing_category_columns.contains(&"parent_category_id".to_string()) {
+        conn.execute("ALTER TABLE budget_categories ADD COLUMN parent_category_id INTEGER REFERENCES budget_categories(category_id)", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add parent_category_id column: {}", e)))?;
+        println!("Added parent_category_id column to budget_categories");
+    }
+
+    if !existing_category_columns.contains(&"deleted_at".to_string()) {
+        conn.execute("ALTER TABLE budget_categories ADD COLUMN deleted_at TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add deleted_at column: {}", e)))?;
+        println!("Added deleted_at column to budget_categories");
+    }
     
+    // Merge/split records carry what is needed to reverse them
+    let existing_merge_columns = table_columns(&conn, "CategoryMergeHistory")?;
+
+    if !existing_merge_columns.contains(&"operation".to_string()) {
+        conn.execute("ALTER TABLE CategoryMergeHistory ADD COLUMN operation TEXT NOT NULL DEFAULT 'merge'", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add operation column: {}", e)))?;
+        println!("Added operation column to CategoryMergeHistory");
+    }
+
+    if !existing_merge_columns.contains(&"details".to_string()) {
+        conn.execute("ALTER TABLE CategoryMergeHistory ADD COLUMN details TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add details column: {}", e)))?;
+        println!("Added details column to CategoryMergeHistory");
+    }
+
+    if !existing_merge_columns.contains(&"reverted_at".to_string()) {
+        conn.execute("ALTER TABLE CategoryMergeHistory ADD COLUMN reverted_at TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add reverted_at column: {}", e)))?;
+        println!("Added reverted_at column to CategoryMergeHistory");
+    }
+
+    // Row snapshots let a history entry be undone and redone
+    let existing_history_columns = table_columns(&conn, "BudgetChangeHistory")?;
+
+    if !existing_history_columns.contains(&"entity_table".to_string()) {
+        conn.execute("ALTER TABLE BudgetChangeHistory ADD COLUMN entity_table TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add entity_table column: {}", e)))?;
+        println!("Added entity_table column to BudgetChangeHistory");
+    }
+
+    if !existing_history_columns.contains(&"entity_id".to_string()) {
+        conn.execute("ALTER TABLE BudgetChangeHistory ADD COLUMN entity_id INTEGER", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add entity_id column: {}", e)))?;
+        println!("Added entity_id column to BudgetChangeHistory");
+    }
+
+    if !existing_history_columns.contains(&"before_snapshot".to_string()) {
+        conn.execute("ALTER TABLE BudgetChangeHistory ADD COLUMN before_snapshot TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add before_snapshot column: {}", e)))?;
+        println!("Added before_snapshot column to BudgetChangeHistory");
+    }
+
+    if !existing_history_columns.contains(&"after_snapshot".to_string()) {
+        conn.execute("ALTER TABLE BudgetChangeHistory ADD COLUMN after_snapshot TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add after_snapshot column: {}", e)))?;
+        println!("Added after_snapshot column to BudgetChangeHistory");
+    }
+
+    if !existing_history_columns.contains(&"reverts_change_id".to_string()) {
+        conn.execute("ALTER TABLE BudgetChangeHistory ADD COLUMN reverts_change_id INTEGER", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add reverts_change_id column: {}", e)))?;
+        println!("Added reverts_change_id column to BudgetChangeHistory");
+    }
+
+    // Each history row is hash-chained to the previous row of the same budget
+    if !existing_history_columns.contains(&"prev_hash".to_string()) {
+        conn.execute("ALTER TABLE BudgetChangeHistory ADD COLUMN prev_hash TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add prev_hash column: {}", e)))?;
+        println!("Added prev_hash column to BudgetChangeHistory");
+    }
+
+    if !existing_history_columns.contains(&"row_hash".to_string()) {
+        conn.execute("ALTER TABLE BudgetChangeHistory ADD COLUMN row_hash TEXT", [])
+            .map_err(|e| DbError::Sql(format!("Failed to add row_hash column: {}", e)))?;
+        println!("Added row_hash column to BudgetChangeHistory");
+
+        // Rows written before hashing existed are sealed once, here. From now on every row is
+        // sealed when written, so a row without a hash is reported as a break
+        let sealed = audit::seal_unsealed_history(&conn).map_err(|e| DbError::Sql(e.to_string()))?;
+        println!("Sealed {} existing change history rows", sealed);
+    }
+
+    // History hashes are keyed with a secret kept outside the database from now on
+    let resealed = audit::seal_history_with_key(&conn).map_err(|e| DbError::Sql(e.to_string()))?;
+    if resealed > 0 {
+        println!("Re-sealed {} change history rows with the history key", resealed);
+    }
+
     // Create indexes for performance
     conn.execute("CREATE INDEX IF NOT EXISTS idx_budget_categories_budget_id ON budget_categories(budget_id)", [])
         .map_err(|e| DbError::Sql(format!("Failed to create budget_categories index: {}", e)))?;
     
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_budget_categories_parent ON budget_categories(parent_category_id)", [])
+        .map_err(|e| DbError::Sql(format!("Failed to create budget_categories parent index: {}", e)))?;
+    
     conn.execute("CREATE INDEX IF NOT EXISTS idx_expenses_category_date ON Expenses(allocation_id, date)", [])
         .map_err(|e| DbError::Sql(format!("Failed to create expenses index: {}", e)))?;
     
@@ -237,8 +410,424 @@ pub fn run_migrations(state: &DbState) -> Result<(), DbError> {
     conn.execute("CREATE INDEX IF NOT EXISTS idx_expenses_entry_type ON Expenses(entry_type)", [])
         .map_err(|e| DbError::Sql(format!("Failed to create expenses entry_type index: {}", e)))?;
     
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_expenses_linked_entry ON Expenses(linked_entry_id) WHERE linked_entry_id IS NOT NULL", [])
+        .map_err(|e| DbError::Sql(format!("Failed to create expenses linked_entry index: {}", e)))?;
+    
+    // The surviving side of a transfer whose other side was purged no longer points at it
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS expenses_linked_entry_cleanup AFTER DELETE ON expenses BEGIN
+            UPDATE expenses SET linked_entry_id = NULL WHERE linked_entry_id = old.expense_id;
+        END",
+        [],
+    ).map_err(|e| DbError::Sql(format!("Failed to create linked entry cleanup trigger: {}", e)))?;
+    
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_expenses_account ON Expenses(account_id, date) WHERE account_id IS NOT NULL", [])
+        .map_err(|e| DbError::Sql(format!("Failed to create expenses account index: {}", e)))?;
+    
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_budget_change_history_budget ON BudgetChangeHistory(budget_id, changed_at)", [])
+        .map_err(|e| DbError::Sql(format!("Failed to create change history budget index: {}", e)))?;
+    
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_budget_change_history_changed_at ON BudgetChangeHistory(changed_at)", [])
+        .map_err(|e| DbError::Sql(format!("Failed to create change history date index: {}", e)))?;
+    
     println!("Created performance indexes");
 
+    // Full-text index over entry text, kept in step with the ledger by triggers
+    let fts_exists: bool = conn
+        .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'expenses_fts'", [], |row| row.get::<_, i64>(0))
+        .map(|count| count > 0)
+        .map_err(|e| DbError::Sql(e.to_string()))?;
+
+    conn.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS expenses_fts USING fts5(
+            description, place, notes,
+            content = 'expenses', content_rowid = 'expense_id',
+            tokenize = 'unicode61 remove_diacritics 2', prefix = '2 3'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS expenses_fts_insert AFTER INSERT ON expenses BEGIN
+            INSERT INTO expenses_fts(rowid, description, place, notes)
+            VALUES (new.expense_id, new.description, new.place, new.notes);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS expenses_fts_delete AFTER DELETE ON expenses BEGIN
+            INSERT INTO expenses_fts(expenses_fts, rowid, description, place, notes)
+            VALUES ('delete', old.expense_id, old.description, old.place, old.notes);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS expenses_fts_update AFTER UPDATE OF description, place, notes ON expenses BEGIN
+            INSERT INTO expenses_fts(expenses_fts, rowid, description, place, notes)
+            VALUES ('delete', old.expense_id, old.description, old.place, old.notes);
+            INSERT INTO expenses_fts(rowid, description, place, notes)
+            VALUES (new.expense_id, new.description, new.place, new.notes);
+        END;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create full-text index: {}", e)))?;
+
+    if !fts_exists {
+        rebuild_search_index(&conn).map_err(|e| DbError::Sql(format!("Failed to build full-text index: {}", e)))?;
+        println!("Built full-text index over existing entries");
+    }
+
+    // Free-form tags on ledger entries. Entries are hard-deleted from several places,
+    // so a trigger rather than each caller drops their tag links
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS tags (
+            tag_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS entry_tags (
+            expense_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (expense_id, tag_id),
+            FOREIGN KEY (expense_id) REFERENCES expenses(expense_id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(tag_id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_tags_tag_id ON entry_tags(tag_id);
+
+        CREATE TRIGGER IF NOT EXISTS entry_tags_cleanup AFTER DELETE ON expenses BEGIN
+            DELETE FROM entry_tags WHERE expense_id = old.expense_id;
+        END;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create tag tables: {}", e)))?;
+
+    // Split entries spread their amount over parts in other categories of the same budget.
+    // entry_lines has one row per category an entry counts towards, which is the entry
+    // itself when it is not split
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS entry_splits (
+            split_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            expense_id INTEGER NOT NULL,
+            category_id INTEGER NOT NULL,
+            amount REAL NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (expense_id) REFERENCES expenses(expense_id) ON DELETE CASCADE,
+            FOREIGN KEY (category_id) REFERENCES budget_categories(category_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_splits_expense_id ON entry_splits(expense_id);
+        CREATE INDEX IF NOT EXISTS idx_entry_splits_category_id ON entry_splits(category_id);
+
+        CREATE TRIGGER IF NOT EXISTS entry_splits_cleanup AFTER DELETE ON expenses BEGIN
+            DELETE FROM entry_splits WHERE expense_id = old.expense_id;
+        END;
+
+        -- Parts left in a purged category fall back to their entry's own category
+        CREATE TRIGGER IF NOT EXISTS entry_splits_rehome AFTER DELETE ON budget_categories BEGIN
+            UPDATE entry_splits
+            SET category_id = (SELECT e.category_id FROM expenses e WHERE e.expense_id = entry_splits.expense_id)
+            WHERE category_id = old.category_id;
+        END;
+
+        CREATE VIEW IF NOT EXISTS entry_lines AS
+            SELECT e.expense_id, e.category_id, e.entry_type, e.amount, e.date, e.deleted_at, 0 AS is_split
+            FROM expenses e
+            WHERE NOT EXISTS (SELECT 1 FROM entry_splits s WHERE s.expense_id = e.expense_id)
+            UNION ALL
+            SELECT e.expense_id, s.category_id, e.entry_type, SUM(s.amount), e.date, e.deleted_at, 1
+            FROM entry_splits s
+            JOIN expenses e ON e.expense_id = s.expense_id
+            GROUP BY e.expense_id, s.category_id;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create entry split tables: {}", e)))?;
+
+    // File metadata for attachments; the files themselves live in attachments_dir(),
+    // named by their SHA-256 so identical files are stored once
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS attachments (
+            attachment_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner_type TEXT NOT NULL, -- 'entry'
+            owner_id INTEGER NOT NULL,
+            file_name TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            sha256 TEXT NOT NULL,
+            encryption TEXT NOT NULL DEFAULT 'none',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_attachments_owner ON attachments(owner_type, owner_id);
+        CREATE INDEX IF NOT EXISTS idx_attachments_sha256 ON attachments(sha256);
+
+        CREATE TRIGGER IF NOT EXISTS attachments_entry_cleanup AFTER DELETE ON expenses BEGIN
+            DELETE FROM attachments WHERE owner_type = 'entry' AND owner_id = old.expense_id;
+        END;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create attachments table: {}", e)))?;
+
+    // Merchants group the free-text place of entries: every alias is a place spelling
+    // (trimmed, ASCII-lowercased) that belongs to the merchant
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS merchants (
+            merchant_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS merchant_aliases (
+            alias TEXT PRIMARY KEY,
+            merchant_id INTEGER NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (merchant_id) REFERENCES merchants(merchant_id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_merchant_aliases_merchant_id ON merchant_aliases(merchant_id);
+
+        CREATE TRIGGER IF NOT EXISTS merchant_aliases_cleanup AFTER DELETE ON merchants BEGIN
+            DELETE FROM merchant_aliases WHERE merchant_id = old.merchant_id;
+        END;
+
+        -- The merchant of every entry with a place; places without an alias stand for themselves
+        CREATE VIEW IF NOT EXISTS entry_merchants AS
+            SELECT e.expense_id, a.merchant_id, COALESCE(m.name, TRIM(e.place)) AS merchant,
+                   LOWER(TRIM(e.place)) AS place_key
+            FROM expenses e
+            LEFT JOIN merchant_aliases a ON a.alias = LOWER(TRIM(e.place))
+            LEFT JOIN merchants m ON m.merchant_id = a.merchant_id
+            WHERE TRIM(COALESCE(e.place, '')) <> '';
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create merchant tables: {}", e)))?;
+
+    // Savings goals; progress is read from the savings-type categories linked to the same
+    // global category, so only the goal itself is stored
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS savings_goals (
+            goal_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            global_category_id INTEGER NOT NULL,
+            target_amount REAL NOT NULL,
+            target_date TEXT NOT NULL, -- YYYY-MM-DD
+            starting_amount REAL NOT NULL DEFAULT 0, -- saved before the app tracked it
+            notes TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (global_category_id) REFERENCES global_categories(global_category_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_savings_goals_global_category_id ON savings_goals(global_category_id);
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create savings goals table: {}", e)))?;
+
+    // Debts and the ledger entries that pay them off; principal is the balance on balance_date,
+    // payments dated from then on reduce it
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS debts (
+            debt_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            principal REAL NOT NULL,
+            balance_date TEXT NOT NULL, -- YYYY-MM-DD
+            interest_rate REAL NOT NULL DEFAULT 0, -- yearly, in percent
+            minimum_payment REAL NOT NULL DEFAULT 0,
+            due_day INTEGER NOT NULL DEFAULT 1, -- 1-31, clamped to the month's length
+            notes TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS debt_payments (
+            expense_id INTEGER PRIMARY KEY,
+            debt_id INTEGER NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (expense_id) REFERENCES expenses(expense_id) ON DELETE CASCADE,
+            FOREIGN KEY (debt_id) REFERENCES debts(debt_id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_debt_payments_debt_id ON debt_payments(debt_id);
+
+        CREATE TRIGGER IF NOT EXISTS debt_payments_entry_cleanup AFTER DELETE ON expenses BEGIN
+            DELETE FROM debt_payments WHERE expense_id = old.expense_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS debt_payments_debt_cleanup AFTER DELETE ON debts BEGIN
+            DELETE FROM debt_payments WHERE debt_id = old.debt_id;
+        END;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create debt tables: {}", e)))?;
+
+    // Accounts hold money (or debt) over time. A balance snapshot is the balance at the end of
+    // its date; later entries on the account move it from there.
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS accounts (
+            account_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            account_type TEXT NOT NULL, -- 'checking' | 'savings' | 'cash' | 'credit_card' | 'investment' | 'loan'
+            opening_balance REAL NOT NULL DEFAULT 0,
+            opening_date TEXT NOT NULL, -- YYYY-MM-DD
+            notes TEXT,
+            closed_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS account_balances (
+            snapshot_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            account_id INTEGER NOT NULL,
+            balance_date TEXT NOT NULL, -- YYYY-MM-DD
+            balance REAL NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE (account_id, balance_date),
+            FOREIGN KEY (account_id) REFERENCES accounts(account_id) ON DELETE CASCADE
+        );
+
+        CREATE TRIGGER IF NOT EXISTS account_balances_cleanup AFTER DELETE ON accounts BEGIN
+            DELETE FROM account_balances WHERE account_id = old.account_id;
+        END;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create account tables: {}", e)))?;
+
+    // One row per statement an account was (or is being) reconciled against
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS reconciliations (
+            reconciliation_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            account_id INTEGER NOT NULL,
+            statement_date TEXT NOT NULL, -- YYYY-MM-DD
+            statement_balance REAL NOT NULL,
+            cleared_balance REAL, -- set when finished
+            status TEXT NOT NULL DEFAULT 'open', -- 'open' | 'finished'
+            started_at TEXT NOT NULL DEFAULT (datetime('now')),
+            finished_at TEXT,
+            FOREIGN KEY (account_id) REFERENCES accounts(account_id) ON DELETE CASCADE
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_reconciliations_open ON reconciliations(account_id) WHERE status = 'open';
+
+        CREATE TRIGGER IF NOT EXISTS reconciliations_cleanup AFTER DELETE ON accounts BEGIN
+            DELETE FROM reconciliations WHERE account_id = old.account_id;
+        END;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create reconciliation table: {}", e)))?;
+
+    // Spending alerts: thresholds are configured once and apply to every budget, each crossing
+    // is stored so it only fires once per budget (and category)
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS alert_thresholds (
+            threshold_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            scope TEXT NOT NULL, -- 'category' | 'budget'
+            global_category_id INTEGER, -- category scope only; NULL applies to every category
+            percent REAL NOT NULL, -- of the allocated amount
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (global_category_id) REFERENCES global_categories(global_category_id) ON DELETE CASCADE
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_alert_thresholds_unique
+            ON alert_thresholds(scope, COALESCE(global_category_id, 0), percent);
+
+        CREATE TABLE IF NOT EXISTS budget_alerts (
+            alert_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            threshold_id INTEGER NOT NULL,
+            budget_id INTEGER NOT NULL,
+            category_id INTEGER, -- NULL for budget-wide alerts
+            percent REAL NOT NULL,
+            used_amount REAL NOT NULL,
+            limit_amount REAL NOT NULL,
+            message TEXT NOT NULL,
+            fired_at TEXT NOT NULL DEFAULT (datetime('now')),
+            acknowledged_at TEXT,
+            FOREIGN KEY (threshold_id) REFERENCES alert_thresholds(threshold_id) ON DELETE CASCADE,
+            FOREIGN KEY (budget_id) REFERENCES MonthlyBudgets(budget_id) ON DELETE CASCADE,
+            FOREIGN KEY (category_id) REFERENCES budget_categories(category_id) ON DELETE CASCADE
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_budget_alerts_once
+            ON budget_alerts(threshold_id, budget_id, COALESCE(category_id, 0));
+
+        CREATE TRIGGER IF NOT EXISTS budget_alerts_threshold_cleanup AFTER DELETE ON alert_thresholds BEGIN
+            DELETE FROM budget_alerts WHERE threshold_id = old.threshold_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS budget_alerts_budget_cleanup AFTER DELETE ON MonthlyBudgets BEGIN
+            DELETE FROM budget_alerts WHERE budget_id = old.budget_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS budget_alerts_category_cleanup AFTER DELETE ON budget_categories BEGIN
+            DELETE FROM budget_alerts WHERE category_id = old.category_id;
+        END;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create alert tables: {}", e)))?;
+
+    // Reminders fired by the scheduler; times are local "YYYY-MM-DD HH:MM:SS"
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS reminders (
+            reminder_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL, -- 'invoice' | 'recurring_bill' | 'budget_deadline' | 'custom'
+            title TEXT NOT NULL,
+            notes TEXT,
+            amount REAL,
+            due_at TEXT NOT NULL, -- the pending occurrence
+            repeat_months INTEGER NOT NULL DEFAULT 0, -- 0 fires once
+            anchor_day INTEGER, -- day of month repeats return to after short months
+            budget_id INTEGER, -- budget deadlines
+            entry_id INTEGER, -- the ledger entry a bill reminder was made from
+            snoozed_until TEXT,
+            last_fired_at TEXT,
+            dismissed_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (budget_id) REFERENCES MonthlyBudgets(budget_id) ON DELETE CASCADE,
+            FOREIGN KEY (entry_id) REFERENCES expenses(expense_id) ON DELETE SET NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_reminders_due ON reminders(dismissed_at, due_at);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_reminders_budget_deadline ON reminders(budget_id) WHERE kind = 'budget_deadline';
+
+        CREATE TRIGGER IF NOT EXISTS reminders_budget_cleanup AFTER DELETE ON MonthlyBudgets BEGIN
+            DELETE FROM reminders WHERE budget_id = old.budget_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS reminders_entry_cleanup AFTER DELETE ON expenses BEGIN
+            UPDATE reminders SET entry_id = NULL WHERE entry_id = old.expense_id;
+        END;
+        "#,
+    )
+    .map_err(|e| DbError::Sql(format!("Failed to create reminders table: {}", e)))?;
+
     Ok(())
 }
 
+// Re-reads every ledger row into expenses_fts, returning how many rows are indexed
+pub(crate) fn rebuild_search_index(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.execute("INSERT INTO expenses_fts(expenses_fts) VALUES ('rebuild')", [])?;
+    conn.query_row("SELECT COUNT(*) FROM expenses", [], |row| row.get(0))
+}
+
+pub(crate) fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, DbError> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({})", table))
+        .map_err(|e| DbError::Sql(e.to_string()))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| DbError::Sql(e.to_string()))?;
+
+    let mut columns = Vec::new();
+    for row in rows {
+        columns.push(row.map_err(|e| DbError::Sql(e.to_string()))?);
+    }
+    Ok(columns)
+}
@@ -0,0 +1,318 @@
+use hmac::{Hmac, Mac};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+// prev_hash of the first row in every budget's chain
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// The history key lives next to the database file, never inside it, so whoever can rewrite
+// the database cannot recompute the hashes of the rows they changed
+const KEY_FILE_NAME: &str = "history.key";
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("db error: {0}")]
+    Db(String),
+    #[error("history key error: {0}")]
+    Key(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainBreak {
+    pub change_id: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainReport {
+    pub budget_id: i64,
+    pub rows_checked: usize,
+    pub valid: bool,
+    pub breaks: Vec<ChainBreak>,
+}
+
+struct HistoryRow {
+    change_id: i64,
+    budget_id: i64,
+    change_type: String,
+    field_name: Option<String>,
+    old_value: Option<String>,
+    new_value: Option<String>,
+    change_description: Option<String>,
+    changed_at: String,
+    entity_table: Option<String>,
+    entity_id: Option<i64>,
+    before_snapshot: Option<String>,
+    after_snapshot: Option<String>,
+    reverts_change_id: Option<i64>,
+    prev_hash: Option<String>,
+    row_hash: Option<String>,
+}
+
+const HISTORY_COLUMNS: &str = "change_id, budget_id, change_type, field_name, old_value, new_value, change_description, changed_at,
+     entity_table, entity_id, before_snapshot, after_snapshot, reverts_change_id, prev_hash, row_hash";
+
+fn read_row(row: &rusqlite::Row) -> rusqlite::Result<HistoryRow> {
+    Ok(HistoryRow {
+        change_id: row.get(0)?,
+        budget_id: row.get(1)?,
+        change_type: row.get(2)?,
+        field_name: row.get(3)?,
+        old_value: row.get(4)?,
+        new_value: row.get(5)?,
+        change_description: row.get(6)?,
+        changed_at: row.get(7)?,
+        entity_table: row.get(8)?,
+        entity_id: row.get(9)?,
+        before_snapshot: row.get(10)?,
+        after_snapshot: row.get(11)?,
+        reverts_change_id: row.get(12)?,
+        prev_hash: row.get(13)?,
+        row_hash: row.get(14)?,
+    })
+}
+
+fn key_path(conn: &Connection) -> Result<PathBuf, AuditError> {
+    match conn.path() {
+        Some(path) if !path.is_empty() => Ok(Path::new(path).with_file_name(KEY_FILE_NAME)),
+        _ => Err(AuditError::Key("history can only be sealed for a database stored on disk".to_string())),
+    }
+}
+
+// Writes a fresh random key unless one already exists. Returns whether it was created
+fn create_key(path: &Path) -> Result<bool, AuditError> {
+    let mut key = [0u8; KEY_LEN];
+    getrandom::getrandom(&mut key).map_err(|e| AuditError::Key(e.to_string()))?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    match options.open(path) {
+        Ok(mut file) => {
+            file.write_all(&key).map_err(|e| AuditError::Key(e.to_string()))?;
+            file.sync_all().map_err(|e| AuditError::Key(e.to_string()))?;
+            Ok(true)
+        }
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(AuditError::Key(e.to_string())),
+    }
+}
+
+fn load_key(conn: &Connection) -> Result<Vec<u8>, AuditError> {
+    let path = key_path(conn)?;
+    create_key(&path)?;
+    let key = std::fs::read(&path).map_err(|e| AuditError::Key(e.to_string()))?;
+    if key.len() != KEY_LEN {
+        return Err(AuditError::Key(format!("{} is not a valid history key", path.display())));
+    }
+    Ok(key)
+}
+
+// Hashes every column of the row together with the previous row's hash, keyed with the history
+// key. `None` gives the unkeyed hash rows were sealed with before the key existed. The fields are
+// encoded as a JSON array so that NULL, empty strings and embedded separators stay distinct
+fn compute_hash(row: &HistoryRow, prev_hash: &str, key: Option<&[u8]>) -> String {
+    let content = serde_json::json!([
+        row.change_id,
+        row.budget_id,
+        row.change_type,
+        row.field_name,
+        row.old_value,
+        row.new_value,
+        row.change_description,
+        row.changed_at,
+        row.entity_table,
+        row.entity_id,
+        row.before_snapshot,
+        row.after_snapshot,
+        row.reverts_change_id,
+        prev_hash,
+    ]);
+
+    match key {
+        Some(key) => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+            mac.update(content.to_string().as_bytes());
+            format!("{:x}", mac.finalize().into_bytes())
+        }
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.update(content.to_string().as_bytes());
+            format!("{:x}", hasher.finalize())
+        }
+    }
+}
+
+/// Links a history row to the row before it in the same budget and stores its hash.
+/// Must be called again whenever the row is completed after being inserted.
+pub fn seal_history_row(conn: &Connection, change_id: i64) -> Result<(), AuditError> {
+    let key = load_key(conn)?;
+    let row = conn
+        .query_row(
+            &format!("SELECT {} FROM BudgetChangeHistory WHERE change_id = ?1", HISTORY_COLUMNS),
+            [change_id],
+            read_row,
+        )
+        .map_err(|e| AuditError::Db(e.to_string()))?;
+
+    let prev_hash: Option<String> = conn
+        .query_row(
+            "SELECT row_hash FROM BudgetChangeHistory WHERE budget_id = ?1 AND change_id < ?2 ORDER BY change_id DESC LIMIT 1",
+            rusqlite::params![row.budget_id, change_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| AuditError::Db(e.to_string()))?
+        .flatten();
+    let prev_hash = prev_hash.unwrap_or_else(|| GENESIS_HASH.to_string());
+    let row_hash = compute_hash(&row, &prev_hash, Some(&key));
+
+    conn.execute(
+        "UPDATE BudgetChangeHistory SET prev_hash = ?1, row_hash = ?2 WHERE change_id = ?3",
+        rusqlite::params![prev_hash, row_hash, change_id],
+    )
+    .map_err(|e| AuditError::Db(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Seals history rows written before hashing existed, oldest first. Only the migration that adds
+/// the hash columns may call this; re-sealing later would turn tampered rows into a valid chain.
+pub fn seal_unsealed_history(conn: &Connection) -> Result<usize, AuditError> {
+    let ids: Vec<i64> = {
+        let mut stmt = conn
+            .prepare("SELECT change_id FROM BudgetChangeHistory WHERE row_hash IS NULL ORDER BY change_id")
+            .map_err(|e| AuditError::Db(e.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| AuditError::Db(e.to_string()))?;
+
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| AuditError::Db(e.to_string()))?);
+        }
+        ids
+    };
+
+    for change_id in &ids {
+        seal_history_row(conn, *change_id)?;
+    }
+    Ok(ids.len())
+}
+
+/// Creates the history key on first run. Chains sealed before the key existed are re-sealed with
+/// it, but only those that still verify under the old unkeyed hash; the rest keep reporting
+/// their breaks. Only the migrations may call this.
+pub fn seal_history_with_key(conn: &Connection) -> Result<usize, AuditError> {
+    if !create_key(&key_path(conn)?)? {
+        return Ok(0);
+    }
+
+    let budget_ids: Vec<i64> = {
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT budget_id FROM BudgetChangeHistory ORDER BY budget_id")
+            .map_err(|e| AuditError::Db(e.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| AuditError::Db(e.to_string()))?;
+
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| AuditError::Db(e.to_string()))?);
+        }
+        ids
+    };
+
+    let mut resealed = 0;
+    for budget_id in budget_ids {
+        if !check_chain(conn, budget_id, None)?.valid {
+            continue;
+        }
+
+        let mut stmt = conn
+            .prepare("SELECT change_id FROM BudgetChangeHistory WHERE budget_id = ?1 ORDER BY change_id")
+            .map_err(|e| AuditError::Db(e.to_string()))?;
+        let change_ids = stmt
+            .query_map([budget_id], |row| row.get::<_, i64>(0))
+            .map_err(|e| AuditError::Db(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AuditError::Db(e.to_string()))?;
+        for change_id in change_ids {
+            seal_history_row(conn, change_id)?;
+            resealed += 1;
+        }
+    }
+    Ok(resealed)
+}
+
+/// Walks a budget's history in order and reports every row whose link or hash does not match.
+pub fn verify_chain(conn: &Connection, budget_id: i64) -> Result<ChainReport, AuditError> {
+    let key = load_key(conn)?;
+    check_chain(conn, budget_id, Some(&key))
+}
+
+fn check_chain(conn: &Connection, budget_id: i64, key: Option<&[u8]>) -> Result<ChainReport, AuditError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM BudgetChangeHistory WHERE budget_id = ?1 ORDER BY change_id",
+            HISTORY_COLUMNS
+        ))
+        .map_err(|e| AuditError::Db(e.to_string()))?;
+    let rows = stmt
+        .query_map([budget_id], read_row)
+        .map_err(|e| AuditError::Db(e.to_string()))?;
+
+    let mut breaks = Vec::new();
+    let mut rows_checked = 0;
+    let mut expected_prev = GENESIS_HASH.to_string();
+
+    for row in rows {
+        let row = row.map_err(|e| AuditError::Db(e.to_string()))?;
+        rows_checked += 1;
+
+        match (&row.prev_hash, &row.row_hash) {
+            (Some(prev_hash), Some(row_hash)) => {
+                if *prev_hash != expected_prev {
+                    breaks.push(ChainBreak {
+                        change_id: row.change_id,
+                        reason: "Does not link to the previous row; a row before it was removed or altered".to_string(),
+                    });
+                }
+                if compute_hash(&row, prev_hash, key) != *row_hash {
+                    breaks.push(ChainBreak {
+                        change_id: row.change_id,
+                        reason: "Contents do not match the stored hash".to_string(),
+                    });
+                }
+                expected_prev = row_hash.clone();
+            }
+            _ => {
+                breaks.push(ChainBreak {
+                    change_id: row.change_id,
+                    reason: "Row has no hash".to_string(),
+                });
+                expected_prev = row.row_hash.clone().unwrap_or_default();
+            }
+        }
+    }
+
+    Ok(ChainReport {
+        budget_id,
+        rows_checked,
+        valid: breaks.is_empty(),
+        breaks,
+    })
+}
@@ -1,4 +1,18 @@
 // Placeholder for SQLCipher/AES-256 integration in a later iteration.
 // For now, the DB is unencrypted to bootstrap app features.
 
+// Files stored next to the database (attachments) go through these two functions so that
+// they pick up the database key once it exists. Until then they are stored as-is and
+// recorded with this scheme, so the files that still need encrypting can be found later.
+pub const FILE_ENCRYPTION_SCHEME: &str = "none";
 
+pub fn seal_file(plain: Vec<u8>) -> Vec<u8> {
+    plain
+}
+
+pub fn open_file(sealed: Vec<u8>, scheme: &str) -> Result<Vec<u8>, String> {
+    match scheme {
+        "none" => Ok(sealed),
+        other => Err(format!("Unsupported file encryption scheme: {}", other)),
+    }
+}
@@ -0,0 +1,463 @@
+// Safe expression evaluator for formula-based category allocations.
+//
+// Supported syntax:
+//   numbers        200, 12.5, 10% (= 0.1)
+//   income         the budget's total_income
+//   [Category]     the allocated amount of another category in the same budget
+//   operators      + - * / and parentheses
+//   functions      min(a, b, ...), max(a, b, ...), abs(x), round(x[, digits]), floor(x), ceil(x)
+//
+// Expressions are parsed into a tree and evaluated without any access to SQL or the host.
+
+use thiserror::Error;
+
+const MAX_FORMULA_LENGTH: usize = 500;
+const MAX_NESTING_DEPTH: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum FormulaError {
+    #[error("formula is empty")]
+    Empty,
+    #[error("formula is longer than {0} characters")]
+    TooLong(usize),
+    #[error("formula is nested too deeply")]
+    TooDeep,
+    #[error("unexpected character '{0}' at position {1}")]
+    UnexpectedChar(char, usize),
+    #[error("unexpected {0}")]
+    UnexpectedToken(String),
+    #[error("unknown name '{0}'")]
+    UnknownName(String),
+    #[error("unknown category [{0}]")]
+    UnknownCategory(String),
+    #[error("{0}() expects {1}")]
+    BadArity(String, &'static str),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("result is not a finite number")]
+    NotFinite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Income,
+    Category(String),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Category(String),
+    Percent,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Comma,
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(n) => format!("number {}", n),
+            Token::Ident(name) => format!("name '{}'", name),
+            Token::Category(name) => format!("category [{}]", name),
+            Token::Percent => "'%'".to_string(),
+            Token::Plus => "'+'".to_string(),
+            Token::Minus => "'-'".to_string(),
+            Token::Star => "'*'".to_string(),
+            Token::Slash => "'/'".to_string(),
+            Token::Comma => "','".to_string(),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, FormulaError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' | '\n' | '\r' => i += 1,
+            '+' => { tokens.push(Token::Plus); i += 1; }
+            '-' => { tokens.push(Token::Minus); i += 1; }
+            '*' => { tokens.push(Token::Star); i += 1; }
+            '/' => { tokens.push(Token::Slash); i += 1; }
+            '%' => { tokens.push(Token::Percent); i += 1; }
+            ',' => { tokens.push(Token::Comma); i += 1; }
+            '(' => { tokens.push(Token::LParen); i += 1; }
+            ')' => { tokens.push(Token::RParen); i += 1; }
+            '[' => {
+                let start = i + 1;
+                let end = chars[start..]
+                    .iter()
+                    .position(|&ch| ch == ']')
+                    .map(|offset| start + offset)
+                    .ok_or(FormulaError::UnexpectedChar('[', i))?;
+                let name: String = chars[start..end].iter().collect();
+                tokens.push(Token::Category(name.trim().to_string()));
+                i = end + 1;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value = text
+                    .parse::<f64>()
+                    .map_err(|_| FormulaError::UnexpectedToken(format!("number '{}'", text)))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect::<String>().to_lowercase()));
+            }
+            other => return Err(FormulaError::UnexpectedChar(other, i)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), FormulaError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(FormulaError::UnexpectedToken(token.describe())),
+            None => Err(FormulaError::UnexpectedToken("end of formula".to_string())),
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), FormulaError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            return Err(FormulaError::TooDeep);
+        }
+        Ok(())
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, FormulaError> {
+        self.enter()?;
+        let mut left = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => Op::Add,
+                Some(Token::Minus) => Op::Sub,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.term()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        self.depth -= 1;
+        Ok(left)
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => Op::Mul,
+                Some(Token::Slash) => Op::Div,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.unary()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    // unary := '-' unary | '+' unary | postfix
+    fn unary(&mut self) -> Result<Expr, FormulaError> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                self.pos += 1;
+                self.enter()?;
+                let inner = self.unary()?;
+                self.depth -= 1;
+                Ok(Expr::Neg(Box::new(inner)))
+            }
+            Some(Token::Plus) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    // postfix := primary '%'?
+    fn postfix(&mut self) -> Result<Expr, FormulaError> {
+        let value = self.primary()?;
+        if let Some(Token::Percent) = self.peek() {
+            self.pos += 1;
+            return Ok(Expr::Binary(Op::Div, Box::new(value), Box::new(Expr::Number(100.0))));
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> Result<Expr, FormulaError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Category(name)) => Ok(Expr::Category(name)),
+            Some(Token::LParen) => {
+                let inner = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) => {
+                if let Some(Token::LParen) = self.peek() {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if let Some(Token::RParen) = self.peek() {
+                        self.pos += 1;
+                    } else {
+                        loop {
+                            args.push(self.expr()?);
+                            match self.next() {
+                                Some(Token::Comma) => continue,
+                                Some(Token::RParen) => break,
+                                Some(token) => return Err(FormulaError::UnexpectedToken(token.describe())),
+                                None => return Err(FormulaError::UnexpectedToken("end of formula".to_string())),
+                            }
+                        }
+                    }
+                    check_arity(&name, args.len())?;
+                    Ok(Expr::Call(name, args))
+                } else if name == "income" {
+                    Ok(Expr::Income)
+                } else {
+                    Err(FormulaError::UnknownName(name))
+                }
+            }
+            Some(token) => Err(FormulaError::UnexpectedToken(token.describe())),
+            None => Err(FormulaError::UnexpectedToken("end of formula".to_string())),
+        }
+    }
+}
+
+fn check_arity(name: &str, count: usize) -> Result<(), FormulaError> {
+    let ok = match name {
+        "min" | "max" => count >= 1,
+        "abs" | "floor" | "ceil" => count == 1,
+        "round" => count == 1 || count == 2,
+        _ => return Err(FormulaError::UnknownName(name.to_string())),
+    };
+    if ok {
+        return Ok(());
+    }
+    let expected = match name {
+        "min" | "max" => "at least one argument",
+        "round" => "one or two arguments",
+        _ => "exactly one argument",
+    };
+    Err(FormulaError::BadArity(name.to_string(), expected))
+}
+
+pub fn parse(src: &str) -> Result<Expr, FormulaError> {
+    if src.chars().count() > MAX_FORMULA_LENGTH {
+        return Err(FormulaError::TooLong(MAX_FORMULA_LENGTH));
+    }
+    let tokens = tokenize(src)?;
+    if tokens.is_empty() {
+        return Err(FormulaError::Empty);
+    }
+
+    let mut parser = Parser { tokens, pos: 0, depth: 0 };
+    let expr = parser.expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(FormulaError::UnexpectedToken(token.describe()));
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /// Names of all categories referenced with `[Name]`.
+    pub fn category_refs(&self) -> Vec<String> {
+        let mut refs = Vec::new();
+        self.collect_refs(&mut refs);
+        refs
+    }
+
+    fn collect_refs(&self, refs: &mut Vec<String>) {
+        match self {
+            Expr::Category(name) => {
+                if !refs.contains(name) {
+                    refs.push(name.clone());
+                }
+            }
+            Expr::Neg(inner) => inner.collect_refs(refs),
+            Expr::Binary(_, left, right) => {
+                left.collect_refs(refs);
+                right.collect_refs(refs);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.collect_refs(refs)),
+            Expr::Number(_) | Expr::Income => {}
+        }
+    }
+
+    /// Evaluates the expression; `category` resolves `[Name]` references.
+    pub fn evaluate<F>(&self, income: f64, category: &F) -> Result<f64, FormulaError>
+    where
+        F: Fn(&str) -> Option<f64>,
+    {
+        let value = match self {
+            Expr::Number(n) => *n,
+            Expr::Income => income,
+            Expr::Category(name) => category(name).ok_or_else(|| FormulaError::UnknownCategory(name.clone()))?,
+            Expr::Neg(inner) => -inner.evaluate(income, category)?,
+            Expr::Binary(op, left, right) => {
+                let l = left.evaluate(income, category)?;
+                let r = right.evaluate(income, category)?;
+                match op {
+                    Op::Add => l + r,
+                    Op::Sub => l - r,
+                    Op::Mul => l * r,
+                    Op::Div => {
+                        if r == 0.0 {
+                            return Err(FormulaError::DivisionByZero);
+                        }
+                        l / r
+                    }
+                }
+            }
+            Expr::Call(name, args) => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.evaluate(income, category))
+                    .collect::<Result<Vec<f64>, FormulaError>>()?;
+                match name.as_str() {
+                    "min" => values.iter().copied().fold(f64::INFINITY, f64::min),
+                    "max" => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                    "abs" => values[0].abs(),
+                    "floor" => values[0].floor(),
+                    "ceil" => values[0].ceil(),
+                    "round" => {
+                        let factor = 10f64.powi(values.get(1).copied().unwrap_or(0.0).clamp(0.0, 6.0) as i32);
+                        (values[0] * factor).round() / factor
+                    }
+                    other => return Err(FormulaError::UnknownName(other.to_string())),
+                }
+            }
+        };
+
+        if !value.is_finite() {
+            return Err(FormulaError::NotFinite);
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(src: &str) -> Result<f64, FormulaError> {
+        let categories = |name: &str| match name {
+            "Rent" => Some(1200.0),
+            "Food" => Some(400.0),
+            _ => None,
+        };
+        parse(src)?.evaluate(3000.0, &categories)
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        assert_eq!(eval("2 + 3 * 4").unwrap(), 14.0);
+        assert_eq!(eval("(2 + 3) * 4").unwrap(), 20.0);
+        assert_eq!(eval("10 - 4 - 3").unwrap(), 3.0);
+        assert_eq!(eval("12 / 3 / 2").unwrap(), 2.0);
+        assert_eq!(eval("-2 * 3 + 10").unwrap(), 4.0);
+    }
+
+    #[test]
+    fn percent_divides_the_preceding_value_by_100() {
+        assert_eq!(eval("10%").unwrap(), 0.1);
+        assert_eq!(eval("income * 10%").unwrap(), 300.0);
+        assert_eq!(eval("(income - [Rent]) * 50%").unwrap(), 900.0);
+        assert!(matches!(parse("%"), Err(FormulaError::UnexpectedToken(_))));
+        assert!(matches!(parse("10%%"), Err(FormulaError::UnexpectedToken(_))));
+    }
+
+    #[test]
+    fn functions_and_names() {
+        assert_eq!(eval("min([Rent], [Food], 500)").unwrap(), 400.0);
+        assert_eq!(eval("MAX(income / 10, 250)").unwrap(), 300.0);
+        assert_eq!(eval("round(10 / 3, 2)").unwrap(), 3.33);
+        assert!(matches!(parse("abs(1, 2)"), Err(FormulaError::BadArity(_, _))));
+        assert!(matches!(parse("sqrt(4)"), Err(FormulaError::UnknownName(_))));
+        assert!(matches!(parse("salary * 2"), Err(FormulaError::UnknownName(_))));
+        assert!(matches!(eval("[Travel] + 1"), Err(FormulaError::UnknownCategory(name)) if name == "Travel"));
+    }
+
+    #[test]
+    fn division_by_zero_is_an_error() {
+        assert!(matches!(eval("income / 0"), Err(FormulaError::DivisionByZero)));
+        assert!(matches!(eval("[Rent] / ([Food] - 400)"), Err(FormulaError::DivisionByZero)));
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(MAX_NESTING_DEPTH - 1)).unwrap(), 1.0);
+        assert!(matches!(parse(&nested(MAX_NESTING_DEPTH)), Err(FormulaError::TooDeep)));
+        assert!(matches!(parse(&format!("{}1", "-".repeat(MAX_NESTING_DEPTH + 1))), Err(FormulaError::TooDeep)));
+    }
+
+    #[test]
+    fn length_is_limited() {
+        let longest = format!("1{}", " ".repeat(MAX_FORMULA_LENGTH - 1));
+        assert_eq!(eval(&longest).unwrap(), 1.0);
+        assert!(matches!(parse(&format!("{} ", longest)), Err(FormulaError::TooLong(MAX_FORMULA_LENGTH))));
+        assert!(matches!(parse("   "), Err(FormulaError::Empty)));
+    }
+
+    #[test]
+    fn category_refs_are_trimmed_and_listed_once() {
+        let expr = parse("[ Rent ] + max([Food], [Rent] * 10%) - -[Savings]").unwrap();
+        assert_eq!(expr.category_refs(), vec!["Rent", "Food", "Savings"]);
+        assert!(parse("income * 2").unwrap().category_refs().is_empty());
+        assert!(matches!(parse("[Rent"), Err(FormulaError::UnexpectedChar('[', 0))));
+    }
+}
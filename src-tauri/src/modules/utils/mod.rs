@@ -1,3 +1,3 @@
-// Placeholder for helpers such as formula parser in later iterations
-
-
+pub mod formula;
+pub mod notify;
+pub mod pdf;
@@ -0,0 +1,14 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+/// Tells the frontend through an event and the user through a native desktop notification.
+/// Failures are only logged: the change that caused the notice has already been saved.
+pub fn notify<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: &S, title: &str, body: &str) {
+    if let Err(e) = app.emit(event, payload.clone()) {
+        println!("Warning: failed to emit {} event: {}", event, e);
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        println!("Warning: failed to show notification '{}': {}", title, e);
+    }
+}
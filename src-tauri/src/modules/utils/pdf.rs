@@ -0,0 +1,106 @@
+// Minimal PDF writer for plain-text reports.
+//
+// Produces A4 pages of lines: headings in Helvetica-Bold, everything else in Courier so that
+// columns padded with spaces line up. Only the built-in PDF fonts are used, so nothing needs
+// embedding; characters outside Latin-1 are printed as '?'.
+
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 50.0;
+const LINE_HEIGHT: f64 = 13.0;
+const HEADING_SIZE: f64 = 13.0;
+const TEXT_SIZE: f64 = 9.0;
+
+pub enum PdfLine {
+    Heading(String),
+    Text(String),
+    Blank,
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            ' '..='~' => out.push(ch),
+            // Latin-1 maps onto WinAnsiEncoding; write it as an octal escape
+            '\u{a0}'..='\u{ff}' => out.push_str(&format!("\\{:03o}", ch as u32)),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+fn page_content(lines: &[PdfLine]) -> String {
+    let mut content = String::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    for line in lines {
+        match line {
+            PdfLine::Heading(text) => {
+                content.push_str(&format!(
+                    "BT /F2 {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+                    HEADING_SIZE, MARGIN, y, escape_text(text)
+                ));
+            }
+            PdfLine::Text(text) => {
+                content.push_str(&format!(
+                    "BT /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+                    TEXT_SIZE, MARGIN, y, escape_text(text)
+                ));
+            }
+            PdfLine::Blank => {}
+        }
+        y -= LINE_HEIGHT;
+    }
+    content
+}
+
+/// Lays the lines out over as many pages as needed and returns the PDF file.
+pub fn render_lines(lines: &[PdfLine]) -> Vec<u8> {
+    let per_page = ((PAGE_HEIGHT - 2.0 * MARGIN) / LINE_HEIGHT).floor() as usize;
+    let pages: Vec<&[PdfLine]> = if lines.is_empty() { vec![lines] } else { lines.chunks(per_page).collect() };
+
+    // Objects 1-4 are the catalog, page tree and two fonts; each page adds itself and its content
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+
+    let mut kids = Vec::new();
+    for page in &pages {
+        let page_id = objects.len() + 1;
+        let content = page_content(page);
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, page_id + 1
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+        kids.push(format!("{} 0 R", page_id));
+    }
+    objects[1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len());
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.push_str(&format!("{} 0 obj\n{}\nendobj\n", index + 1, object));
+    }
+
+    let xref_at = out.len();
+    out.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        out.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    out.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_at
+    ));
+
+    out.into_bytes()
+}